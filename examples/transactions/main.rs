@@ -20,8 +20,15 @@ pub enum Instrument {
     Stock(Stock),
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+impl Default for Instrument {
+    fn default() -> Self {
+        Instrument::Cash(Currency::EUR)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Operation {
+    #[default]
     Buy,
     Sell,
     Dividend,
@@ -29,7 +36,7 @@ pub enum Operation {
     Withdrawal,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, FromRow, Entity)]
+#[derive(Clone, Debug, Eq, PartialEq, FromRow, Entity, Factory)]
 pub struct Transaction {
     #[postgres(DATE NOT NULL)]
     pub date: NaiveDate,
@@ -56,6 +63,7 @@ pub struct Transaction {
 
     #[sqlx(json)]
     #[postgres(JSONB NOT NULL)]
+    #[factory(value = Currency::EUR)]
     pub currency: Currency,
 
     #[postgres(DECIMAL NOT NULL)]
@@ -116,5 +124,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Err(sqlx::Error::RowNotFound)
     ));
 
+    println!("Creating random transactions...");
+    let mut deposits = Transaction::factory().with(|tx| tx.operation = Operation::Deposit);
+    let created = deposits.create_many(&store, 3).await?;
+    assert_eq!(store.count().await?, 3);
+    assert!(created.iter().all(|tx| tx.operation == Operation::Deposit));
+
     Ok(())
 }
@@ -2,7 +2,8 @@ use darling::FromField;
 use quote::quote;
 use std::collections::HashMap;
 use std::string::ToString;
-use syn::{Field, Ident, Meta};
+use strum::IntoEnumIterator;
+use syn::{Field, GenericArgument, Ident, Meta, PathArguments, Type};
 
 use crate::database::Database;
 
@@ -10,6 +11,7 @@ use crate::database::Database;
 #[darling(attributes(sqlx))]
 struct InnerColumn {
     ident: Option<Ident>,
+    ty: Type,
     #[darling(skip)]
     schema: HashMap<Database, String>,
     rename: Option<String>,
@@ -91,4 +93,118 @@ impl Column {
     pub fn skip(&self) -> bool {
         self.0.skip
     }
+
+    pub fn json(&self) -> bool {
+        self.0.json
+    }
+
+    /// Returns the declaration of the column for the first backend
+    /// it supports, if any.
+    pub fn any_schema(&self) -> Option<&String> {
+        Database::iter().find_map(|db| self.0.schema.get(&db))
+    }
+
+    /// Returns the JSON schema of the serialized field, from its type or, for
+    /// the types that are not known here, the type of its column in `db`, along
+    /// with whether the field is required, i.e. is not an `Option`.
+    pub fn json_schema(&self, db: &Database) -> (String, bool) {
+        let inner = option_inner(&self.0.ty);
+        let required = inner.is_none();
+        let ty = inner.unwrap_or(&self.0.ty);
+        let schema = if self.0.json {
+            // left unconstrained
+            None
+        } else {
+            rust_type_schema(ty).or_else(|| sql_type_schema(self.schema_for_db(db)))
+        };
+        let schema = match schema {
+            None => "{}".to_string(),
+            Some((ty, format)) => {
+                let ty = if required {
+                    format!("\"{ty}\"")
+                } else {
+                    format!("[\"{ty}\",\"null\"]")
+                };
+                match format {
+                    Some(format) => format!("{{\"type\":{ty},\"format\":\"{format}\"}}"),
+                    None => format!("{{\"type\":{ty}}}"),
+                }
+            }
+        };
+        (schema, required)
+    }
+}
+
+/// JSON schema type of a field, along with its format, if any.
+type JsonType = (&'static str, Option<&'static str>);
+
+/// Returns `T` if `ty` is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) if segment.ident == "Option" => Some(inner),
+        _ => None,
+    }
+}
+
+/// Returns the JSON schema type of the usual Rust types.
+fn rust_type_schema(ty: &Type) -> Option<JsonType> {
+    let ty = match ty {
+        Type::Reference(reference) => &reference.elem,
+        ty => ty,
+    };
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let schema = match path.path.segments.last()?.ident.to_string().as_str() {
+        "bool" => ("boolean", None),
+        "i8" | "i16" | "i32" | "u8" | "u16" => ("integer", Some("int32")),
+        "i64" | "u32" | "u64" | "isize" | "usize" => ("integer", Some("int64")),
+        "f32" => ("number", Some("float")),
+        "f64" => ("number", Some("double")),
+        "String" | "str" | "char" => ("string", None),
+        "NaiveDate" | "Date" => ("string", Some("date")),
+        "NaiveTime" | "Time" => ("string", Some("time")),
+        "NaiveDateTime" | "DateTime" | "OffsetDateTime" | "PrimitiveDateTime" => {
+            ("string", Some("date-time"))
+        }
+        "Uuid" => ("string", Some("uuid")),
+        _ => return None,
+    };
+    Some(schema)
+}
+
+/// Returns the JSON schema type of a column given its SQL declaration,
+/// whose first word is its type.
+fn sql_type_schema(declaration: &str) -> Option<JsonType> {
+    let declaration = declaration.to_uppercase();
+    let ty = declaration
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default();
+    let schema = match ty {
+        "BOOL" | "BOOLEAN" => ("boolean", None),
+        "TINYINT" if declaration.replace(' ', "").starts_with("TINYINT(1)") => ("boolean", None),
+        "SMALLINT" | "INT2" | "TINYINT" | "MEDIUMINT" | "INT" | "INT4" | "INTEGER" => {
+            ("integer", Some("int32"))
+        }
+        "BIGINT" | "INT8" | "SERIAL" | "BIGSERIAL" => ("integer", Some("int64")),
+        "REAL" | "FLOAT" | "FLOAT4" => ("number", Some("float")),
+        "DOUBLE" | "FLOAT8" => ("number", Some("double")),
+        "DECIMAL" | "NUMERIC" => ("number", None),
+        "DATE" => ("string", Some("date")),
+        "TIME" => ("string", Some("time")),
+        "TIMESTAMP" | "TIMESTAMPTZ" | "DATETIME" => ("string", Some("date-time")),
+        "UUID" => ("string", Some("uuid")),
+        "TEXT" | "VARCHAR" | "CHAR" | "CHARACTER" | "CITEXT" => ("string", None),
+        // JSON, arrays, blobs, ... are left unconstrained
+        _ => return None,
+    };
+    Some(schema)
 }
@@ -30,6 +30,7 @@ impl SchemaArgs {
 
     pub fn generate_schema_impl(&self, db: &Database) -> proc_macro2::TokenStream {
         let ident = &self.ident;
+        let entity = self.ident.to_string();
         let table = self.table_name();
         let cols = self.columns().map(|col| col.name()).join(",");
         let col_name = self.columns().map(|col| col.name());
@@ -82,6 +83,26 @@ impl SchemaArgs {
         let delete = format!("DELETE FROM {table} WHERE id={}", db.placeholder(1));
         let delete_all = format!("DELETE FROM {table}");
 
+        // JSON schema
+        let json_schema = {
+            let columns = self
+                .columns()
+                .map(|col| (col.name(), col.json_schema(db)))
+                .collect::<Vec<_>>();
+            let properties = columns
+                .iter()
+                .map(|(name, (schema, _))| format!("\"{name}\":{schema}"))
+                .join(",");
+            let required = columns
+                .iter()
+                .filter(|(_, (_, required))| *required)
+                .map(|(name, _)| format!("\"{name}\""))
+                .join(",");
+            format!(
+                "{{\"type\":\"object\",\"properties\":{{{properties}}},\"required\":[{required}]}}"
+            )
+        };
+
         let db = db.to_token_stream();
         quote! {
             impl ::miniorm::prelude::Schema<#db> for #ident {
@@ -95,6 +116,8 @@ impl SchemaArgs {
                 const MINIORM_DELETE: &'static str = #delete;
                 const MINIORM_DELETE_ALL: &'static str = #delete_all;
                 const MINIORM_TABLE_NAME: &'static str = #table;
+                const MINIORM_ENTITY_NAME: &'static str = #entity;
+                const MINIORM_JSON_SCHEMA: &'static str = #json_schema;
                 const MINIORM_COLUMNS: &'static [&'static str] = &[
                     #(#col_name,)*
                 ];
@@ -0,0 +1,83 @@
+use darling::{ast::Data, FromDeriveInput, FromField};
+use quote::quote;
+use syn::{Expr, Field, Ident};
+
+use crate::column::Column;
+
+#[derive(Clone, Debug, FromField)]
+#[darling(attributes(factory))]
+struct FactoryAttrs {
+    sequence: Option<String>,
+    value: Option<Expr>,
+}
+
+pub struct FactoryField {
+    column: Column,
+    attrs: FactoryAttrs,
+}
+
+impl FromField for FactoryField {
+    fn from_field(field: &Field) -> darling::Result<Self> {
+        let column = Column::from_field(field)?;
+        let attrs = FactoryAttrs::from_field(field)?;
+        if attrs.sequence.is_some() && attrs.value.is_some() {
+            return Err(darling::Error::custom(
+                "#[factory(...)] cannot have both a sequence and a value",
+            ));
+        }
+        Ok(Self { column, attrs })
+    }
+}
+
+impl FactoryField {
+    fn value(&self) -> proc_macro2::TokenStream {
+        let column = &self.column;
+        if let Some(sequence) = &self.attrs.sequence {
+            quote!(::std::format!(#sequence, n = n).into())
+        } else if let Some(value) = &self.attrs.value {
+            quote!(#value)
+        } else if column.skip() || column.json() {
+            quote!(::std::default::Default::default())
+        } else {
+            let name = column.name();
+            let declaration = column.any_schema().map(String::as_str).unwrap_or_default();
+            quote! {
+                ::miniorm::factory::Fake::fake(
+                    faker,
+                    &::miniorm::factory::Column::new(#name, #declaration),
+                )
+            }
+        }
+    }
+}
+
+#[derive(FromDeriveInput)]
+#[darling(supports(struct_named))]
+pub struct FactoryArgs {
+    ident: Ident,
+    data: Data<(), FactoryField>,
+}
+
+impl FactoryArgs {
+    pub fn generate_factory_impl(&self) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        let fields = match &self.data {
+            Data::Enum(_) => unreachable!(),
+            Data::Struct(fields) => &fields.fields,
+        };
+        let field_ident = fields.iter().map(|field| field.column.ident());
+        let field_value = fields.iter().map(|field| field.value());
+
+        quote! {
+            impl ::miniorm::factory::Factory for #ident {
+                #[allow(unused_variables)]
+                fn fake(faker: &mut ::miniorm::factory::Faker) -> Self {
+                    let n = faker.sequence();
+                    Self {
+                        #(#field_ident: #field_value,)*
+                    }
+                }
+            }
+        }
+    }
+}
@@ -8,10 +8,12 @@
 mod column;
 mod database;
 mod entity;
+mod factory;
 
 use darling::FromDeriveInput;
 use database::Database;
 use entity::SchemaArgs;
+use factory::FactoryArgs;
 use proc_macro::TokenStream;
 use quote::quote;
 use strum::IntoEnumIterator;
@@ -120,3 +122,50 @@ pub fn derive_entity(input: TokenStream) -> TokenStream {
 
     result.into()
 }
+
+/// Derive macro to automatically derive the `Factory` trait, building random
+/// instances of an entity for tests.
+///
+/// Each field is generated according to its type and to the declaration of its
+/// column (see the `factory` module of `miniorm`), the fields stored as JSON
+/// with `#[sqlx(json)]` getting their `Default` value, unless its value is given by
+/// the `factory` directive, either as a `sequence`, i.e. a format string in which
+/// `{n}` is the number of the entity, starting from 1, or as a `value`, i.e. an
+/// expression in which `n` can be used as well:
+///
+/// ```rust
+/// use miniorm::prelude::*;
+/// use sqlx::FromRow;
+///
+/// #[derive(Debug, Clone, Eq, PartialEq, FromRow, Entity, Factory)]
+/// struct Todo {
+///     #[column(TEXT NOT NULL)]
+///     #[factory(sequence = "todo #{n}")]
+///     description: String,
+///
+///     #[column(BOOLEAN NOT NULL DEFAULT false)]
+///     #[factory(value = n % 2 == 0)]
+///     done: bool,
+/// }
+///
+/// let mut todos = Todo::factory();
+/// let todo = todos.build();
+/// assert_eq!(todo.description, "todo #1");
+/// assert!(!todo.done);
+/// assert!(todos.build().done);
+/// ```
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">factory</span> feature flag.
+///         </td>
+///     </tr>
+/// </table>
+#[proc_macro_derive(Factory, attributes(factory))]
+pub fn derive_factory(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse(input).unwrap();
+    match FactoryArgs::from_derive_input(&input) {
+        Ok(args) => args.generate_factory_impl().into(),
+        Err(err) => err.write_errors().into(),
+    }
+}
@@ -87,3 +87,38 @@ mod columns {
         assert_eq!(columns[1], "z");
     }
 }
+
+mod json_schema {
+    use super::*;
+
+    #[test]
+    fn types_and_nullability() {
+        #[derive(Entity)]
+        struct Todo {
+            #[sqlite(TEXT CHECK (description IS NOT NULL))]
+            description: Option<String>,
+            #[sqlite(BOOLEAN DEFAULT false)]
+            done: bool,
+            #[sqlite(INTEGER NOT NULL)]
+            #[sqlx(rename = "prio")]
+            priority: i32,
+            #[sqlite(TEXT NOT NULL)]
+            tag: Vec<u8>,
+            #[sqlite(BLOB)]
+            data: Option<Vec<u8>>,
+        }
+
+        assert_eq!(
+            <Todo as Schema<Sqlite>>::MINIORM_JSON_SCHEMA,
+            concat!(
+                r#"{"type":"object","properties":{"#,
+                r#""description":{"type":["string","null"]},"#,
+                r#""done":{"type":"boolean"},"#,
+                r#""prio":{"type":"integer","format":"int32"},"#,
+                r#""tag":{"type":"string"},"#,
+                r#""data":{}},"#,
+                r#""required":["done","prio","tag"]}"#
+            )
+        );
+    }
+}
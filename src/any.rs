@@ -0,0 +1,323 @@
+use crate::{
+    prelude::{Batch, Create, Delete, Read, Table, Update},
+    Store, WithId,
+};
+use async_trait::async_trait;
+use futures_core::stream::BoxStream;
+
+/// A [`Store`] on a database whose backend is only known at runtime, e.g.
+/// when it is selected by a connection URL.
+///
+/// All the operations are dispatched to the [`Store`] of the corresponding
+/// backend, which runs the SQL queries generated for that backend, so that
+/// the entity should implement the traits required by the stores of all
+/// the enabled backends (see [`AnyEntity`]), e.g. by deriving
+/// [`Entity`](crate::Entity) using the `column` directive.
+///
+/// # Example
+///
+/// ```no_run
+/// use miniorm::{prelude::*, AnyStore};
+/// use sqlx::FromRow;
+///
+/// #[derive(Debug, Clone, FromRow, Entity)]
+/// struct Todo {
+///     #[column(TEXT NOT NULL)]
+///     description: String,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let url = std::env::var("DATABASE_URL")?;
+/// let todos = AnyStore::<Todo>::connect(&url).await?;
+/// todos.create_table().await?;
+/// let todo = todos.create(Todo { description: "checkout miniorm".into() }).await?;
+/// # Ok(())
+/// # }
+/// ```
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         This example requires the <span style="color:blue">full</span> feature flag.
+///         </td>
+///     </tr>
+/// </table>
+pub enum AnyStore<E> {
+    /// A store on a PostgreSQL database.
+    #[cfg(feature = "postgres")]
+    Postgres(Store<sqlx::Postgres, E>),
+    /// A store on a SQLite database.
+    #[cfg(feature = "sqlite")]
+    Sqlite(Store<sqlx::Sqlite, E>),
+    /// A store on a MySQL database.
+    #[cfg(feature = "mysql")]
+    MySql(Store<sqlx::MySql, E>),
+}
+
+/// Runs `$body` on the [`Store`] of the backend of an [`AnyStore`].
+macro_rules! dispatch {
+    ($any: expr, $store: ident => $body: expr) => {
+        match $any {
+            #[cfg(feature = "postgres")]
+            AnyStore::Postgres($store) => $body,
+            #[cfg(feature = "sqlite")]
+            AnyStore::Sqlite($store) => $body,
+            #[cfg(feature = "mysql")]
+            AnyStore::MySql($store) => $body,
+        }
+    };
+}
+
+impl<E> AnyStore<E> {
+    /// Connects to the database at `url` and returns a store on it, the backend
+    /// being selected by the scheme of the url: `postgres://` or `postgresql://`,
+    /// `sqlite:`, `mysql://` or `mariadb://`.
+    ///
+    /// Fails with [`sqlx::Error::Configuration`] if the backend of the url is
+    /// unknown or its feature flag is not enabled.
+    pub async fn connect(url: &str) -> sqlx::Result<Self> {
+        let scheme = url.split(':').next().unwrap_or_default();
+        match scheme {
+            #[cfg(feature = "postgres")]
+            "postgres" | "postgresql" => Ok(Store::new(sqlx::PgPool::connect(url).await?).into()),
+            #[cfg(feature = "sqlite")]
+            "sqlite" => Ok(Store::new(sqlx::SqlitePool::connect(url).await?).into()),
+            #[cfg(feature = "mysql")]
+            "mysql" | "mariadb" => Ok(Store::new(sqlx::MySqlPool::connect(url).await?).into()),
+            _ => {
+                let msg = format!("unsupported database '{scheme}'");
+                Err(sqlx::Error::Configuration(msg.into()))
+            }
+        }
+    }
+
+    /// Returns the name of the backend, as in [`sqlx::Database::NAME`].
+    pub fn backend(&self) -> &'static str {
+        use sqlx::Database;
+        match self {
+            #[cfg(feature = "postgres")]
+            AnyStore::Postgres(_) => sqlx::Postgres::NAME,
+            #[cfg(feature = "sqlite")]
+            AnyStore::Sqlite(_) => sqlx::Sqlite::NAME,
+            #[cfg(feature = "mysql")]
+            AnyStore::MySql(_) => sqlx::MySql::NAME,
+        }
+    }
+}
+
+impl<E: AnyEntity> AnyStore<E> {
+    /// Creates the table of the entity, as [`Table::create_table`].
+    pub async fn create_table(&self) -> sqlx::Result<()> {
+        dispatch!(self, store => store.create_table().await.map(|_| ()))
+    }
+
+    /// Drops the table of the entity, as [`Table::drop_table`].
+    pub async fn drop_table(&self) -> sqlx::Result<()> {
+        dispatch!(self, store => store.drop_table().await.map(|_| ()))
+    }
+
+    /// Drops and creates the table of the entity, as [`Table::recreate_table`].
+    pub async fn recreate_table(&self) -> sqlx::Result<()> {
+        dispatch!(self, store => store.recreate_table().await.map(|_| ()))
+    }
+}
+
+impl<E> Clone for AnyStore<E> {
+    fn clone(&self) -> Self {
+        match self {
+            #[cfg(feature = "postgres")]
+            AnyStore::Postgres(store) => AnyStore::Postgres(store.clone()),
+            #[cfg(feature = "sqlite")]
+            AnyStore::Sqlite(store) => AnyStore::Sqlite(store.clone()),
+            #[cfg(feature = "mysql")]
+            AnyStore::MySql(store) => AnyStore::MySql(store.clone()),
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<E> From<Store<sqlx::Postgres, E>> for AnyStore<E> {
+    fn from(store: Store<sqlx::Postgres, E>) -> Self {
+        AnyStore::Postgres(store)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<E> From<Store<sqlx::Sqlite, E>> for AnyStore<E> {
+    fn from(store: Store<sqlx::Sqlite, E>) -> Self {
+        AnyStore::Sqlite(store)
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl<E> From<Store<sqlx::MySql, E>> for AnyStore<E> {
+    fn from(store: Store<sqlx::MySql, E>) -> Self {
+        AnyStore::MySql(store)
+    }
+}
+
+/// Defines the trait implemented by the entities which can be stored in the
+/// [`Store`] of a backend, if its feature flag is enabled, and by all types
+/// otherwise.
+macro_rules! backend_entity {
+    ($name: ident, $feature: literal, $db: ty, $row: ty) => {
+        #[doc = concat!("Entity which can be stored in a [`Store`] on `", stringify!($db), "`,")]
+        #[doc = concat!("if the `", $feature, "` feature flag is enabled.")]
+        #[cfg(feature = $feature)]
+        pub trait $name:
+            crate::prelude::Schema<$db>
+            + crate::prelude::BindColumn<$db>
+            + for<'r> sqlx::FromRow<'r, $row>
+        {
+        }
+
+        #[cfg(feature = $feature)]
+        impl<E> $name for E where
+            E: crate::prelude::Schema<$db>
+                + crate::prelude::BindColumn<$db>
+                + for<'r> sqlx::FromRow<'r, $row>
+        {
+        }
+
+        #[doc = concat!("Entity which can be stored in a [`Store`] on `", stringify!($db), "`,")]
+        #[doc = concat!("if the `", $feature, "` feature flag is enabled.")]
+        #[cfg(not(feature = $feature))]
+        pub trait $name {}
+
+        #[cfg(not(feature = $feature))]
+        impl<E> $name for E {}
+    };
+}
+
+backend_entity!(
+    PostgresEntity,
+    "postgres",
+    sqlx::Postgres,
+    sqlx::postgres::PgRow
+);
+backend_entity!(
+    SqliteEntity,
+    "sqlite",
+    sqlx::Sqlite,
+    sqlx::sqlite::SqliteRow
+);
+backend_entity!(MySqlEntity, "mysql", sqlx::MySql, sqlx::mysql::MySqlRow);
+
+/// Entity which can be stored in an [`AnyStore`], i.e. in the [`Store`] of
+/// all the enabled backends.
+///
+/// This trait is implemented automatically.
+pub trait AnyEntity:
+    PostgresEntity + SqliteEntity + MySqlEntity + Send + Sync + Unpin + 'static
+{
+}
+
+impl<E> AnyEntity for E where
+    E: PostgresEntity + SqliteEntity + MySqlEntity + Send + Sync + Unpin + 'static
+{
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Crud
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<E: AnyEntity> Create<E> for AnyStore<E> {
+    async fn create(&self, entity: E) -> sqlx::Result<WithId<E>> {
+        dispatch!(self, store => store.create(entity).await)
+    }
+}
+
+#[async_trait]
+impl<E: AnyEntity> Read<E> for AnyStore<E> {
+    async fn read(&self, id: i64) -> sqlx::Result<WithId<E>> {
+        dispatch!(self, store => store.read(id).await)
+    }
+
+    async fn list(&self) -> sqlx::Result<Vec<WithId<E>>> {
+        dispatch!(self, store => store.list().await)
+    }
+
+    async fn count(&self) -> sqlx::Result<u64> {
+        dispatch!(self, store => store.count().await)
+    }
+
+    fn stream<'s>(&'s self) -> BoxStream<'s, sqlx::Result<WithId<E>>>
+    where
+        E: 's,
+    {
+        dispatch!(self, store => store.stream())
+    }
+
+    #[cfg(feature = "axum")]
+    async fn query(&self, query: &crate::ListQuery) -> sqlx::Result<crate::Page<E>>
+    where
+        Self: Sync,
+        E: serde::Serialize + Send + 'async_trait,
+    {
+        dispatch!(self, store => store.query(query).await)
+    }
+}
+
+#[async_trait]
+impl<E: AnyEntity> Update<E> for AnyStore<E> {
+    async fn update(&self, entity: WithId<E>) -> sqlx::Result<WithId<E>> {
+        dispatch!(self, store => store.update(entity).await)
+    }
+
+    async fn update_columns(
+        &self,
+        entity: WithId<E>,
+        columns: &[&'static str],
+    ) -> sqlx::Result<WithId<E>> {
+        dispatch!(self, store => store.update_columns(entity, columns).await)
+    }
+
+    async fn update_if<F>(
+        &self,
+        entity: WithId<E>,
+        columns: &[&'static str],
+        check: F,
+    ) -> sqlx::Result<Option<WithId<E>>>
+    where
+        Self: Read<E> + Sync,
+        E: Send + 'async_trait,
+        F: Fn(&WithId<E>) -> bool + Send + Sync + 'async_trait,
+    {
+        dispatch!(self, store => store.update_if(entity, columns, check).await)
+    }
+}
+
+#[async_trait]
+impl<E: AnyEntity> Delete<E> for AnyStore<E> {
+    async fn delete(&self, id: i64) -> sqlx::Result<()> {
+        dispatch!(self, store => store.delete(id).await)
+    }
+
+    async fn delete_all(&self) -> sqlx::Result<u64> {
+        dispatch!(self, store => store.delete_all().await)
+    }
+
+    async fn delete_if<F>(&self, id: i64, check: F) -> sqlx::Result<bool>
+    where
+        Self: Read<E> + Sync,
+        E: Send + 'async_trait,
+        F: Fn(&WithId<E>) -> bool + Send + Sync + 'async_trait,
+    {
+        dispatch!(self, store => store.delete_if(id, check).await)
+    }
+}
+
+#[async_trait]
+impl<E: AnyEntity> Batch<E> for AnyStore<E> {
+    async fn create_many(&self, entities: Vec<E>) -> sqlx::Result<Vec<WithId<E>>> {
+        dispatch!(self, store => store.create_many(entities).await)
+    }
+
+    async fn update_many(&self, entities: Vec<WithId<E>>) -> sqlx::Result<Vec<WithId<E>>> {
+        dispatch!(self, store => store.update_many(entities).await)
+    }
+
+    async fn delete_many(&self, ids: &[i64]) -> sqlx::Result<()> {
+        dispatch!(self, store => store.delete_many(ids).await)
+    }
+}
@@ -0,0 +1,541 @@
+use crate::{
+    prelude::{BindColumn, Create, Delete, Read, Schema, Table, Update},
+    traits::sqlx::{Dialect, RowsAffected, SupportsReturning},
+    Store, WithId,
+};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::{
+    database::HasArguments, ColumnIndex, Database, Decode, Encode, Executor, FromRow,
+    IntoArguments, Type,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An `AuditedStore` is a [`Store`] that keeps track of every change made
+/// to its entities in a companion `<table>_history` table.
+///
+/// Every `create`, `update` and `delete` writes a row in the history table,
+/// within the same transaction, recording:
+/// - the id of the entity and the kind of operation,
+/// - when the operation occurred and who performed it (see [`AuditedStore::with_actor`]),
+/// - the entity before and after the operation, serialized as JSON.
+///
+/// The entity is read with its row locked until the end of the transaction, so
+/// that concurrent changes are recorded one after the other. Just like those of
+/// the underlying [`Store`], the changes are instrumented, retried and sent to
+/// the primary database.
+///
+/// The history of an entity can then be retrieved using [`AuditedStore::history`].
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">audit</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+///
+/// # Example
+///
+/// ```
+/// use miniorm::{prelude::*, AuditOperation};
+/// use serde::{Deserialize, Serialize};
+/// use sqlx::FromRow;
+///
+/// #[derive(Debug, Clone, Eq, PartialEq, FromRow, Entity, Serialize, Deserialize)]
+/// struct Todo {
+///     #[sqlite(TEXT NOT NULL)]
+///     description: String,
+///
+///     #[sqlite(BOOLEAN NOT NULL DEFAULT false)]
+///     done: bool,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = sqlx::SqlitePool::connect(":memory:").await?;
+/// let store = Store::new(db).audited().with_actor("alice");
+/// store.recreate_table().await?;
+///
+/// let mut todo = store.create(Todo { description: "checkout miniorm".into(), done: false }).await?;
+/// todo.done = true;
+/// store.update(todo.clone()).await?;
+///
+/// let history = store.history(todo.id()).await?;
+/// assert_eq!(history.len(), 2);
+/// assert_eq!(history[1].operation, AuditOperation::Update);
+/// assert_eq!(history[1].actor.as_deref(), Some("alice"));
+/// assert_eq!(history[1].after.as_ref(), Some(todo.inner()));
+/// # Ok(())
+/// # }
+/// ```
+pub struct AuditedStore<DB: Database, E> {
+    store: Store<DB, E>,
+    actor: Option<String>,
+}
+
+/// Kind of operation recorded in the history of an entity.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum AuditOperation {
+    /// the entity was created
+    Create,
+    /// the entity was updated
+    Update,
+    /// the entity was deleted
+    Delete,
+}
+
+/// One row of the history of an entity, as returned by [`AuditedStore::history`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HistoryEntry<E> {
+    /// id of the row in the history table
+    pub id: i64,
+    /// id of the entity that was changed
+    pub entity_id: i64,
+    /// kind of operation performed on the entity
+    pub operation: AuditOperation,
+    /// when the operation occurred, in milliseconds since the UNIX epoch
+    pub timestamp: i64,
+    /// who performed the operation, if known
+    pub actor: Option<String>,
+    /// the entity before the operation (`None` for [`AuditOperation::Create`])
+    pub before: Option<E>,
+    /// the entity after the operation (`None` for [`AuditOperation::Delete`])
+    pub after: Option<E>,
+}
+
+#[derive(FromRow)]
+struct HistoryRow {
+    id: i64,
+    entity_id: i64,
+    operation: String,
+    changed_at: i64,
+    actor: Option<String>,
+    before_value: Option<String>,
+    after_value: Option<String>,
+}
+
+impl AuditOperation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuditOperation::Create => "create",
+            AuditOperation::Update => "update",
+            AuditOperation::Delete => "delete",
+        }
+    }
+
+    fn parse(s: &str) -> sqlx::Result<Self> {
+        match s {
+            "create" => Ok(AuditOperation::Create),
+            "update" => Ok(AuditOperation::Update),
+            "delete" => Ok(AuditOperation::Delete),
+            _ => Err(sqlx::Error::Decode(
+                format!("invalid audit operation '{s}'").into(),
+            )),
+        }
+    }
+}
+
+impl<DB: Database, E> Store<DB, E> {
+    /// Turns this store into an [`AuditedStore`]
+    pub fn audited(self) -> AuditedStore<DB, E> {
+        let store = self;
+        let actor = None;
+        AuditedStore { store, actor }
+    }
+}
+
+impl<DB: Database, E> AuditedStore<DB, E> {
+    /// Returns a copy of this store which records `actor` as the author
+    /// of all subsequent changes.
+    pub fn with_actor(&self, actor: impl Into<String>) -> Self {
+        let store = self.store.clone();
+        let actor = Some(actor.into());
+        Self { store, actor }
+    }
+
+    /// Returns the actor recorded as the author of the changes, if any.
+    pub fn actor(&self) -> Option<&str> {
+        self.actor.as_deref()
+    }
+
+    /// Returns a reference to the underlying, non-audited, [`Store`]
+    pub fn store(&self) -> &Store<DB, E> {
+        &self.store
+    }
+}
+
+impl<DB: Database, E> Clone for AuditedStore<DB, E> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            actor: self.actor.clone(),
+        }
+    }
+}
+
+fn to_json<E: Serialize>(entity: &E) -> sqlx::Result<String> {
+    // sqlx does not provide a dedicated variant for encoding errors
+    serde_json::to_string(entity).map_err(|err| sqlx::Error::Decode(Box::new(err)))
+}
+
+fn from_json<E: DeserializeOwned>(json: Option<String>) -> sqlx::Result<Option<E>> {
+    json.map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|err| sqlx::Error::Decode(Box::new(err)))
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// History
+///////////////////////////////////////////////////////////////////////////////////////////////////
+impl<DB, E> AuditedStore<DB, E>
+where
+    DB: Database + Dialect,
+    E: Schema<DB> + Serialize + DeserializeOwned,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+    for<'c> String: Type<DB> + Encode<'c, DB>,
+    for<'c> Option<String>: Type<DB> + Encode<'c, DB>,
+    for<'r> String: Decode<'r, DB>,
+    for<'r> &'r str: ColumnIndex<<DB as Database>::Row>,
+{
+    fn history_table() -> String {
+        format!("{}_history", E::MINIORM_TABLE_NAME)
+    }
+
+    fn create_history_table_query() -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} ({}, entity_id BIGINT NOT NULL, operation TEXT NOT NULL, \
+            changed_at BIGINT NOT NULL, actor TEXT, before_value TEXT, after_value TEXT)",
+            Self::history_table(),
+            DB::ID_DECLARATION
+        )
+    }
+
+    fn drop_history_table_query() -> String {
+        format!("DROP TABLE IF EXISTS {}", Self::history_table())
+    }
+
+    async fn record(
+        &self,
+        conn: &mut <DB as Database>::Connection,
+        entity_id: i64,
+        operation: AuditOperation,
+        before: Option<&E>,
+        after: Option<&E>,
+    ) -> sqlx::Result<()> {
+        let before = before.map(to_json).transpose()?;
+        let after = after.map(to_json).transpose()?;
+        let placeholders = (1..=6).map(DB::placeholder).collect::<Vec<_>>().join(", ");
+        let query = format!(
+            "INSERT INTO {} (entity_id, operation, changed_at, actor, before_value, after_value) \
+            VALUES ({placeholders})",
+            Self::history_table(),
+        );
+        sqlx::query(&query)
+            .bind(entity_id)
+            .bind(operation.as_str().to_string())
+            .bind(now())
+            .bind(self.actor.clone())
+            .bind(before)
+            .bind(after)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns all the changes made to the entity with the provided `id`,
+    /// from the oldest to the most recent.
+    pub async fn history(&self, id: i64) -> sqlx::Result<Vec<HistoryEntry<E>>> {
+        let query = format!(
+            "SELECT id, entity_id, operation, changed_at, actor, before_value, after_value \
+            FROM {} WHERE entity_id={} ORDER BY id",
+            Self::history_table(),
+            DB::placeholder(1)
+        );
+        let rows: Vec<HistoryRow> = sqlx::query_as(&query)
+            .bind(id)
+            .fetch_all(&self.store.db)
+            .await?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(HistoryEntry {
+                    id: row.id,
+                    entity_id: row.entity_id,
+                    operation: AuditOperation::parse(&row.operation)?,
+                    timestamp: row.changed_at,
+                    actor: row.actor,
+                    before: from_json(row.before_value)?,
+                    after: from_json(row.after_value)?,
+                })
+            })
+            .collect()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Table
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<DB, E> Table<DB> for AuditedStore<DB, E>
+where
+    DB: Database + Dialect,
+    E: Schema<DB> + Serialize + DeserializeOwned + Send + Sync,
+    <DB as Database>::QueryResult: RowsAffected,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+    for<'c> String: Type<DB> + Encode<'c, DB>,
+    for<'c> Option<String>: Type<DB> + Encode<'c, DB>,
+    for<'r> String: Decode<'r, DB>,
+    for<'r> &'r str: ColumnIndex<<DB as Database>::Row>,
+{
+    async fn create_table(&self) -> sqlx::Result<<DB as Database>::QueryResult> {
+        let result = self.store.create_table().await?;
+        sqlx::query(&Self::create_history_table_query())
+            .execute(&self.store.db)
+            .await?;
+        Ok(result)
+    }
+
+    async fn drop_table(&self) -> sqlx::Result<<DB as Database>::QueryResult> {
+        let result = self.store.drop_table().await?;
+        sqlx::query(&Self::drop_history_table_query())
+            .execute(&self.store.db)
+            .await?;
+        Ok(result)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Create
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<DB, E> Create<E> for AuditedStore<DB, E>
+where
+    DB: Database + Dialect + SupportsReturning,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB> + BindColumn<DB>,
+    E: Serialize + DeserializeOwned + Sync + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+    for<'c> String: Type<DB> + Encode<'c, DB>,
+    for<'c> Option<String>: Type<DB> + Encode<'c, DB>,
+    for<'r> String: Decode<'r, DB>,
+    for<'r> &'r str: ColumnIndex<<DB as Database>::Row>,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    async fn create(&self, entity: E) -> sqlx::Result<WithId<E>> {
+        let create = || async {
+            let mut tx = self.store.writer().begin().await?;
+            let (id,) = E::MINIORM_COLUMNS
+                .iter()
+                .fold(sqlx::query_as(E::MINIORM_CREATE), |query, col| {
+                    entity.bind_column(query, col)
+                })
+                .fetch_one(&mut *tx)
+                .await?;
+            self.record(&mut tx, id, AuditOperation::Create, None, Some(&entity))
+                .await?;
+            tx.commit().await?;
+            Ok(id)
+        };
+        let id = self.store.call("create").run(create, |_| 1).await?;
+        Ok(WithId::new(entity, id))
+    }
+}
+
+#[cfg(feature = "mysql")]
+mod mysql {
+    use super::{AuditOperation, AuditedStore};
+    use crate::{
+        prelude::{BindColumn, Create, Schema},
+        WithId,
+    };
+    use async_trait::async_trait;
+    use serde::{de::DeserializeOwned, Serialize};
+    use sqlx::{mysql::MySqlRow, FromRow, MySql};
+
+    #[async_trait]
+    impl<E> Create<E> for AuditedStore<MySql, E>
+    where
+        E: for<'r> FromRow<'r, MySqlRow> + Schema<MySql> + BindColumn<MySql>,
+        E: Serialize + DeserializeOwned + Sync + Send,
+    {
+        async fn create(&self, entity: E) -> sqlx::Result<WithId<E>> {
+            let create = || async {
+                let mut tx = self.store.writer().begin().await?;
+                let res = E::MINIORM_COLUMNS
+                    .iter()
+                    .fold(sqlx::query(E::MINIORM_CREATE), |query, col| {
+                        entity.bind_column(query, col)
+                    })
+                    .execute(&mut *tx)
+                    .await?;
+                let id = res.last_insert_id() as i64;
+                self.record(&mut tx, id, AuditOperation::Create, None, Some(&entity))
+                    .await?;
+                tx.commit().await?;
+                Ok(id)
+            };
+            let id = self.store.call("create").run(create, |_| 1).await?;
+            Ok(WithId::new(entity, id))
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Read
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<DB, E> Read<E> for AuditedStore<DB, E>
+where
+    DB: Database,
+    E: Send + Sync,
+    Store<DB, E>: Read<E>,
+{
+    async fn read(&self, id: i64) -> sqlx::Result<WithId<E>> {
+        self.store.read(id).await
+    }
+
+    async fn list(&self) -> sqlx::Result<Vec<WithId<E>>> {
+        self.store.list().await
+    }
+
+    async fn count(&self) -> sqlx::Result<u64> {
+        self.store.count().await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Update
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<DB, E> Update<E> for AuditedStore<DB, E>
+where
+    DB: Database + Dialect,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB> + BindColumn<DB>,
+    E: Serialize + DeserializeOwned + Unpin + Sync + Send,
+    <DB as Database>::QueryResult: RowsAffected,
+    for<'c> &'c str: ColumnIndex<<DB as Database>::Row>,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+    for<'c> String: Type<DB> + Encode<'c, DB>,
+    for<'c> Option<String>: Type<DB> + Encode<'c, DB>,
+    for<'r> String: Decode<'r, DB>,
+    for<'r> &'r str: ColumnIndex<<DB as Database>::Row>,
+{
+    async fn update(&self, entity: WithId<E>) -> sqlx::Result<WithId<E>> {
+        // the row is locked until the end of the transaction, so that concurrent
+        // updates are recorded one after the other, with the right `before`.
+        let read = format!("{}{}", E::MINIORM_READ, DB::FOR_UPDATE);
+        let update = || async {
+            let mut tx = self.store.writer().begin().await?;
+            let before: Option<WithId<E>> = sqlx::query_as(&read)
+                .bind(entity.id())
+                .fetch_optional(&mut *tx)
+                .await?;
+            // just like for a `Store`, updating a missing entity does not
+            // affect any row, and therefore is not recorded either.
+            let Some(before) = before else {
+                return Ok(0);
+            };
+            let res = E::MINIORM_COLUMNS
+                .iter()
+                .fold(sqlx::query(E::MINIORM_UPDATE), |query, col| {
+                    entity.bind_column(query, col)
+                })
+                .bind(entity.id())
+                .execute(&mut *tx)
+                .await?;
+            let operation = AuditOperation::Update;
+            self.record(
+                &mut tx,
+                entity.id(),
+                operation,
+                Some(&before),
+                Some(&entity),
+            )
+            .await?;
+            tx.commit().await?;
+            Ok(res.rows_affected())
+        };
+        self.store
+            .call("update")
+            .id(entity.id())
+            .run(update, |count| *count)
+            .await?;
+        Ok(entity)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Delete
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<DB, E> Delete<E> for AuditedStore<DB, E>
+where
+    DB: Database + Dialect,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB>,
+    E: Serialize + DeserializeOwned + Unpin + Sync + Send,
+    <DB as Database>::QueryResult: RowsAffected,
+    for<'c> &'c str: ColumnIndex<<DB as Database>::Row>,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+    for<'c> String: Type<DB> + Encode<'c, DB>,
+    for<'c> Option<String>: Type<DB> + Encode<'c, DB>,
+    for<'r> String: Decode<'r, DB>,
+    for<'r> &'r str: ColumnIndex<<DB as Database>::Row>,
+{
+    async fn delete(&self, id: i64) -> sqlx::Result<()> {
+        let read = format!("{}{}", E::MINIORM_READ, DB::FOR_UPDATE);
+        let delete = || async {
+            let mut tx = self.store.writer().begin().await?;
+            let before: WithId<E> = sqlx::query_as(&read).bind(id).fetch_one(&mut *tx).await?;
+            sqlx::query(E::MINIORM_DELETE)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            self.record(&mut tx, id, AuditOperation::Delete, Some(&before), None)
+                .await?;
+            tx.commit().await
+        };
+        self.store.call("delete").id(id).run(delete, |_| 1).await
+    }
+
+    async fn delete_all(&self) -> sqlx::Result<u64> {
+        let list = format!("{}{}", E::MINIORM_LIST, DB::FOR_UPDATE);
+        let delete_all = || async {
+            let mut tx = self.store.writer().begin().await?;
+            let all: Vec<WithId<E>> = sqlx::query_as(&list).fetch_all(&mut *tx).await?;
+            let res = sqlx::query(E::MINIORM_DELETE_ALL).execute(&mut *tx).await?;
+            for before in &all {
+                self.record(
+                    &mut tx,
+                    before.id(),
+                    AuditOperation::Delete,
+                    Some(before),
+                    None,
+                )
+                .await?;
+            }
+            tx.commit().await?;
+            Ok(res.rows_affected())
+        };
+        self.store
+            .call("delete_all")
+            .run(delete_all, |count| *count)
+            .await
+    }
+}
@@ -0,0 +1,300 @@
+use crate::{prelude::Table, Store};
+use sqlx::{
+    migrate::{Migrate, Migrator},
+    pool::PoolOptions,
+    Connection, Database, Pool,
+};
+use std::time::Duration;
+
+/// Configuration of the pool of connections to a database, e.g. as loaded
+/// from the configuration file of an application, from which a
+/// [`StoreBuilder`] can be created using [`StoreBuilder::from_config`].
+///
+/// The settings left to `None` keep the defaults of [`PoolOptions`].
+///
+/// With the `serde` feature flag, the configuration can be deserialized,
+/// the durations being given as a number of seconds, e.g. in TOML:
+///
+/// ```toml
+/// url = "postgres://localhost/miniorm"
+/// max_connections = 10
+/// acquire_timeout = 2.5
+/// create_tables = true
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct StoreConfig {
+    /// url of the database, e.g. `postgres://localhost/miniorm`
+    pub url: String,
+    /// maximum number of connections of the pool
+    pub max_connections: Option<u32>,
+    /// minimum number of connections of the pool
+    pub min_connections: Option<u32>,
+    /// maximum time spent waiting for a connection
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "seconds"))]
+    pub acquire_timeout: Option<Duration>,
+    /// maximum idle time of a connection before it is closed
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "seconds"))]
+    pub idle_timeout: Option<Duration>,
+    /// maximum lifetime of a connection before it is closed
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "seconds"))]
+    pub max_lifetime: Option<Duration>,
+    /// whether the table of each store should be created if it does not exist
+    pub create_tables: bool,
+}
+
+/// Deserializes an optional duration from a number of seconds.
+#[cfg(feature = "serde")]
+fn seconds<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    use serde::{de::Error, Deserialize};
+    match Option::<f64>::deserialize(deserializer)? {
+        Some(secs) => Duration::try_from_secs_f64(secs)
+            .map(Some)
+            .map_err(D::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// Builder connecting to a database and returning [`Stores`], from which
+/// stores of several entities sharing the same pool of connections can be
+/// obtained.
+///
+/// # Example
+///
+/// ```no_run
+/// use miniorm::{prelude::*, StoreBuilder};
+/// use sqlx::{sqlite::SqliteJournalMode, FromRow, Sqlite};
+/// use std::time::Duration;
+///
+/// #[derive(Debug, Clone, FromRow, Entity)]
+/// struct Todo {
+///     #[sqlite(TEXT NOT NULL)]
+///     description: String,
+/// }
+///
+/// #[derive(Debug, Clone, FromRow, Entity)]
+/// struct Tag {
+///     #[sqlite(TEXT NOT NULL)]
+///     name: String,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let stores = StoreBuilder::<Sqlite>::new("sqlite://todos.db?mode=rwc")?
+///     .max_connections(4)
+///     .journal_mode(SqliteJournalMode::Wal)
+///     .foreign_keys(true)
+///     .busy_timeout(Duration::from_secs(1))
+///     .create_tables(true)
+///     .connect()
+///     .await?;
+///
+/// let todos: Store<_, Todo> = stores.store().await?;
+/// let tags: Store<_, Tag> = stores.store().await?;
+/// # Ok(())
+/// # }
+/// ```
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         This example requires the <span style="color:blue">sqlite</span> feature flag.
+///         </td>
+///     </tr>
+/// </table>
+pub struct StoreBuilder<DB: Database> {
+    options: <DB::Connection as Connection>::Options,
+    pool: PoolOptions<DB>,
+    create_tables: bool,
+    migrator: Option<Migrator>,
+}
+
+impl<DB: Database> StoreBuilder<DB> {
+    /// Creates a builder connecting to the database at `url`.
+    ///
+    /// Fails if the url is not valid for the backend.
+    pub fn new(url: &str) -> sqlx::Result<Self> {
+        Ok(Self::with_options(url.parse()?))
+    }
+
+    /// Creates a builder connecting to the database using the provided
+    /// connection options (e.g. [`PgConnectOptions`](sqlx::postgres::PgConnectOptions)).
+    pub fn with_options(options: <DB::Connection as Connection>::Options) -> Self {
+        Self {
+            options,
+            pool: PoolOptions::new(),
+            create_tables: false,
+            migrator: None,
+        }
+    }
+
+    /// Creates a builder from the provided configuration.
+    ///
+    /// Fails if the url is not valid for the backend.
+    pub fn from_config(config: &StoreConfig) -> sqlx::Result<Self> {
+        let mut builder = Self::new(&config.url)?.create_tables(config.create_tables);
+        if let Some(max) = config.max_connections {
+            builder = builder.max_connections(max);
+        }
+        if let Some(min) = config.min_connections {
+            builder = builder.min_connections(min);
+        }
+        if let Some(timeout) = config.acquire_timeout {
+            builder = builder.acquire_timeout(timeout);
+        }
+        if let Some(timeout) = config.idle_timeout {
+            builder = builder.idle_timeout(timeout);
+        }
+        if let Some(lifetime) = config.max_lifetime {
+            builder = builder.max_lifetime(lifetime);
+        }
+        Ok(builder)
+    }
+
+    /// Sets the maximum number of connections of the pool.
+    pub fn max_connections(self, max: u32) -> Self {
+        let pool = self.pool.max_connections(max);
+        Self { pool, ..self }
+    }
+
+    /// Sets the minimum number of connections of the pool.
+    pub fn min_connections(self, min: u32) -> Self {
+        let pool = self.pool.min_connections(min);
+        Self { pool, ..self }
+    }
+
+    /// Sets the maximum time spent waiting for a connection.
+    pub fn acquire_timeout(self, timeout: Duration) -> Self {
+        let pool = self.pool.acquire_timeout(timeout);
+        Self { pool, ..self }
+    }
+
+    /// Sets the maximum idle time of a connection before it is closed.
+    pub fn idle_timeout(self, timeout: Duration) -> Self {
+        let pool = self.pool.idle_timeout(timeout);
+        Self { pool, ..self }
+    }
+
+    /// Sets the maximum lifetime of a connection before it is closed.
+    pub fn max_lifetime(self, lifetime: Duration) -> Self {
+        let pool = self.pool.max_lifetime(lifetime);
+        Self { pool, ..self }
+    }
+
+    /// Sets whether [`Stores::store`] creates the table of the entity, if it
+    /// does not exist yet.
+    pub fn create_tables(self, create_tables: bool) -> Self {
+        Self {
+            create_tables,
+            ..self
+        }
+    }
+
+    /// Runs the provided migrations, e.g. as embedded by [`sqlx::migrate!`],
+    /// once connected.
+    pub fn migrate(self, migrator: Migrator) -> Self {
+        let migrator = Some(migrator);
+        Self { migrator, ..self }
+    }
+
+    /// Connects to the database and runs the migrations, if any.
+    pub async fn connect(self) -> sqlx::Result<Stores<DB>>
+    where
+        DB::Connection: Migrate,
+    {
+        let pool = self.pool.connect_with(self.options).await?;
+        if let Some(migrator) = &self.migrator {
+            migrator.run(&pool).await?;
+        }
+        let create_tables = self.create_tables;
+        Ok(Stores {
+            pool,
+            create_tables,
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl StoreBuilder<sqlx::Sqlite> {
+    /// Sets the journal mode of the database (e.g. `WAL`).
+    pub fn journal_mode(self, mode: sqlx::sqlite::SqliteJournalMode) -> Self {
+        let options = self.options.journal_mode(mode);
+        Self { options, ..self }
+    }
+
+    /// Sets whether the foreign key constraints are enforced.
+    pub fn foreign_keys(self, on: bool) -> Self {
+        let options = self.options.foreign_keys(on);
+        Self { options, ..self }
+    }
+
+    /// Sets the maximum time spent waiting for a lock on the database.
+    pub fn busy_timeout(self, timeout: Duration) -> Self {
+        let options = self.options.busy_timeout(timeout);
+        Self { options, ..self }
+    }
+
+    /// Sets any other `PRAGMA` on each connection.
+    pub fn pragma(self, key: &str, value: &str) -> Self {
+        let options = self.options.pragma(key.to_owned(), value.to_owned());
+        Self { options, ..self }
+    }
+}
+
+/// The stores on a database, sharing the same pool of connections,
+/// as returned by [`StoreBuilder::connect`].
+pub struct Stores<DB: Database> {
+    pool: Pool<DB>,
+    create_tables: bool,
+}
+
+impl<DB: Database> Stores<DB> {
+    /// Returns the pool of connections shared by the stores.
+    pub fn pool(&self) -> &Pool<DB> {
+        &self.pool
+    }
+
+    /// Returns a store of entities of type `E`, after creating its table
+    /// if [`StoreBuilder::create_tables`] was set.
+    pub async fn store<E>(&self) -> sqlx::Result<Store<DB, E>>
+    where
+        Store<DB, E>: Table<DB>,
+    {
+        let store = Store::new(self.pool.clone());
+        if self.create_tables {
+            store.create_table().await?;
+        }
+        Ok(store)
+    }
+}
+
+impl<DB: Database> Clone for Stores<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            create_tables: self.create_tables,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deserialize_config() {
+        let json = r#"{"url": "sqlite::memory:", "max_connections": 4, "acquire_timeout": 2.5}"#;
+        let config: StoreConfig = serde_json::from_str(json).unwrap();
+        let expected = StoreConfig {
+            url: "sqlite::memory:".into(),
+            max_connections: Some(4),
+            acquire_timeout: Some(Duration::from_millis(2500)),
+            ..Default::default()
+        };
+        assert_eq!(config, expected);
+
+        let json = r#"{"url": "sqlite::memory:", "idle_timeout": -1}"#;
+        assert!(serde_json::from_str::<StoreConfig>(json).is_err());
+    }
+}
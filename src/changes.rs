@@ -0,0 +1,165 @@
+use crate::{prelude::Schema, Store};
+use futures::{stream::BoxStream, StreamExt};
+use sqlx::{postgres::PgListener, Postgres};
+
+/// Kind of change notified by [`Store::changes`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ChangeOperation {
+    /// an entity was created
+    Create,
+    /// an entity was updated
+    Update,
+    /// an entity was deleted
+    Delete,
+}
+
+/// A change made to one of the entities of a [`Store`], as notified by
+/// [`Store::changes`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Change {
+    /// kind of change
+    pub op: ChangeOperation,
+    /// id of the entity that was changed
+    pub id: i64,
+}
+
+impl Change {
+    fn parse(payload: &str) -> sqlx::Result<Self> {
+        let invalid = || sqlx::Error::Decode(format!("invalid change '{payload}'").into());
+        let (op, id) = payload.split_once(':').ok_or_else(invalid)?;
+        let op = match op {
+            "INSERT" => ChangeOperation::Create,
+            "UPDATE" => ChangeOperation::Update,
+            "DELETE" => ChangeOperation::Delete,
+            _ => return Err(invalid()),
+        };
+        let id = id.parse().map_err(|_| invalid())?;
+        Ok(Self { op, id })
+    }
+}
+
+impl<E: Schema<Postgres>> Store<Postgres, E> {
+    fn notify_channel() -> String {
+        format!("miniorm_{}", E::MINIORM_TABLE_NAME)
+    }
+
+    fn notify_function() -> String {
+        format!("{}_miniorm_notify", E::MINIORM_TABLE_NAME)
+    }
+
+    /// Installs a trigger on the table of the store that notifies every
+    /// insert, update and delete to the listeners of [`Store::changes`].
+    ///
+    /// The trigger is dropped along with the table, so this should be called
+    /// again after [`Table::recreate_table`](crate::prelude::Table::recreate_table).
+    ///
+    /// <table>
+    ///     <tr>
+    ///         <td style="background-color:green;color:black;">
+    ///         Requires the <span style="color:blue">changes</span>
+    ///         feature flag.
+    ///         </td>
+    ///     </tr>
+    /// </table>
+    pub async fn install_change_trigger(&self) -> sqlx::Result<()> {
+        let table = E::MINIORM_TABLE_NAME;
+        let channel = Self::notify_channel();
+        let function = Self::notify_function();
+
+        let create_function = format!(
+            "CREATE OR REPLACE FUNCTION {function}() RETURNS trigger AS $$
+            BEGIN
+                IF TG_OP = 'DELETE' THEN
+                    PERFORM pg_notify('{channel}', TG_OP || ':' || OLD.id);
+                    RETURN OLD;
+                END IF;
+                PERFORM pg_notify('{channel}', TG_OP || ':' || NEW.id);
+                RETURN NEW;
+            END;
+            $$ LANGUAGE plpgsql"
+        );
+        let drop_trigger = format!("DROP TRIGGER IF EXISTS {function} ON {table}");
+        let create_trigger = format!(
+            "CREATE TRIGGER {function} AFTER INSERT OR UPDATE OR DELETE ON {table} \
+            FOR EACH ROW EXECUTE FUNCTION {function}()"
+        );
+
+        let mut tx = self.db.begin().await?;
+        for query in [create_function, drop_trigger, create_trigger] {
+            sqlx::query(&query).execute(&mut *tx).await?;
+        }
+        tx.commit().await
+    }
+
+    /// Returns a stream of all the changes made to the entities of the store,
+    /// from now on, whether they go through this store or not.
+    ///
+    /// The changes are only notified once the trigger has been installed using
+    /// [`Store::install_change_trigger`].
+    ///
+    /// <table>
+    ///     <tr>
+    ///         <td style="background-color:green;color:black;">
+    ///         Requires the <span style="color:blue">changes</span>
+    ///         feature flag.
+    ///         </td>
+    ///     </tr>
+    /// </table>
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use futures::StreamExt;
+    /// use miniorm::{prelude::*, ChangeOperation};
+    /// use sqlx::FromRow;
+    ///
+    /// #[derive(Debug, Clone, Eq, PartialEq, FromRow, Entity)]
+    /// struct Todo {
+    ///     #[postgres(TEXT NOT NULL)]
+    ///     description: String,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = sqlx::PgPool::connect("postgres://localhost/miniorm").await?;
+    /// let store = Store::<_, Todo>::new(db);
+    /// store.install_change_trigger().await?;
+    ///
+    /// let mut changes = store.changes().await?;
+    /// while let Some(change) = changes.next().await {
+    ///     let change = change?;
+    ///     if change.op != ChangeOperation::Delete {
+    ///         println!("{:?}", store.read(change.id).await?);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn changes(&self) -> sqlx::Result<BoxStream<'static, sqlx::Result<Change>>> {
+        let mut listener = PgListener::connect_with(&self.db).await?;
+        listener.listen(&Self::notify_channel()).await?;
+        let changes = listener
+            .into_stream()
+            .map(|notification| Change::parse(notification?.payload()));
+        Ok(changes.boxed())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Change, ChangeOperation};
+
+    #[test]
+    fn parse() {
+        let change = Change::parse("INSERT:12").unwrap();
+        assert_eq!(change.op, ChangeOperation::Create);
+        assert_eq!(change.id, 12);
+        let change = Change::parse("UPDATE:1").unwrap();
+        assert_eq!(change.op, ChangeOperation::Update);
+        let change = Change::parse("DELETE:3").unwrap();
+        assert_eq!(change.op, ChangeOperation::Delete);
+        assert!(Change::parse("TRUNCATE:3").is_err());
+        assert!(Change::parse("INSERT").is_err());
+        assert!(Change::parse("INSERT:x").is_err());
+    }
+}
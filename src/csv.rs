@@ -0,0 +1,186 @@
+//! Minimal CSV support (RFC 4180), shared by the CSV export and import of the
+//! axum router and by the table dumps.
+
+use crate::WithId;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Returns the header line of the CSV representation of the entities:
+/// `id` followed by the `columns`.
+pub(crate) fn header(columns: &[&str]) -> String {
+    let header = std::iter::once("id")
+        .chain(columns.iter().copied())
+        .map(field)
+        .collect::<Vec<_>>()
+        .join(",");
+    header + "\n"
+}
+
+/// Returns the line of the CSV representation of one entity, made of its id
+/// followed by the values of the `columns` in its serialized form.
+pub(crate) fn record<E: Serialize>(
+    entity: &WithId<E>,
+    columns: &[&str],
+) -> serde_json::Result<String> {
+    let json = serde_json::to_value(entity.inner())?;
+    let fields = columns.iter().map(|column| match json.get(column) {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => field(s),
+        Some(other) => field(&other.to_string()),
+    });
+    let line = std::iter::once(entity.id().to_string())
+        .chain(fields)
+        .collect::<Vec<_>>()
+        .join(",");
+    Ok(line + "\n")
+}
+
+/// Quotes a CSV field if needed
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Splits a CSV record into its fields, or returns `None` if the record is
+/// incomplete because a quoted field spans several lines.
+pub(crate) fn parse_record(record: &str) -> Option<Vec<String>> {
+    let record = record.strip_suffix('\n').unwrap_or(record);
+    let record = record.strip_suffix('\r').unwrap_or(record);
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = record.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            (c, _) => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+/// Converts the fields of a CSV record into a JSON object, using the
+/// JSON schema of the entity to convert each field to the type of its column.
+///
+/// Empty fields of nullable columns are converted to `null`, and the `id`
+/// column, if any, is ignored.
+pub(crate) fn to_json(
+    header: &[String],
+    fields: Vec<String>,
+    schema: &Value,
+) -> Result<Value, String> {
+    if fields.len() != header.len() {
+        return Err(format!(
+            "expected {} fields, found {}",
+            header.len(),
+            fields.len()
+        ));
+    }
+    let required = schema["required"].as_array();
+    let mut object = Map::new();
+    for (column, field) in header.iter().zip(fields) {
+        if column == "id" {
+            continue;
+        }
+        let Some(property) = schema["properties"].get(column) else {
+            return Err(format!("unknown column '{column}'"));
+        };
+        let nullable = !required.is_some_and(|required| required.contains(&column.as_str().into()));
+        let ty = match &property["type"] {
+            Value::Array(types) => types.first().and_then(Value::as_str),
+            ty => ty.as_str(),
+        };
+        let value = match ty {
+            _ if field.is_empty() && nullable => Value::Null,
+            Some("string") => Value::String(field),
+            Some("boolean") => match field.to_lowercase().as_str() {
+                "true" | "t" | "1" => Value::Bool(true),
+                "false" | "f" | "0" => Value::Bool(false),
+                _ => return Err(format!("invalid boolean '{field}' for '{column}'")),
+            },
+            Some("integer") | Some("number") => serde_json::from_str(&field)
+                .ok()
+                .filter(Value::is_number)
+                .ok_or_else(|| format!("invalid number '{field}' for '{column}'"))?,
+            _ => serde_json::from_str(&field).unwrap_or(Value::String(field)),
+        };
+        object.insert(column.clone(), value);
+    }
+    Ok(Value::Object(object))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{header, parse_record, record, to_json};
+    use crate::WithId;
+    use serde::Serialize;
+    use serde_json::json;
+
+    #[test]
+    fn write_and_parse() {
+        #[derive(Serialize)]
+        struct Todo {
+            description: &'static str,
+            done: bool,
+        }
+
+        let columns = ["description", "done"];
+        assert_eq!(header(&columns), "id,description,done\n");
+        let todo = Todo {
+            description: "buy milk, \"bread\"",
+            done: false,
+        };
+        let line = record(&WithId::new(todo, 3), &columns).unwrap();
+        assert_eq!(line, "3,\"buy milk, \"\"bread\"\"\",false\n");
+        assert_eq!(
+            parse_record(&line).unwrap(),
+            ["3", "buy milk, \"bread\"", "false"]
+        );
+        assert_eq!(parse_record("1,\"multi\n"), None);
+        assert_eq!(
+            parse_record("1,\"multi\nline\",true\r\n").unwrap(),
+            ["1", "multi\nline", "true"]
+        );
+    }
+
+    #[test]
+    fn coerce() {
+        let schema = json!({
+            "properties": {
+                "description": {"type": "string"},
+                "done": {"type": "boolean"},
+                "price": {"type": ["number", "null"]},
+            },
+            "required": ["description", "done"],
+        });
+        let header = ["id", "description", "done", "price"].map(String::from);
+        let fields = |fields: [&str; 4]| fields.map(String::from).to_vec();
+
+        let json = to_json(&header, fields(["1", "milk", "true", "1.5"]), &schema);
+        assert_eq!(
+            json.unwrap(),
+            json!({"description": "milk", "done": true, "price": 1.5})
+        );
+        let json = to_json(&header, fields(["", "", "0", ""]), &schema);
+        assert_eq!(
+            json.unwrap(),
+            json!({"description": "", "done": false, "price": null})
+        );
+        assert!(to_json(&header, fields(["", "milk", "yes", ""]), &schema).is_err());
+        assert!(to_json(&header, fields(["", "milk", "true", "x"]), &schema).is_err());
+        assert!(to_json(&header, vec!["1".into()], &schema).is_err());
+    }
+}
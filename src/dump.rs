@@ -0,0 +1,312 @@
+use crate::{
+    csv, json_schema,
+    prelude::{BindColumn, Read, Schema},
+    traits::sqlx::Dialect,
+    Flat, Store, WithId,
+};
+use futures::StreamExt;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use sqlx::{
+    database::HasArguments, ColumnIndex, Database, Decode, Encode, Executor, FromRow,
+    IntoArguments, Type,
+};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, Lines};
+
+/// Format of the dumps written by [`Store::dump`] and read by [`Store::restore`].
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">dump</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DumpFormat {
+    /// [JSON Lines](https://jsonlines.org/): one JSON object per row, with the `id`
+    /// alongside the fields of the entity (see [`Flat`]).
+    JsonLines,
+    /// CSV: a header line with `id` followed by the
+    /// [`Schema::MINIORM_COLUMNS`], then one line per row.
+    Csv,
+}
+
+fn decode_error(line: usize, err: impl std::fmt::Display) -> sqlx::Error {
+    sqlx::Error::Decode(format!("line {line}: {err}").into())
+}
+
+/// Reads the rows of a dump one at a time.
+struct Rows<'a, R> {
+    format: DumpFormat,
+    lines: Lines<R>,
+    schema: &'a Value,
+    header: Option<Vec<String>>,
+    line: usize,
+}
+
+impl<'a, R: AsyncBufRead + Unpin> Rows<'a, R> {
+    fn new(format: DumpFormat, reader: R, schema: &'a Value) -> Self {
+        Self {
+            format,
+            lines: reader.lines(),
+            schema,
+            header: None,
+            line: 0,
+        }
+    }
+
+    /// Returns the next row, or `None` at the end of the dump.
+    async fn next<E: DeserializeOwned>(&mut self) -> sqlx::Result<Option<WithId<E>>> {
+        let mut record = String::new();
+        let mut start = self.line + 1;
+        while let Some(line) = self.lines.next_line().await? {
+            self.line += 1;
+            record += &line;
+            if record.trim().is_empty() {
+                record.clear();
+                start = self.line + 1;
+                continue;
+            }
+            if self.format == DumpFormat::JsonLines {
+                let Flat(row) =
+                    serde_json::from_str(&record).map_err(|err| decode_error(start, err))?;
+                return Ok(Some(row));
+            }
+            let Some(fields) = csv::parse_record(&record) else {
+                // a quoted field continues on the next line
+                record.push('\n');
+                continue;
+            };
+            let Some(header) = &self.header else {
+                if !fields.iter().any(|field| field == "id") {
+                    return Err(decode_error(start, "missing id column"));
+                }
+                self.header = Some(fields);
+                record.clear();
+                start = self.line + 1;
+                continue;
+            };
+            let id = header
+                .iter()
+                .zip(&fields)
+                .find(|(column, _)| *column == "id")
+                .and_then(|(_, id)| id.parse().ok())
+                .ok_or_else(|| decode_error(start, "invalid id"))?;
+            let json = csv::to_json(header, fields, self.schema)
+                .map_err(|err| decode_error(start, err))?;
+            let entity = serde_json::from_value(json).map_err(|err| decode_error(start, err))?;
+            return Ok(Some(WithId::new(entity, id)));
+        }
+        if record.is_empty() {
+            Ok(None)
+        } else {
+            Err(decode_error(start, "unterminated quoted field"))
+        }
+    }
+}
+
+impl<DB, E> Store<DB, E>
+where
+    DB: Database + Dialect,
+    E: Unpin + Send + Sync,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB> + Serialize,
+    for<'c> &'c str: ColumnIndex<<DB as Database>::Row>,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+{
+    /// Writes all the rows of the table, including their ids and ordered by id,
+    /// to `writer` in the given format, and returns the number of rows written.
+    ///
+    /// The rows are streamed from the database, so that large tables can be dumped
+    /// without loading them in memory. The dump can be loaded back into an empty
+    /// table, possibly of another database, using [`Store::restore`].
+    ///
+    /// The rows are written asynchronously, e.g. to a [`tokio::fs::File`] or a
+    /// socket, without blocking the runtime.
+    ///
+    /// <table>
+    ///     <tr>
+    ///         <td style="background-color:green;color:black;">
+    ///         Requires the <span style="color:blue">dump</span>
+    ///         feature flag.
+    ///         </td>
+    ///     </tr>
+    /// </table>
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use miniorm::{prelude::*, DumpFormat};
+    /// use serde::{Deserialize, Serialize};
+    /// use sqlx::FromRow;
+    /// use tokio::{fs::File, io::BufReader};
+    ///
+    /// #[derive(Debug, Clone, FromRow, Entity, Serialize, Deserialize)]
+    /// struct Todo {
+    ///     #[postgres(TEXT NOT NULL)]
+    ///     description: String,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let prod = Store::<_, Todo>::new(sqlx::PgPool::connect("postgres://prod/todos").await?);
+    /// prod.dump(DumpFormat::JsonLines, File::create("todos.jsonl").await?).await?;
+    ///
+    /// let staging = Store::<_, Todo>::new(sqlx::PgPool::connect("postgres://staging/todos").await?);
+    /// staging.recreate_table().await?;
+    /// let dump = BufReader::new(File::open("todos.jsonl").await?);
+    /// staging.restore(DumpFormat::JsonLines, dump).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn dump(
+        &self,
+        format: DumpFormat,
+        mut writer: impl AsyncWrite + Unpin + Send,
+    ) -> sqlx::Result<u64> {
+        if format == DumpFormat::Csv {
+            let header = csv::header(E::MINIORM_COLUMNS);
+            writer.write_all(header.as_bytes()).await?;
+        }
+        let mut rows = self.stream();
+        let mut count = 0;
+        while let Some(row) = rows.next().await {
+            let line = match format {
+                DumpFormat::JsonLines => serde_json::to_string(&Flat(row?)).map(|line| line + "\n"),
+                DumpFormat::Csv => csv::record(&row?, E::MINIORM_COLUMNS),
+            };
+            let line = line.map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+            writer.write_all(line.as_bytes()).await?;
+            count += 1;
+        }
+        writer.flush().await?;
+        Ok(count)
+    }
+}
+
+impl<DB, E> Store<DB, E>
+where
+    DB: Database + Dialect,
+    E: Schema<DB> + BindColumn<DB> + DeserializeOwned + Send + Sync,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Encode<'c, DB>,
+{
+    /// Loads the rows of a dump written by [`Store::dump`] into the table, which
+    /// must be empty, and returns the number of rows loaded.
+    ///
+    /// The ids of the rows are preserved, and the sequence generating the ids is
+    /// reset past the largest one, so that the entities created afterwards do not
+    /// collide with the restored ones. The rows are loaded in one transaction:
+    /// if one of them is invalid, none is loaded. Since the dump is consumed as it
+    /// is loaded, the restore is not retried.
+    ///
+    /// The dump is read asynchronously, e.g. from a [`tokio::io::BufReader`]
+    /// over a [`tokio::fs::File`], without blocking the runtime.
+    ///
+    /// <table>
+    ///     <tr>
+    ///         <td style="background-color:green;color:black;">
+    ///         Requires the <span style="color:blue">dump</span>
+    ///         feature flag.
+    ///         </td>
+    ///     </tr>
+    /// </table>
+    pub async fn restore(
+        &self,
+        format: DumpFormat,
+        reader: impl AsyncBufRead + Unpin + Send,
+    ) -> sqlx::Result<u64> {
+        let table = E::MINIORM_TABLE_NAME;
+        let columns = E::MINIORM_COLUMNS;
+        let placeholders = (1..=columns.len() + 1)
+            .map(DB::placeholder)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let insert = format!(
+            "INSERT INTO {table} (id, {}) VALUES ({placeholders})",
+            columns.join(", ")
+        );
+        let schema = json_schema::entity_schema(E::MINIORM_JSON_SCHEMA);
+
+        let restore = async {
+            let mut tx = self.writer().begin().await?;
+            let any = format!("SELECT id FROM {table} LIMIT 1");
+            if sqlx::query(&any).fetch_optional(&mut *tx).await?.is_some() {
+                return Err(sqlx::Error::Configuration(format!(
+                    "cannot restore a dump into the non-empty table '{table}'"
+                )
+                .into()));
+            }
+            let mut rows = Rows::new(format, reader, &schema);
+            let mut count = 0;
+            while let Some(row) = rows.next::<E>().await? {
+                columns
+                    .iter()
+                    .fold(sqlx::query(&insert).bind(row.id()), |query, col| {
+                        row.bind_column(query, col)
+                    })
+                    .execute(&mut *tx)
+                    .await?;
+                count += 1;
+            }
+            if let Some(reset) = DB::reset_id_sequence(table) {
+                sqlx::query(&reset).execute(&mut *tx).await?;
+            }
+            tx.commit().await?;
+            Ok(count)
+        };
+        self.call("restore").once(restore, |count| *count).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DumpFormat, Rows};
+    use crate::WithId;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Todo {
+        description: String,
+        done: bool,
+    }
+
+    fn todo(id: i64, description: &str, done: bool) -> WithId<Todo> {
+        let description = description.into();
+        WithId::new(Todo { description, done }, id)
+    }
+
+    #[tokio::test]
+    async fn read_rows() {
+        let schema = json!({
+            "properties": {"description": {"type": "string"}, "done": {"type": "boolean"}},
+            "required": ["description", "done"],
+        });
+        let dump = "{\"id\":3,\"description\":\"milk\",\"done\":true}\n\n\
+            {\"id\":7,\"description\":\"bread\",\"done\":false}\n";
+        let mut rows = Rows::new(DumpFormat::JsonLines, dump.as_bytes(), &schema);
+        assert_eq!(rows.next().await.unwrap(), Some(todo(3, "milk", true)));
+        assert_eq!(rows.next().await.unwrap(), Some(todo(7, "bread", false)));
+        assert_eq!(rows.next::<Todo>().await.unwrap(), None);
+
+        let dump = "id,description,done\n3,\"multi\nline\",true\n7,bread,false";
+        let mut rows = Rows::new(DumpFormat::Csv, dump.as_bytes(), &schema);
+        assert_eq!(rows.next().await.unwrap(), Some(todo(3, "multi\nline", true)));
+        assert_eq!(rows.next().await.unwrap(), Some(todo(7, "bread", false)));
+        assert_eq!(rows.next::<Todo>().await.unwrap(), None);
+
+        let dump = "description,done\nmilk,true\n";
+        let mut rows = Rows::new(DumpFormat::Csv, dump.as_bytes(), &schema);
+        assert!(rows.next::<Todo>().await.is_err());
+        let dump = "id,description,done\n3,milk,true\nx,bread,false\n";
+        let mut rows = Rows::new(DumpFormat::Csv, dump.as_bytes(), &schema);
+        assert!(rows.next::<Todo>().await.is_ok());
+        let err = rows.next::<Todo>().await.unwrap_err().to_string();
+        assert!(err.contains("line 3"), "{err}");
+    }
+}
@@ -0,0 +1,567 @@
+//! Random, yet valid, test entities.
+//!
+//! The [`Factory`] trait, which can be derived for [`Entity`](crate::Entity)
+//! types, builds random instances of an entity, each field being generated
+//! according to its type and to the declaration of its column:
+//! - `VARCHAR(n)` and `CHAR(n)` strings are at most `n` characters long,
+//! - `SMALLINT` and `INT`/`INTEGER` numbers fit in 16 and 32 bits,
+//! - `Option` fields are sometimes `None`, unless the column is `NOT NULL`,
+//! - dates and times of `chrono` and `time` (with the `chrono` and `time`
+//!   feature flags) fall between 2000 and 2030,
+//! - `Decimal` numbers (with the `rust_decimal` feature flag) have two decimals,
+//! - fields stored as JSON with `#[sqlx(json)]`, as well as fields skipped
+//!   with `#[sqlx(skip)]`, get their [`Default`] value.
+//!
+//! The value of a field can also be given with `#[factory(...)]`, either as
+//! a `sequence`, i.e. a format string in which `{n}` is the number of the
+//! entity being built (starting from 1), or as an expression `value` in
+//! which `n` can be used as well.
+//!
+//! Entities are built by a [`Generator`], which can apply overrides to each
+//! of them, and create them directly into a store.
+//!
+//! <table>
+//!     <tr>
+//!         <td style="background-color:green;color:black;">
+//!         Requires the <span style="color:blue">factory</span>
+//!         feature flag.
+//!         </td>
+//!     </tr>
+//! </table>
+//!
+//! # Example
+//!
+//! ```
+//! use miniorm::{prelude::*, MemoryStore};
+//! use sqlx::FromRow;
+//!
+//! #[derive(Debug, Clone, FromRow, Entity, Factory)]
+//! struct Todo {
+//!     #[column(VARCHAR(40) NOT NULL)]
+//!     #[factory(sequence = "todo #{n}")]
+//!     description: String,
+//!
+//!     #[column(BOOLEAN NOT NULL DEFAULT false)]
+//!     done: bool,
+//!
+//!     #[column(INTEGER)]
+//!     priority: Option<i32>,
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> sqlx::Result<()> {
+//!     let mut todos = Todo::factory();
+//!     assert_eq!(todos.build().description, "todo #1");
+//!
+//!     let store = MemoryStore::new();
+//!     let mut done = Todo::factory().with(|todo| todo.done = true);
+//!     let created = done.create_many(&store, 3).await?;
+//!     assert!(created.iter().all(|todo| todo.done));
+//!     Ok(())
+//! }
+//! ```
+use crate::{prelude::Create, WithId};
+use std::ops::RangeInclusive;
+
+pub use miniorm_macros::Factory;
+
+/// Types of which random instances can be built by a [`Generator`].
+///
+/// It is usually derived (see the [module documentation](self)), but can
+/// also be implemented manually:
+///
+/// ```
+/// use miniorm::factory::{Factory, Faker};
+///
+/// struct Point {
+///     x: i64,
+///     y: i64,
+/// }
+///
+/// impl Factory for Point {
+///     fn fake(faker: &mut Faker) -> Self {
+///         let x = faker.integer(-100..=100);
+///         let y = faker.integer(-100..=100);
+///         Point { x, y }
+///     }
+/// }
+///
+/// let point = Point::factory().build();
+/// assert!((-100..=100).contains(&point.x));
+/// ```
+pub trait Factory: Sized {
+    /// Builds a random instance.
+    fn fake(faker: &mut Faker) -> Self;
+
+    /// Returns a new [`Generator`] of random instances.
+    fn factory() -> Generator<Self> {
+        Generator::new()
+    }
+}
+
+/// Types of the fields of a [`Factory`], of which random values can be built
+/// for a given column.
+///
+/// Implement it for the other types used as fields of a derived [`Factory`],
+/// or give their value using `#[factory(value = ...)]`.
+pub trait Fake: Sized {
+    /// Builds a random value, valid for `column`.
+    fn fake(faker: &mut Faker, column: &Column) -> Self;
+}
+
+/// Declaration of the column of a field, as given to [`Fake::fake`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    name: &'static str,
+    declaration: &'static str,
+}
+
+impl Column {
+    /// Creates a column from its name and its SQL declaration (e.g. `TEXT NOT NULL`).
+    pub fn new(name: &'static str, declaration: &'static str) -> Self {
+        Self { name, declaration }
+    }
+
+    /// Returns the name of the column.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the SQL declaration of the column.
+    pub fn declaration(&self) -> &'static str {
+        self.declaration
+    }
+
+    /// Returns the (uppercase) words of the declaration, outside of its string
+    /// literals, along with their depth in parentheses, e.g. 1 for the length
+    /// of `VARCHAR(20)` or the condition of a `CHECK (...)`.
+    fn words(&self) -> impl Iterator<Item = (usize, String)> {
+        let mut words = Vec::new();
+        let mut word = String::new();
+        let mut depth = 0_usize;
+        let mut quote = None;
+        for c in self.declaration.chars() {
+            if let Some(end) = quote {
+                if c == end {
+                    quote = None;
+                }
+                continue;
+            }
+            if c.is_ascii_alphanumeric() || c == '_' {
+                word.push(c.to_ascii_uppercase());
+                continue;
+            }
+            if !word.is_empty() {
+                words.push((depth, std::mem::take(&mut word)));
+            }
+            match c {
+                '\'' | '"' => quote = Some(c),
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if !word.is_empty() {
+            words.push((depth, word));
+        }
+        words.into_iter()
+    }
+
+    /// Returns `true` unless the column is declared `NOT NULL` or `PRIMARY KEY`.
+    pub fn is_nullable(&self) -> bool {
+        let words = self
+            .words()
+            .filter(|(depth, _)| *depth == 0)
+            .map(|(_, word)| word)
+            .collect::<Vec<_>>();
+        !words
+            .windows(2)
+            .any(|w| w == ["NOT", "NULL"] || w == ["PRIMARY", "KEY"])
+    }
+
+    /// Returns the maximum length of a `VARCHAR(n)` or `CHAR(n)` column.
+    pub fn max_len(&self) -> Option<usize> {
+        let mut words = self.words().map(|(_, word)| word);
+        let kind = words.next()?;
+        if !kind.ends_with("CHAR") && kind != "CHARACTER" {
+            return None;
+        }
+        words.find_map(|word| word.parse().ok())
+    }
+
+    /// Returns the range of the values of an integer column narrower than
+    /// 64 bits.
+    pub fn integer_range(&self) -> Option<RangeInclusive<i64>> {
+        match self.words().next()?.1.as_str() {
+            "TINYINT" => Some(i8::MIN.into()..=i8::MAX.into()),
+            "SMALLINT" | "INT2" => Some(i16::MIN.into()..=i16::MAX.into()),
+            "INT" | "INTEGER" | "INT4" | "MEDIUMINT" => Some(i32::MIN.into()..=i32::MAX.into()),
+            _ => None,
+        }
+    }
+}
+
+const WORDS: &[&str] = &[
+    "lorem",
+    "ipsum",
+    "dolor",
+    "sit",
+    "amet",
+    "consectetur",
+    "adipiscing",
+    "elit",
+    "sed",
+    "do",
+    "eiusmod",
+    "tempor",
+    "incididunt",
+    "ut",
+    "labore",
+    "et",
+    "dolore",
+    "magna",
+    "aliqua",
+    "enim",
+    "ad",
+    "minim",
+    "veniam",
+    "quis",
+    "nostrud",
+    "exercitation",
+    "ullamco",
+    "laboris",
+    "nisi",
+    "aliquip",
+    "ex",
+    "ea",
+    "commodo",
+    "consequat",
+];
+
+/// Source of the random values of a [`Generator`], along with the number of
+/// the entity being built.
+#[derive(Debug, Clone)]
+pub struct Faker {
+    rng: fastrand::Rng,
+    sequence: u64,
+}
+
+impl Faker {
+    fn new(rng: fastrand::Rng) -> Self {
+        Self { rng, sequence: 0 }
+    }
+
+    /// Returns the number of the entity being built, starting from 1.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns a random boolean.
+    pub fn bool(&mut self) -> bool {
+        self.rng.bool()
+    }
+
+    /// Returns a random integer in `range`.
+    pub fn integer(&mut self, range: RangeInclusive<i64>) -> i64 {
+        self.rng.i64(range)
+    }
+
+    /// Returns a random number between 0 and 1000, with two decimals.
+    pub fn float(&mut self) -> f64 {
+        self.rng.u32(0..=100_000) as f64 / 100.0
+    }
+
+    /// Returns a random UNIX timestamp, in seconds, between 2000-01-01 and
+    /// 2030-12-31.
+    pub fn timestamp(&mut self) -> i64 {
+        self.rng.i64(946_684_800..=1_924_991_999)
+    }
+
+    /// Returns a random element of `choices`, which must not be empty.
+    pub fn choice<'a, T>(&mut self, choices: &'a [T]) -> &'a T {
+        &choices[self.rng.usize(..choices.len())]
+    }
+
+    /// Returns a random sentence of at most `max_len` characters.
+    pub fn sentence(&mut self, max_len: usize) -> String {
+        let mut sentence = String::new();
+        for _ in 0..self.rng.usize(2..=6) {
+            let word = *self.choice(WORDS);
+            let len = sentence.len() + word.len() + usize::from(!sentence.is_empty());
+            if len > max_len {
+                break;
+            }
+            if !sentence.is_empty() {
+                sentence.push(' ');
+            }
+            sentence.push_str(word);
+        }
+        sentence
+    }
+}
+
+impl Fake for bool {
+    fn fake(faker: &mut Faker, _: &Column) -> Self {
+        faker.bool()
+    }
+}
+
+macro_rules! fake_integer {
+    ($($ty: ty),*) => {
+        $(
+            impl Fake for $ty {
+                fn fake(faker: &mut Faker, column: &Column) -> Self {
+                    // small positive numbers, unless the column is narrower
+                    let range = column.integer_range().unwrap_or(0..=10_000);
+                    let max = i64::try_from(<$ty>::MAX).unwrap_or(i64::MAX);
+                    let max = (*range.end()).min(10_000).min(max);
+                    faker.integer(0..=max) as $ty
+                }
+            }
+        )*
+    };
+}
+
+fake_integer!(i8, i16, i32, i64, u8, u16, u32, u64, isize, usize);
+
+impl Fake for f32 {
+    fn fake(faker: &mut Faker, _: &Column) -> Self {
+        faker.float() as f32
+    }
+}
+
+impl Fake for f64 {
+    fn fake(faker: &mut Faker, _: &Column) -> Self {
+        faker.float()
+    }
+}
+
+impl Fake for String {
+    fn fake(faker: &mut Faker, column: &Column) -> Self {
+        faker.sentence(column.max_len().unwrap_or(60))
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl Fake for sqlx::types::Decimal {
+    fn fake(faker: &mut Faker, _: &Column) -> Self {
+        Self::new(faker.rng.i64(0..=100_000), 2)
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod with_chrono {
+    use super::{Column, Fake, Faker};
+    use sqlx::types::chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+    impl Fake for DateTime<Utc> {
+        fn fake(faker: &mut Faker, _: &Column) -> Self {
+            DateTime::from_timestamp(faker.timestamp(), 0).unwrap_or_default()
+        }
+    }
+
+    impl Fake for NaiveDateTime {
+        fn fake(faker: &mut Faker, column: &Column) -> Self {
+            DateTime::<Utc>::fake(faker, column).naive_utc()
+        }
+    }
+
+    impl Fake for NaiveDate {
+        fn fake(faker: &mut Faker, column: &Column) -> Self {
+            NaiveDateTime::fake(faker, column).date()
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod with_time {
+    use super::{Column, Fake, Faker};
+    use sqlx::types::time::{Date, OffsetDateTime, PrimitiveDateTime};
+
+    impl Fake for OffsetDateTime {
+        fn fake(faker: &mut Faker, _: &Column) -> Self {
+            OffsetDateTime::from_unix_timestamp(faker.timestamp())
+                .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+        }
+    }
+
+    impl Fake for PrimitiveDateTime {
+        fn fake(faker: &mut Faker, column: &Column) -> Self {
+            let datetime = OffsetDateTime::fake(faker, column);
+            PrimitiveDateTime::new(datetime.date(), datetime.time())
+        }
+    }
+
+    impl Fake for Date {
+        fn fake(faker: &mut Faker, column: &Column) -> Self {
+            OffsetDateTime::fake(faker, column).date()
+        }
+    }
+}
+
+impl<T: Fake> Fake for Option<T> {
+    fn fake(faker: &mut Faker, column: &Column) -> Self {
+        if column.is_nullable() && faker.rng.u8(..4) == 0 {
+            None
+        } else {
+            Some(T::fake(faker, column))
+        }
+    }
+}
+
+type Override<E> = Box<dyn Fn(&mut E, u64) + Send + Sync>;
+
+/// Builds random entities, numbered from 1, applying the overrides to each
+/// of them.
+pub struct Generator<E> {
+    faker: Faker,
+    overrides: Vec<Override<E>>,
+}
+
+impl<E: Factory> Default for Generator<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Factory> Generator<E> {
+    /// Creates a generator with a random seed.
+    pub fn new() -> Self {
+        Self::from_faker(Faker::new(fastrand::Rng::new()))
+    }
+
+    /// Creates a generator with a fixed seed, which always builds the same
+    /// entities.
+    pub fn seeded(seed: u64) -> Self {
+        Self::from_faker(Faker::new(fastrand::Rng::with_seed(seed)))
+    }
+
+    fn from_faker(faker: Faker) -> Self {
+        let overrides = Vec::new();
+        Self { faker, overrides }
+    }
+
+    /// Applies `f` to each entity built from now on.
+    pub fn with(self, f: impl Fn(&mut E) + Send + Sync + 'static) -> Self {
+        self.sequence(move |entity, _| f(entity))
+    }
+
+    /// Applies `f` to each entity built from now on, along with its number.
+    pub fn sequence(mut self, f: impl Fn(&mut E, u64) + Send + Sync + 'static) -> Self {
+        self.overrides.push(Box::new(f));
+        self
+    }
+
+    /// Builds the next entity.
+    pub fn build(&mut self) -> E {
+        self.faker.sequence += 1;
+        let mut entity = E::fake(&mut self.faker);
+        for f in &self.overrides {
+            f(&mut entity, self.faker.sequence);
+        }
+        entity
+    }
+
+    /// Builds the next `count` entities.
+    pub fn build_many(&mut self, count: usize) -> Vec<E> {
+        (0..count).map(|_| self.build()).collect()
+    }
+
+    /// Builds the next entity and creates it into `store`.
+    pub async fn create<S: Create<E> + Sync>(&mut self, store: &S) -> sqlx::Result<WithId<E>> {
+        let entity = self.build();
+        store.create(entity).await
+    }
+
+    /// Builds the next `count` entities and creates them into `store`, one
+    /// after the other.
+    pub async fn create_many<S: Create<E> + Sync>(
+        &mut self,
+        store: &S,
+        count: usize,
+    ) -> sqlx::Result<Vec<WithId<E>>> {
+        let mut created = Vec::with_capacity(count);
+        for _ in 0..count {
+            created.push(self.create(store).await?);
+        }
+        Ok(created)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Column, Factory, Fake, Faker, Generator};
+
+    #[test]
+    fn columns() {
+        let column = |declaration| Column::new("x", declaration);
+        assert!(column("TEXT").is_nullable());
+        assert!(!column("TEXT NOT NULL").is_nullable());
+        assert!(!column("integer not null default 0").is_nullable());
+        assert!(column("TEXT DEFAULT 'NOT NULL'").is_nullable());
+        assert!(column("TEXT CHECK (x IS NOT NULL OR y IS NULL)").is_nullable());
+        assert!(!column("TEXT CHECK (x <> '') NOT NULL").is_nullable());
+        assert_eq!(column("VARCHAR (20) NOT NULL").max_len(), Some(20));
+        assert_eq!(column("character varying(8)").max_len(), Some(8));
+        assert_eq!(column("TEXT NOT NULL").max_len(), None);
+        assert_eq!(column("SMALLINT").integer_range(), Some(-32768..=32767));
+        assert_eq!(column("BIGINT NOT NULL").integer_range(), None);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Row {
+        name: String,
+        count: i16,
+        note: Option<String>,
+        required: Option<i64>,
+    }
+
+    impl Factory for Row {
+        fn fake(faker: &mut Faker) -> Self {
+            Row {
+                name: Fake::fake(faker, &Column::new("name", "VARCHAR(12) NOT NULL")),
+                count: Fake::fake(faker, &Column::new("count", "SMALLINT NOT NULL")),
+                note: Fake::fake(faker, &Column::new("note", "TEXT")),
+                required: Fake::fake(faker, &Column::new("required", "BIGINT NOT NULL")),
+            }
+        }
+    }
+
+    #[test]
+    fn valid_and_reproducible() {
+        let rows = Generator::<Row>::seeded(7).build_many(100);
+        assert!(rows.iter().all(|row| row.name.len() <= 12));
+        assert!(rows.iter().all(|row| row.count >= 0));
+        assert!(rows.iter().all(|row| row.required.is_some()));
+        assert!(rows.iter().any(|row| row.note.is_none()));
+        assert!(rows.iter().any(|row| row.note.is_some()));
+        assert_eq!(rows, Generator::<Row>::seeded(7).build_many(100));
+    }
+
+    #[cfg(all(feature = "chrono", feature = "time", feature = "rust_decimal"))]
+    #[test]
+    fn dates_and_decimals() {
+        use sqlx::types::{chrono::NaiveDate, time::OffsetDateTime, Decimal};
+
+        let mut faker = super::Faker::new(fastrand::Rng::with_seed(7));
+        let column = Column::new("x", "DATE NOT NULL");
+        for _ in 0..100 {
+            let date = NaiveDate::fake(&mut faker, &column);
+            assert!(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap() <= date);
+            assert!(date <= NaiveDate::from_ymd_opt(2030, 12, 31).unwrap());
+            let datetime = OffsetDateTime::fake(&mut faker, &column);
+            assert!((2000..=2030).contains(&datetime.year()));
+            assert_eq!(Decimal::fake(&mut faker, &column).scale(), 2);
+        }
+    }
+
+    #[test]
+    fn overrides() {
+        let mut rows = Row::factory()
+            .with(|row| row.note = None)
+            .sequence(|row, n| row.name = format!("row {n}"));
+        let row = rows.build();
+        assert_eq!((row.name.as_str(), row.note), ("row 1", None));
+        assert_eq!(rows.build().name, "row 2");
+    }
+}
@@ -0,0 +1,496 @@
+use crate::prelude::{Create, Schema, Store, Table};
+use async_trait::async_trait;
+use serde::{
+    de::{DeserializeOwned, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::Value;
+use sqlx::Database;
+use std::{collections::HashMap, fmt, path::Path, sync::Mutex};
+
+/// Format of a fixture file.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">fixtures</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum FixtureFormat {
+    /// JSON (`.json` files)
+    Json,
+    /// YAML (`.yaml` or `.yml` files)
+    Yaml,
+    /// TOML (`.toml` files)
+    Toml,
+}
+
+impl FixtureFormat {
+    /// Returns the format of a file, according to its extension.
+    pub fn of_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "json" => Some(FixtureFormat::Json),
+            "yaml" | "yml" => Some(FixtureFormat::Yaml),
+            "toml" => Some(FixtureFormat::Toml),
+            _ => None,
+        }
+    }
+
+    fn parse(&self, content: &str) -> sqlx::Result<Document> {
+        let decode_error = |err: Box<dyn std::error::Error + Send + Sync>| sqlx::Error::Decode(err);
+        match self {
+            FixtureFormat::Json => {
+                serde_json::from_str(content).map_err(|e| decode_error(e.into()))
+            }
+            FixtureFormat::Yaml => {
+                serde_yaml::from_str(content).map_err(|e| decode_error(e.into()))
+            }
+            FixtureFormat::Toml => toml::from_str(content).map_err(|e| decode_error(e.into())),
+        }
+    }
+}
+
+/// Seeds the tables of several stores with the records of fixture files.
+///
+/// A fixture file maps the name of each table to its records, either as a
+/// list, or as a map giving a name to each record. The fields of a record may
+/// refer to the id of a named record, possibly of another table or loaded by a
+/// previous file, as `"@name"` (a string actually starting with `@` is written
+/// `"@@..."`). The records are inserted in the order of the file, except that
+/// a record referring to other records is delayed until they are inserted.
+///
+/// ```yaml
+/// todo:
+///   laundry:
+///     description: do the laundry
+///     done: false
+///   dishes:
+///     description: wash the dishes
+///     done: true
+/// comment:
+///   - todo_id: "@dishes"
+///     text: the blue plate is chipped
+/// ```
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">fixtures</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+///
+/// # Example
+///
+/// ```no_run
+/// use miniorm::{prelude::*, Fixtures};
+/// use serde::{Deserialize, Serialize};
+/// use sqlx::FromRow;
+///
+/// #[derive(Debug, Clone, FromRow, Entity, Serialize, Deserialize)]
+/// struct Todo {
+///     #[postgres(TEXT NOT NULL)]
+///     description: String,
+///     #[postgres(BOOLEAN NOT NULL)]
+///     done: bool,
+/// }
+///
+/// #[derive(Debug, Clone, FromRow, Entity, Serialize, Deserialize)]
+/// struct Comment {
+///     #[postgres(BIGINT NOT NULL REFERENCES todo(id))]
+///     todo_id: i64,
+///     #[postgres(TEXT NOT NULL)]
+///     text: String,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = sqlx::PgPool::connect("postgres://localhost/todos").await?;
+/// let fixtures = Fixtures::new()
+///     .with_store(Store::<_, Todo>::new(db.clone()))
+///     .with_store(Store::<_, Comment>::new(db));
+///
+/// fixtures.reset().await?;
+/// fixtures.load("tests/fixtures/todos.yaml").await?;
+/// let dishes = fixtures.id("dishes").unwrap();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Fixtures {
+    tables: Vec<Box<dyn Seed>>,
+    /// ids of the named records loaded since the last reset
+    ids: Mutex<HashMap<String, i64>>,
+}
+
+impl Fixtures {
+    /// Creates fixtures without any table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the table of `store`, named after the entity (see
+    /// [`Schema::MINIORM_TABLE_NAME`]) in the fixture files.
+    ///
+    /// The tables are created in the order they are added, and dropped in the
+    /// reverse order: the tables referenced by foreign keys should be added first.
+    pub fn with_store<DB, E>(mut self, store: Store<DB, E>) -> Self
+    where
+        DB: Database,
+        E: Schema<DB> + DeserializeOwned + Send + Sync + 'static,
+        Store<DB, E>: Create<E> + Table<DB>,
+    {
+        self.tables.push(Box::new(store));
+        self
+    }
+
+    /// Returns the id of the record named `name`, loaded since the last reset.
+    pub fn id(&self, name: &str) -> Option<i64> {
+        self.ids_mut().get(name).copied()
+    }
+
+    /// Drops and recreates all the tables, so that they are empty and their ids
+    /// start from 1 again, and forgets the ids of the loaded records.
+    pub async fn reset(&self) -> sqlx::Result<()> {
+        for table in self.tables.iter().rev() {
+            table.drop_table().await?;
+        }
+        for table in &self.tables {
+            table.create_table().await?;
+        }
+        self.ids_mut().clear();
+        Ok(())
+    }
+
+    /// Loads a fixture file, whose format is given by its extension (see
+    /// [`FixtureFormat::of_path`]), and returns the ids of its named records.
+    ///
+    /// The file is read with [`tokio::fs`], and thus must be loaded within a
+    /// Tokio runtime.
+    pub async fn load(&self, path: impl AsRef<Path>) -> sqlx::Result<HashMap<String, i64>> {
+        let path = path.as_ref();
+        let format = FixtureFormat::of_path(path).ok_or_else(|| {
+            sqlx::Error::Decode(format!("unknown fixture format of {}", path.display()).into())
+        })?;
+        let content = tokio::fs::read_to_string(path).await?;
+        self.load_str(format, &content).await
+    }
+
+    /// Loads fixtures from a string, and returns the ids of its named records.
+    ///
+    /// If a record cannot be inserted, the ones inserted before it are kept.
+    pub async fn load_str(
+        &self,
+        format: FixtureFormat,
+        content: &str,
+    ) -> sqlx::Result<HashMap<String, i64>> {
+        let mut pending = self.records(format.parse(content)?)?;
+        let mut loaded = HashMap::new();
+        while !pending.is_empty() {
+            let (record, value) = {
+                let ids = self.ids_mut();
+                let ready = pending
+                    .iter()
+                    .position(|record| record.refs.iter().all(|name| ids.contains_key(name)))
+                    .ok_or_else(|| {
+                        let names = pending.iter().filter_map(|record| record.name.as_deref());
+                        let names = names.collect::<Vec<_>>().join(", ");
+                        decode_error(format!("circular references between {names}"))
+                    })?;
+                let mut record = pending.remove(ready);
+                let value = resolve(std::mem::take(&mut record.value), &ids);
+                (record, value)
+            };
+            let id = self.tables[record.table].insert(value).await?;
+            if let Some(name) = record.name {
+                self.ids_mut().insert(name.clone(), id);
+                loaded.insert(name, id);
+            }
+        }
+        Ok(loaded)
+    }
+
+    fn ids_mut(&self) -> std::sync::MutexGuard<'_, HashMap<String, i64>> {
+        // a panic cannot leave the ids in an inconsistent state
+        self.ids.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Returns the records of a document, checking their tables and references.
+    fn records(&self, document: Document) -> sqlx::Result<Vec<Record>> {
+        let mut records = Vec::new();
+        for (table, entries) in document.0 {
+            let index = self
+                .tables
+                .iter()
+                .position(|t| t.table_name() == table)
+                .ok_or_else(|| decode_error(format!("unknown table '{table}'")))?;
+            let entries: Vec<_> = match entries {
+                Entries::Named(entries) => entries
+                    .into_iter()
+                    .map(|(name, value)| (Some(name), value))
+                    .collect(),
+                Entries::Anonymous(entries) => entries.into_iter().map(|v| (None, v)).collect(),
+            };
+            for (name, value) in entries {
+                let mut refs = Vec::new();
+                references(&value, &mut refs);
+                records.push(Record {
+                    table: index,
+                    name,
+                    value,
+                    refs,
+                });
+            }
+        }
+
+        let ids = self.ids_mut();
+        let mut names = ids.keys().collect::<Vec<_>>();
+        for name in records.iter().filter_map(|record| record.name.as_ref()) {
+            if names.contains(&name) {
+                return Err(decode_error(format!("duplicate record '{name}'")));
+            }
+            names.push(name);
+        }
+        for name in records.iter().flat_map(|record| &record.refs) {
+            if !names.contains(&name) {
+                return Err(decode_error(format!("unknown record '@{name}'")));
+            }
+        }
+        drop(ids);
+        Ok(records)
+    }
+}
+
+fn decode_error(message: String) -> sqlx::Error {
+    sqlx::Error::Decode(message.into())
+}
+
+/// Returns the name of the record referenced by a string, if any.
+fn reference(value: &str) -> Option<&str> {
+    value
+        .strip_prefix('@')
+        .filter(|name| !name.starts_with('@'))
+}
+
+/// Collects the names of the records referenced by a value.
+fn references(value: &Value, refs: &mut Vec<String>) {
+    match value {
+        Value::String(value) => refs.extend(reference(value).map(String::from)),
+        Value::Array(values) => values.iter().for_each(|value| references(value, refs)),
+        Value::Object(values) => values.values().for_each(|value| references(value, refs)),
+        _ => {}
+    }
+}
+
+/// Replaces the references of a value by the ids of the records, and unescapes
+/// the strings starting with `@@`.
+fn resolve(value: Value, ids: &HashMap<String, i64>) -> Value {
+    match value {
+        Value::String(value) => match reference(&value) {
+            Some(name) => ids[name].into(),
+            None => match value.strip_prefix('@') {
+                Some(value) => value.into(),
+                None => value.into(),
+            },
+        },
+        Value::Array(values) => values.into_iter().map(|v| resolve(v, ids)).collect(),
+        Value::Object(values) => Value::Object(
+            values
+                .into_iter()
+                .map(|(k, v)| (k, resolve(v, ids)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// A record of a fixture file.
+struct Record {
+    /// index of the table in [`Fixtures::tables`]
+    table: usize,
+    name: Option<String>,
+    value: Value,
+    /// names of the records it refers to
+    refs: Vec<String>,
+}
+
+/// Type-erased store of a table of the fixtures.
+#[async_trait]
+trait Seed: Send + Sync {
+    fn table_name(&self) -> &'static str;
+    async fn insert(&self, record: Value) -> sqlx::Result<i64>;
+    async fn create_table(&self) -> sqlx::Result<()>;
+    async fn drop_table(&self) -> sqlx::Result<()>;
+}
+
+#[async_trait]
+impl<DB, E> Seed for Store<DB, E>
+where
+    DB: Database,
+    E: Schema<DB> + DeserializeOwned + Send + Sync + 'static,
+    Store<DB, E>: Create<E> + Table<DB>,
+{
+    fn table_name(&self) -> &'static str {
+        E::MINIORM_TABLE_NAME
+    }
+
+    async fn insert(&self, record: Value) -> sqlx::Result<i64> {
+        let table = E::MINIORM_TABLE_NAME;
+        let entity = serde_json::from_value(record)
+            .map_err(|err| decode_error(format!("invalid record of '{table}': {err}")))?;
+        Ok(self.create(entity).await?.id())
+    }
+
+    async fn create_table(&self) -> sqlx::Result<()> {
+        Table::create_table(self).await.map(|_| ())
+    }
+
+    async fn drop_table(&self) -> sqlx::Result<()> {
+        Table::drop_table(self).await.map(|_| ())
+    }
+}
+
+/// Tables of a fixture file and their records, in the order of the file.
+struct Document(Vec<(String, Entries)>);
+
+/// Records of a table, in the order of the file.
+enum Entries {
+    Named(Vec<(String, Value)>),
+    Anonymous(Vec<Value>),
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DocumentVisitor;
+
+        impl<'de> Visitor<'de> for DocumentVisitor {
+            type Value = Document;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of tables")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Document, A::Error> {
+                let mut tables = Vec::new();
+                while let Some(table) = map.next_entry()? {
+                    tables.push(table);
+                }
+                Ok(Document(tables))
+            }
+        }
+
+        deserializer.deserialize_map(DocumentVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of records or a map of named records")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Entries, A::Error> {
+                let mut records = Vec::new();
+                while let Some(record) = seq.next_element()? {
+                    records.push(record);
+                }
+                Ok(Entries::Anonymous(records))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+                let mut records = Vec::new();
+                while let Some(record) = map.next_entry()? {
+                    records.push(record);
+                }
+                Ok(Entries::Named(records))
+            }
+        }
+
+        deserializer.deserialize_any(EntriesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{references, resolve, Document, Entries, FixtureFormat};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn tables(document: &Document) -> Vec<(&str, Vec<Option<&str>>)> {
+        fn names(entries: &Entries) -> Vec<Option<&str>> {
+            match entries {
+                Entries::Named(entries) => entries.iter().map(|(n, _)| Some(n.as_str())).collect(),
+                Entries::Anonymous(entries) => entries.iter().map(|_| None).collect(),
+            }
+        }
+
+        let tables = document.0.iter();
+        tables
+            .map(|(table, e)| (table.as_str(), names(e)))
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        let expected = [
+            ("todo", vec![Some("zebra"), Some("ant")]),
+            ("comment", vec![None, None]),
+        ];
+        let json = r#"{
+            "todo": {"zebra": {"description": "z"}, "ant": {"description": "a"}},
+            "comment": [{"text": "first"}, {"text": "second"}]
+        }"#;
+        let yaml = "
+todo:
+  zebra: {description: z}
+  ant: {description: a}
+comment:
+  - text: first
+  - text: second
+";
+        let toml = r#"
+[todo.zebra]
+description = "z"
+[todo.ant]
+description = "a"
+[[comment]]
+text = "first"
+[[comment]]
+text = "second"
+"#;
+        for (format, content) in [
+            (FixtureFormat::Json, json),
+            (FixtureFormat::Yaml, yaml),
+            (FixtureFormat::Toml, toml),
+        ] {
+            let document = format.parse(content).unwrap();
+            assert_eq!(tables(&document), expected, "{format:?}");
+        }
+        assert!(FixtureFormat::Yaml.parse("- todo").is_err());
+        assert_eq!(FixtureFormat::of_path("a/b.yml"), Some(FixtureFormat::Yaml));
+        assert_eq!(FixtureFormat::of_path("a/b.txt"), None);
+    }
+
+    #[test]
+    fn references_and_resolve() {
+        let value = json!({"todo_id": "@dishes", "text": "@@home", "tags": ["@laundry", 1]});
+        let mut refs = Vec::new();
+        references(&value, &mut refs);
+        assert_eq!(refs, ["laundry", "dishes"]);
+
+        let ids = HashMap::from([("dishes".to_string(), 2), ("laundry".to_string(), 1)]);
+        assert_eq!(
+            resolve(value, &ids),
+            json!({"todo_id": 2, "text": "@home", "tags": [1, 1]})
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use axum::http::{header::IF_NONE_MATCH, HeaderMap, HeaderName, HeaderValue};
+use serde::Serialize;
+
+/// Computes a strong entity tag for the JSON representation of `value`,
+/// using the 64-bit FNV-1a hash of its serialization.
+pub(crate) fn etag<T: Serialize>(value: &T) -> serde_json::Result<HeaderValue> {
+    let bytes = serde_json::to_vec(value)?;
+    let hash = bytes.iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    });
+    let etag = HeaderValue::from_str(&format!("\"{hash:016x}\"")).expect("valid header value");
+    Ok(etag)
+}
+
+/// Returns `true` if one of the values of the `header` (e.g. `If-Match`)
+/// matches `etag`, or if the header is `*`.
+///
+/// As required by RFC 9110, weak tags (`W/"..."`) are compared as if they were
+/// strong for `If-None-Match`, but never match for `If-Match`.
+pub(crate) fn matches(headers: &HeaderMap, header: HeaderName, etag: &HeaderValue) -> bool {
+    let weak = header == IF_NONE_MATCH;
+    let etag = etag.to_str().unwrap_or_default();
+    headers
+        .get_all(header)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .map(|tag| {
+            if weak {
+                tag.trim_start_matches("W/")
+            } else {
+                tag
+            }
+        })
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{etag, matches};
+    use axum::http::{
+        header::{IF_MATCH, IF_NONE_MATCH},
+        HeaderMap, HeaderName, HeaderValue,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn etag_depends_on_content() {
+        let first = etag(&json!({"id": 1, "done": false})).unwrap();
+        let second = etag(&json!({"id": 1, "done": true})).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first, etag(&json!({"id": 1, "done": false})).unwrap());
+        assert!(first.to_str().unwrap().starts_with('"'));
+    }
+
+    #[test]
+    fn matching() {
+        let etag = etag(&json!({"id": 1})).unwrap();
+        let headers = |header: HeaderName, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        let tag = etag.to_str().unwrap();
+        for header in [IF_MATCH, IF_NONE_MATCH] {
+            let matching =
+                |value: &str| matches(&headers(header.clone(), value), header.clone(), &etag);
+            assert!(matching(tag));
+            assert!(matching(&format!("\"nope\", {tag}")));
+            assert!(matching("*"));
+            assert!(!matching("\"nope\""));
+            assert!(!matches(&HeaderMap::new(), header.clone(), &etag));
+        }
+    }
+
+    #[test]
+    fn weak_matching() {
+        let etag = etag(&json!({"id": 1})).unwrap();
+        let weak = format!("W/{}", etag.to_str().unwrap());
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_MATCH, HeaderValue::from_str(&weak).unwrap());
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_str(&weak).unwrap());
+        assert!(!matches(&headers, IF_MATCH, &etag));
+        assert!(matches(&headers, IF_NONE_MATCH, &etag));
+    }
+}
@@ -0,0 +1,87 @@
+use axum::response::sse;
+use futures::Stream;
+use std::convert::Infallible;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Number of events kept for the subscribers which are lagging behind;
+/// older events are dropped.
+const CAPACITY: usize = 1024;
+
+/// A change made to one of the entities, as sent to the subscribers of `GET /events`.
+#[derive(Debug, Clone)]
+pub(crate) struct Event {
+    /// `create`, `update`, `delete` or `delete_all`
+    kind: &'static str,
+    /// JSON payload of the event
+    data: String,
+}
+
+/// Changes of the entities of a store, broadcast to all subscribers.
+pub(crate) struct Events {
+    sender: broadcast::Sender<Event>,
+    /// changes notified by the database, which replace the ones of the handler
+    #[cfg(feature = "changes")]
+    feed: std::sync::Mutex<Option<Feed>>,
+    from_database: bool,
+}
+
+#[cfg(feature = "changes")]
+pub(crate) type Feed = futures::stream::BoxStream<'static, sqlx::Result<crate::Change>>;
+
+impl Events {
+    pub(crate) fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self {
+            sender,
+            #[cfg(feature = "changes")]
+            feed: std::sync::Mutex::new(None),
+            from_database: false,
+        }
+    }
+
+    /// Broadcasts the changes notified by the database instead of the ones
+    /// made through the handler.
+    #[cfg(feature = "changes")]
+    pub(crate) fn from_database(feed: Feed) -> Self {
+        Self {
+            feed: std::sync::Mutex::new(Some(feed)),
+            from_database: true,
+            ..Self::new()
+        }
+    }
+
+    /// Returns the feed of changes of the database, if any, the first time
+    /// it is called.
+    #[cfg(feature = "changes")]
+    pub(crate) fn take_feed(&self) -> Option<Feed> {
+        self.feed.lock().ok()?.take()
+    }
+
+    /// Returns `true` if the changes made through the handler should be sent,
+    /// i.e. if someone is listening and the database does not notify them.
+    pub(crate) fn is_listened(&self) -> bool {
+        !self.from_database && self.sender.receiver_count() > 0
+    }
+
+    pub(crate) fn send(&self, kind: &'static str, data: String) {
+        // failing means that nobody is listening anymore
+        let _ = self.sender.send(Event { kind, data });
+    }
+
+    /// Returns the stream of events sent from now on, skipping the ones
+    /// missed when lagging behind.
+    pub(crate) fn subscribe(&self) -> impl Stream<Item = Result<sse::Event, Infallible>> {
+        futures::stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        let event = sse::Event::default().event(event.kind).data(event.data);
+                        return Some((Ok(event), receiver));
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
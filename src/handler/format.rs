@@ -0,0 +1,305 @@
+use crate::{csv, WithId};
+use axum::http::{header::ACCEPT, HeaderMap};
+use serde::Serialize;
+use serde_json::Value;
+
+/// An entity serialized in the representation chosen for the router, i.e.
+/// nested (see [`WithId`]) or flattened (see [`Flat`](crate::Flat)).
+pub(crate) struct Repr<'a, E> {
+    pub(crate) entity: &'a WithId<E>,
+    pub(crate) flat: bool,
+}
+
+impl<E> Clone for Repr<'_, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for Repr<'_, E> {}
+
+impl<E: Serialize> Serialize for Repr<'_, E> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.flat {
+            self.entity.serialize_flat(serializer)
+        } else {
+            self.entity.serialize(serializer)
+        }
+    }
+}
+
+/// Representation of a list of entities, negotiated using the `Accept`
+/// or `Content-Type` headers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Format {
+    /// one JSON array
+    Json,
+    /// one JSON object per line
+    Ndjson,
+    /// one header line with the names of the columns, then one line per entity
+    Csv,
+}
+
+impl Format {
+    pub(crate) const NDJSON: &'static str = "application/x-ndjson";
+    pub(crate) const CSV: &'static str = "text/csv";
+
+    fn parse(media_type: &str) -> Option<Self> {
+        let media_type = media_type.split(';').next().unwrap_or_default().trim();
+        match media_type {
+            "application/json" | "application/*" | "*/*" => Some(Format::Json),
+            Self::NDJSON | "application/jsonl" => Some(Format::Ndjson),
+            Self::CSV => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    /// Returns the weight of a media type, given by its `q` parameter.
+    fn quality(media_type: &str) -> f32 {
+        media_type
+            .split(';')
+            .skip(1)
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse().ok())
+            .unwrap_or(1.0)
+    }
+
+    /// Returns the supported format with the highest weight in the `Accept`
+    /// header, the first one listed among those of equal weight, defaulting
+    /// to JSON. The formats of weight `q=0` are not acceptable.
+    pub(crate) fn accepted(headers: &HeaderMap) -> Self {
+        headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|media_type| Some((Self::parse(media_type)?, Self::quality(media_type))))
+            .filter(|(_, quality)| *quality > 0.0)
+            .fold(
+                None,
+                |best: Option<(Self, f32)>, (format, quality)| match best {
+                    Some((_, best_quality)) if best_quality >= quality => best,
+                    _ => Some((format, quality)),
+                },
+            )
+            .map_or(Format::Json, |(format, _)| format)
+    }
+
+    /// Returns the format of a `Content-Type`, if supported.
+    pub(crate) fn of_content_type(content_type: &str) -> Option<Self> {
+        Self::parse(content_type).filter(|format| *format != Format::Json)
+    }
+
+    pub(crate) fn content_type(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Ndjson => Self::NDJSON,
+            Format::Csv => Self::CSV,
+        }
+    }
+
+    /// Returns what comes before the first entity (the header line of CSV)
+    pub(crate) fn header(&self, columns: &[&str]) -> String {
+        match self {
+            Format::Csv => csv::header(columns),
+            _ => String::new(),
+        }
+    }
+
+    /// Encodes one entity as one line (including the trailing newline).
+    pub(crate) fn line<E: Serialize>(
+        &self,
+        entity: Repr<'_, E>,
+        columns: &[&str],
+    ) -> serde_json::Result<String> {
+        match self {
+            Format::Json | Format::Ndjson => Ok(serde_json::to_string(&entity)? + "\n"),
+            Format::Csv => csv::record(entity.entity, columns),
+        }
+    }
+}
+
+/// Incremental decoder of an uploaded NDJSON or CSV body, which is fed with
+/// the chunks of the body as they arrive and returns the complete records
+/// as JSON objects, along with the line on which they start.
+pub(crate) struct Decoder<'a> {
+    format: Format,
+    schema: &'a Value,
+    buffer: Vec<u8>,
+    record: String,
+    header: Option<Vec<String>>,
+    line: usize,
+    start: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(format: Format, schema: &'a Value) -> Self {
+        Self {
+            format,
+            schema,
+            buffer: Vec::new(),
+            record: String::new(),
+            header: None,
+            line: 0,
+            start: 0,
+        }
+    }
+
+    /// Decodes the records completed by `chunk`.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Result<Vec<(usize, Value)>, String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut records = Vec::new();
+        let Some(last) = self.buffer.iter().rposition(|b| *b == b'\n') else {
+            return Ok(records);
+        };
+        let lines: Vec<u8> = self.buffer.drain(..=last).collect();
+        for line in lines.split_inclusive(|b| *b == b'\n') {
+            self.push_line(line, &mut records)?;
+        }
+        Ok(records)
+    }
+
+    /// Decodes the last record, which may not end with a newline.
+    pub(crate) fn finish(&mut self) -> Result<Vec<(usize, Value)>, String> {
+        let mut records = Vec::new();
+        let line = std::mem::take(&mut self.buffer);
+        if !line.is_empty() {
+            self.push_line(&line, &mut records)?;
+        }
+        if !self.record.is_empty() {
+            return Err(format!("line {}: unterminated quoted field", self.start));
+        }
+        Ok(records)
+    }
+
+    fn push_line(&mut self, line: &[u8], records: &mut Vec<(usize, Value)>) -> Result<(), String> {
+        self.line += 1;
+        let line =
+            std::str::from_utf8(line).map_err(|_| format!("line {}: invalid UTF-8", self.line))?;
+        if self.record.is_empty() {
+            self.start = self.line;
+        }
+        self.record.push_str(line);
+        let start = self.start;
+        let error = |err: String| format!("line {start}: {err}");
+
+        if self.format == Format::Csv {
+            let Some(fields) = csv::parse_record(&self.record) else {
+                // a quoted field continues on the next line
+                return Ok(());
+            };
+            self.record.clear();
+            if fields.len() == 1 && fields[0].trim().is_empty() {
+                return Ok(());
+            }
+            match &self.header {
+                None => self.header = Some(fields),
+                Some(header) => {
+                    let json = csv::to_json(header, fields, self.schema).map_err(error)?;
+                    records.push((start, json));
+                }
+            }
+        } else {
+            let record = std::mem::take(&mut self.record);
+            if record.trim().is_empty() {
+                return Ok(());
+            }
+            let json = serde_json::from_str(&record).map_err(|err| error(err.to_string()))?;
+            records.push((start, json));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Decoder, Format, Repr};
+    use crate::WithId;
+    use axum::http::{header::ACCEPT, HeaderMap, HeaderValue};
+    use serde::Serialize;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    struct Todo {
+        description: &'static str,
+        done: bool,
+    }
+
+    #[test]
+    fn accepted() {
+        let accepted = |accept: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, HeaderValue::from_static(accept));
+            Format::accepted(&headers)
+        };
+        assert_eq!(Format::accepted(&HeaderMap::new()), Format::Json);
+        assert_eq!(accepted("text/csv"), Format::Csv);
+        assert_eq!(
+            accepted("text/html, application/x-ndjson;q=0.9"),
+            Format::Ndjson
+        );
+        assert_eq!(accepted("application/json, text/csv"), Format::Json);
+        assert_eq!(accepted("text/csv;q=0.1, application/json"), Format::Json);
+        assert_eq!(
+            accepted("application/json;q=0.5, text/csv;q=0.8"),
+            Format::Csv
+        );
+        assert_eq!(
+            accepted("text/csv;q=0, application/x-ndjson;q=0.2"),
+            Format::Ndjson
+        );
+        assert_eq!(accepted("text/html"), Format::Json);
+    }
+
+    #[test]
+    fn lines() {
+        let columns = ["description", "done"];
+        assert_eq!(Format::Csv.header(&columns), "id,description,done\n");
+        assert_eq!(Format::Ndjson.header(&columns), "");
+        let todo = WithId::new(
+            Todo {
+                description: "buy milk, \"bread\"",
+                done: false,
+            },
+            3,
+        );
+        let nested = Repr {
+            entity: &todo,
+            flat: false,
+        };
+        let line = Format::Csv.line(nested, &columns).unwrap();
+        assert_eq!(line, "3,\"buy milk, \"\"bread\"\"\",false\n");
+        let line = Format::Ndjson.line(nested, &columns).unwrap();
+        assert!(line.starts_with("{\"id\":3,\"inner\":{"), "{line}");
+        let flat = Repr {
+            entity: &todo,
+            flat: true,
+        };
+        let line = Format::Ndjson.line(flat, &columns).unwrap();
+        assert!(line.starts_with("{\"id\":3,\"description\":"), "{line}");
+    }
+
+    #[test]
+    fn decode_chunks() {
+        let schema = json!({
+            "properties": {"description": {"type": "string"}, "done": {"type": "boolean"}},
+            "required": ["description", "done"],
+        });
+        let mut decoder = Decoder::new(Format::Csv, &schema);
+        assert_eq!(decoder.feed(b"description,done\nbuy ").unwrap(), []);
+        assert_eq!(
+            decoder.feed(b"milk,true\n\"multi\nline\",fal").unwrap(),
+            [(2, json!({"description": "buy milk", "done": true}))]
+        );
+        assert_eq!(decoder.feed(b"se").unwrap(), []);
+        assert_eq!(
+            decoder.finish().unwrap(),
+            [(3, json!({"description": "multi\nline", "done": false}))]
+        );
+
+        let mut decoder = Decoder::new(Format::Ndjson, &schema);
+        assert_eq!(decoder.feed(b"{\"done\": true}\n\n").unwrap().len(), 1);
+        let err = decoder.feed(b"{\"done\": \n").unwrap_err();
+        assert!(err.starts_with("line 3:"), "{err}");
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{csv, FilterOp, ListQuery};
+use serde_json::Value;
+
+impl ListQuery {
+    /// Parses the parameters of `GET /` from the query string, e.g.
+    /// `?limit=10&offset=20&sort=-done,description&done=false&description__like=buy%25`,
+    /// making sure that all the columns that are referred to are part of `columns`
+    /// (or `id`), and converting the values of the filters to the types of the
+    /// columns in `schema`.
+    pub(crate) fn parse(
+        params: &[(String, String)],
+        columns: &[&str],
+        schema: &Value,
+    ) -> Result<Self, String> {
+        let check_column = |column: &str| {
+            if column == "id" || columns.contains(&column) {
+                Ok(column.to_string())
+            } else {
+                Err(format!("unknown column '{column}'"))
+            }
+        };
+        let parse_number = |key: &str, value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("invalid value '{value}' for '{key}'"))
+        };
+
+        let mut query = ListQuery::new();
+        for (key, value) in params {
+            match key.as_str() {
+                "limit" => query = query.limit(parse_number(key, value)?),
+                "offset" => query = query.offset(parse_number(key, value)?),
+                "sort" => {
+                    for column in value.split(',').filter(|column| !column.is_empty()) {
+                        query = match column.strip_prefix('-') {
+                            Some(column) => query.sort_desc(check_column(column)?),
+                            None => query.sort(check_column(column)?),
+                        };
+                    }
+                }
+                _ => {
+                    let (column, op) = match key.rsplit_once("__") {
+                        Some((column, suffix)) => {
+                            let op = FilterOp::parse(suffix)
+                                .ok_or_else(|| format!("unknown filter '{key}'"))?;
+                            (column, op)
+                        }
+                        None => (key.as_str(), FilterOp::Eq),
+                    };
+                    let column = check_column(column)?;
+                    let value = filter_value(&column, op, value, schema)?;
+                    query = query.filter(column, op, value);
+                }
+            }
+        }
+        Ok(query)
+    }
+
+    /// Returns the value of the `Link` header pointing to the first, previous,
+    /// next and last pages, if the list is paginated using `limit`.
+    ///
+    /// - `path`: path of the current request
+    /// - `raw_query`: query string of the current request
+    /// - `total`: total number of entities matching the filters
+    pub(crate) fn links(&self, path: &str, raw_query: Option<&str>, total: u64) -> Option<String> {
+        let limit = self.limit.filter(|limit| *limit > 0)?;
+        let params = raw_query
+            .unwrap_or_default()
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .filter(|param| {
+                let key = param.split('=').next().unwrap_or_default();
+                key != "limit" && key != "offset"
+            })
+            .collect::<Vec<_>>();
+        let link = |offset: u64, rel: &str| {
+            let mut query = params.clone();
+            let limit = format!("limit={limit}");
+            let offset = format!("offset={offset}");
+            query.push(&limit);
+            query.push(&offset);
+            format!("<{path}?{}>; rel=\"{rel}\"", query.join("&"))
+        };
+
+        let last = total.saturating_sub(1) / limit * limit;
+        let mut links = vec![link(0, "first")];
+        if self.offset > 0 {
+            links.push(link(self.offset.saturating_sub(limit), "prev"));
+        }
+        if self.offset + limit < total {
+            links.push(link(self.offset + limit, "next"));
+        }
+        links.push(link(last, "last"));
+        Some(links.join(", "))
+    }
+}
+
+/// Converts the value of a filter to the type of its column, e.g. a boolean
+/// for `done=false`; an empty value is null for a nullable column.
+fn filter_value(column: &str, op: FilterOp, value: &str, schema: &Value) -> Result<Value, String> {
+    if column == "id" {
+        return value
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| format!("invalid id '{value}'"));
+    }
+    let property = &schema["properties"][column];
+    if op == FilterOp::Like {
+        let is_text = property.get("format").is_none()
+            && match &property["type"] {
+                Value::Array(types) => types.contains(&"string".into()),
+                ty => ty == "string",
+            };
+        if !is_text {
+            return Err(format!("'{column}__like' only applies to text columns"));
+        }
+        return Ok(Value::String(value.into()));
+    }
+    let mut object = csv::to_json(&[column.into()], vec![value.into()], schema)?;
+    Ok(object[column].take())
+}
+
+#[cfg(test)]
+mod test {
+    use super::ListQuery;
+    use crate::WithId;
+    use serde::Serialize;
+    use serde_json::{json, Value};
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    struct Todo {
+        description: &'static str,
+        done: bool,
+    }
+
+    const COLUMNS: &[&str] = &["description", "done"];
+
+    fn schema() -> Value {
+        json!({
+            "properties": {"description": {"type": "string"}, "done": {"type": "boolean"}},
+            "required": ["description", "done"],
+        })
+    }
+
+    fn parse(query: &str) -> Result<ListQuery, String> {
+        ListQuery::parse(&params(query), COLUMNS, &schema())
+    }
+
+    fn params(query: &str) -> Vec<(String, String)> {
+        query
+            .split('&')
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect()
+    }
+
+    fn todos() -> Vec<WithId<Todo>> {
+        [
+            ("buy milk", false),
+            ("walk the dog", true),
+            ("buy bread", true),
+            ("wash the dishes", false),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (description, done))| WithId::new(Todo { description, done }, i as i64 + 1))
+        .collect()
+    }
+
+    fn ids(query: &str) -> Vec<i64> {
+        let query = parse(query).unwrap();
+        let page = query.apply(todos()).unwrap();
+        page.items.iter().map(|todo| todo.id()).collect()
+    }
+
+    #[test]
+    fn parse_rejects_unknown_columns_and_filters() {
+        assert!(parse("nope=1").is_err());
+        assert!(parse("sort=-nope").is_err());
+        assert!(parse("done__nope=1").is_err());
+        assert!(parse("limit=x").is_err());
+        assert!(parse("done=maybe").is_err());
+        assert!(parse("done__like=t%").is_err());
+        assert!(parse("id=x").is_err());
+        assert!(parse("id__gt=1&sort=-id").is_ok());
+    }
+
+    #[test]
+    fn filter() {
+        assert_eq!(ids(""), [1, 2, 3, 4]);
+        assert_eq!(ids("done=false"), [1, 4]);
+        assert_eq!(ids("done__ne=false"), [2, 3]);
+        assert_eq!(ids("description__like=buy%"), [1, 3]);
+        assert!(ids("description__like=BUY%").is_empty());
+        assert_eq!(ids("description__like=%the%&done=true"), [2]);
+        assert_eq!(ids("id__gte=2&id__lt=4"), [2, 3]);
+    }
+
+    #[test]
+    fn sort() {
+        assert_eq!(ids("sort=description"), [3, 1, 2, 4]);
+        assert_eq!(ids("sort=-done,description"), [3, 2, 1, 4]);
+        assert_eq!(ids("sort=-id"), [4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn paginate() {
+        assert_eq!(ids("limit=2"), [1, 2]);
+        assert_eq!(ids("limit=2&offset=3"), [4]);
+        assert_eq!(ids("offset=1&sort=-id"), [3, 2, 1]);
+
+        let query = parse("done=false&limit=1").unwrap();
+        let page = query.apply(todos()).unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items.len(), 1);
+    }
+
+    #[test]
+    fn links() {
+        let query = parse("limit=2&offset=2").unwrap();
+        let links = query.links("/todos", Some("limit=2&offset=2&done=false"), 5);
+        assert_eq!(
+            links.unwrap(),
+            "</todos?done=false&limit=2&offset=0>; rel=\"first\", \
+            </todos?done=false&limit=2&offset=0>; rel=\"prev\", \
+            </todos?done=false&limit=2&offset=4>; rel=\"next\", \
+            </todos?done=false&limit=2&offset=4>; rel=\"last\""
+        );
+
+        let query = parse("done=false").unwrap();
+        assert_eq!(query.links("/todos", Some("done=false"), 5), None);
+    }
+}
@@ -0,0 +1,651 @@
+mod etag;
+mod events;
+mod format;
+mod list;
+mod patch;
+pub(crate) mod problem;
+pub(crate) mod router;
+
+use crate::{
+    traits::crud::{Batch, Crud},
+    ListQuery, WithId,
+};
+use axum::{
+    body::{Body, Bytes},
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        OriginalUri, Path, Query, State,
+    },
+    http::{
+        header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, LOCATION},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{
+        sse::{KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use events::Events;
+use format::{Decoder, Format, Repr};
+use futures::StreamExt;
+use patch::PatchError;
+use problem::{Problem, ProblemMapper};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{marker::PhantomData, sync::Arc};
+
+/// Number of entities created in one transaction by `POST /_import`
+const IMPORT_CHUNK_SIZE: usize = 1000;
+
+pub(crate) struct Handler<E, S> {
+    entity: PhantomData<fn() -> E>,
+    store: S,
+    columns: &'static [&'static str],
+    schema: Arc<Value>,
+    problems: ProblemMapper,
+    events: Arc<Events>,
+    flat: bool,
+}
+
+impl<E, S> Handler<E, S> {
+    pub(crate) fn new(store: S, columns: &'static [&'static str], json_schema: &str) -> Self {
+        let entity = PhantomData;
+        let schema = Arc::new(crate::json_schema::entity_schema(json_schema));
+        let problems = Arc::new(Problem::from_sqlx_error);
+        let events = Arc::new(Events::new());
+        Handler {
+            entity,
+            store,
+            columns,
+            schema,
+            problems,
+            events,
+            flat: false,
+        }
+    }
+
+    /// Replaces the mapping from the errors of the store to problems.
+    pub(crate) fn with_problems(mut self, problems: ProblemMapper) -> Self {
+        self.problems = problems;
+        self
+    }
+
+    /// Uses the flattened representation of the entities (see [`Flat`](crate::Flat)).
+    pub(crate) fn with_flat_ids(mut self) -> Self {
+        self.flat = true;
+        self
+    }
+
+    /// Serves the changes notified by the database on `GET /events`, instead
+    /// of the ones made through the handler.
+    #[cfg(feature = "changes")]
+    pub(crate) fn with_changes(mut self, feed: events::Feed) -> Self {
+        self.events = Arc::new(Events::from_database(feed));
+        self
+    }
+}
+
+impl<E, S: Clone> Clone for Handler<E, S> {
+    fn clone(&self) -> Self {
+        Handler {
+            entity: PhantomData,
+            store: self.store.clone(),
+            columns: self.columns,
+            schema: self.schema.clone(),
+            problems: self.problems.clone(),
+            events: self.events.clone(),
+            flat: self.flat,
+        }
+    }
+}
+
+/// Problem returned when the serialization of an entity fails
+fn serialization_problem(_: serde_json::Error) -> Problem {
+    Problem::new(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Problem returned when the `If-Match` header does not match the current
+/// version of the entity
+fn precondition_failed() -> Problem {
+    Problem::new(StatusCode::PRECONDITION_FAILED)
+        .with_detail("the entity was modified in the meantime")
+}
+
+/// Returns the check that the `If-Match` header matches the current version
+/// of an entity, passed to [`Update::update_if`](crate::traits::crud::Update::update_if)
+/// and [`Delete::delete_if`](crate::traits::crud::Delete::delete_if) so that the
+/// store checks it when writing; or `None` without `If-Match` header.
+fn if_match<E: Serialize>(
+    headers: &HeaderMap,
+) -> Option<impl Fn(&WithId<E>) -> bool + Send + Sync + 'static> {
+    if !headers.contains_key(IF_MATCH) {
+        return None;
+    }
+    let headers = headers.clone();
+    Some(move |current: &WithId<E>| {
+        etag::etag(current).is_ok_and(|etag| etag::matches(&headers, IF_MATCH, &etag))
+    })
+}
+
+impl<E, S> Handler<E, S>
+where
+    S: Crud<E> + Batch<E> + Sync + Send + Clone + 'static,
+    E: Send + 'static,
+    E: Serialize + for<'de> Deserialize<'de>,
+{
+    fn problem(&self, err: sqlx::Error) -> Problem {
+        (self.problems)(&err)
+    }
+
+    /// Notifies the subscribers of `GET /events` that the entities were
+    /// created or updated (`kind` is `create` or `update`).
+    fn notify<'e>(&self, kind: &'static str, entities: impl IntoIterator<Item = &'e WithId<E>>)
+    where
+        E: 'e,
+    {
+        if !self.events.is_listened() {
+            return;
+        }
+        for entity in entities {
+            if let Ok(data) = serde_json::to_string(&self.repr(entity)) {
+                self.events.send(kind, data);
+            }
+        }
+    }
+
+    /// Notifies the subscribers of `GET /events` that the entities were deleted.
+    fn notify_deleted(&self, ids: &[i64]) {
+        if !self.events.is_listened() {
+            return;
+        }
+        for id in ids {
+            self.events
+                .send("delete", serde_json::json!({ "id": id }).to_string());
+        }
+    }
+
+    /// Forwards the changes notified by the database to the subscribers of `GET /events`.
+    ///
+    /// Since the notifications may concern entities which are not visible to the
+    /// store (e.g. those of other tenants sharing the table of a `TenantStore`),
+    /// only the entities which can be read are forwarded, and only the deletions
+    /// of the entities known to be visible.
+    #[cfg(feature = "changes")]
+    async fn forward(self, mut feed: events::Feed) {
+        use crate::ChangeOperation;
+        use std::collections::HashSet;
+
+        let mut visible = HashSet::new();
+        let mut entities = self.store.stream();
+        while let Some(entity) = entities.next().await {
+            if let Ok(entity) = entity {
+                visible.insert(entity.id());
+            }
+        }
+        drop(entities);
+
+        // the listener reconnects by itself after an error
+        while let Some(change) = feed.next().await {
+            let Ok(change) = change else {
+                continue;
+            };
+            let kind = match change.op {
+                ChangeOperation::Create => "create",
+                ChangeOperation::Update => "update",
+                ChangeOperation::Delete => {
+                    if visible.remove(&change.id) {
+                        let data = serde_json::json!({ "id": change.id }).to_string();
+                        self.events.send("delete", data);
+                    }
+                    continue;
+                }
+            };
+            // the entity may have been deleted in the meantime, or not be
+            // visible to the store, in which case nothing is sent
+            let Ok(entity) = self.store.read(change.id).await else {
+                continue;
+            };
+            visible.insert(change.id);
+            if let Ok(data) = serde_json::to_string(&self.repr(&entity)) {
+                self.events.send(kind, data);
+            }
+        }
+    }
+
+    /// Returns the entity as JSON along with its `ETag`.
+    fn with_etag(&self, entity: WithId<E>) -> Result<Response, Problem> {
+        let etag = etag::etag(&entity).map_err(serialization_problem)?;
+        Ok(([(ETAG, etag)], Json(self.repr(&entity))).into_response())
+    }
+
+    /// Returns the entity in the representation of the router.
+    fn repr<'a>(&self, entity: &'a WithId<E>) -> Repr<'a, E> {
+        Repr {
+            entity,
+            flat: self.flat,
+        }
+    }
+
+    /// Parses an entity with its id, in the representation of the router.
+    fn parse_with_id(&self, json: Value) -> Result<WithId<E>, Problem> {
+        let entity = if self.flat {
+            serde_json::from_value::<crate::Flat<E>>(json).map(WithId::from)
+        } else {
+            serde_json::from_value(json)
+        };
+        entity.map_err(|err| {
+            Problem::new(StatusCode::UNPROCESSABLE_ENTITY).with_detail(err.to_string())
+        })
+    }
+
+    /// Replaces the entity, provided that the `If-Match` header, if any, matches
+    /// its current version.
+    async fn update_if_match(
+        &self,
+        entity: WithId<E>,
+        headers: &HeaderMap,
+    ) -> Result<WithId<E>, Problem> {
+        match if_match(headers) {
+            Some(check) => self
+                .store
+                .update_if(entity, self.columns, check)
+                .await
+                .map_err(|err| self.problem(err))?
+                .ok_or_else(precondition_failed),
+            None => self
+                .store
+                .update(entity)
+                .await
+                .map_err(|err| self.problem(err)),
+        }
+    }
+
+    pub(crate) async fn create(
+        State(handler): State<Self>,
+        OriginalUri(uri): OriginalUri,
+        payload: Result<Json<E>, JsonRejection>,
+    ) -> Result<Response, Problem> {
+        let Json(payload) = payload?;
+        let entity = handler
+            .store
+            .create(payload)
+            .await
+            .map_err(|err| handler.problem(err))?;
+        handler.notify("create", [&entity]);
+        let location = format!("{}/{}", uri.path().trim_end_matches('/'), entity.id());
+        let mut response = handler.with_etag(entity)?;
+        *response.status_mut() = StatusCode::CREATED;
+        if let Ok(location) = HeaderValue::from_str(&location) {
+            response.headers_mut().insert(LOCATION, location);
+        }
+        Ok(response)
+    }
+
+    pub(crate) async fn read(
+        id: Result<Path<i64>, PathRejection>,
+        State(handler): State<Self>,
+        headers: HeaderMap,
+    ) -> Result<Response, Problem> {
+        let Path(id) = id?;
+        let entity = handler
+            .store
+            .read(id)
+            .await
+            .map_err(|err| handler.problem(err))?;
+        let etag = etag::etag(&entity).map_err(serialization_problem)?;
+        if etag::matches(&headers, IF_NONE_MATCH, &etag) {
+            return Ok((StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response());
+        }
+        Ok(([(ETAG, etag)], Json(handler.repr(&entity))).into_response())
+    }
+
+    /// Streams all the entities in the given format, one line per entity.
+    ///
+    /// The entities are read from the store by a spawned task, so that the body
+    /// does not borrow the store; if reading fails midway, the body is aborted.
+    fn export(&self, format: Format) -> Response {
+        let (tx, rx) = tokio::sync::mpsc::channel::<Result<String, axum::BoxError>>(16);
+        let store = self.store.clone();
+        let columns = self.columns;
+        let flat = self.flat;
+        tokio::spawn(async move {
+            if tx.send(Ok(format.header(columns))).await.is_err() {
+                return;
+            }
+            let mut entities = store.stream();
+            while let Some(entity) = entities.next().await {
+                let line = match entity {
+                    Ok(entity) => {
+                        let entity = Repr {
+                            entity: &entity,
+                            flat,
+                        };
+                        format.line(entity, columns).map_err(Into::into)
+                    }
+                    Err(err) => Err(err.into()),
+                };
+                let failed = line.is_err();
+                if tx.send(line).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+        let lines = futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|line| (line, rx))
+        });
+        let content_type = HeaderValue::from_static(format.content_type());
+        ([(CONTENT_TYPE, content_type)], Body::from_stream(lines)).into_response()
+    }
+
+    pub(crate) async fn list(
+        State(handler): State<Self>,
+        OriginalUri(uri): OriginalUri,
+        headers: HeaderMap,
+        params: Result<Query<Vec<(String, String)>>, QueryRejection>,
+    ) -> Result<Response, Problem> {
+        let Query(params) = params?;
+        let format = Format::accepted(&headers);
+        if format != Format::Json && params.is_empty() {
+            return Ok(handler.export(format));
+        }
+        let query = ListQuery::parse(&params, handler.columns, &handler.schema)
+            .map_err(|err| Problem::new(StatusCode::BAD_REQUEST).with_detail(err))?;
+        let page = handler
+            .store
+            .query(&query)
+            .await
+            .map_err(|err| handler.problem(err))?;
+        let links = query.links(uri.path(), uri.query(), page.total);
+
+        let mut response = match format {
+            Format::Json => {
+                let items: Vec<_> = page.items.iter().map(|item| handler.repr(item)).collect();
+                Json(items).into_response()
+            }
+            format => {
+                let mut body = format.header(handler.columns);
+                for entity in &page.items {
+                    body += &format
+                        .line(handler.repr(entity), handler.columns)
+                        .map_err(serialization_problem)?;
+                }
+                let content_type = HeaderValue::from_static(format.content_type());
+                ([(CONTENT_TYPE, content_type)], body).into_response()
+            }
+        };
+        let headers = response.headers_mut();
+        headers.insert("x-total-count", HeaderValue::from(page.total));
+        if let Some(links) = links.and_then(|links| HeaderValue::from_str(&links).ok()) {
+            headers.insert("link", links);
+        }
+        Ok(response)
+    }
+
+    pub(crate) async fn update(
+        id: Result<Path<i64>, PathRejection>,
+        State(handler): State<Self>,
+        headers: HeaderMap,
+        payload: Result<Json<E>, JsonRejection>,
+    ) -> Result<Response, Problem> {
+        let Path(id) = id?;
+        let Json(payload) = payload?;
+        let entity = handler
+            .update_if_match(WithId::new(payload, id), &headers)
+            .await?;
+        handler.notify("update", [&entity]);
+        handler.with_etag(entity)
+    }
+
+    pub(crate) async fn update_with_id(
+        State(handler): State<Self>,
+        headers: HeaderMap,
+        payload: Result<Json<Value>, JsonRejection>,
+    ) -> Result<Response, Problem> {
+        let Json(payload) = payload?;
+        let payload = handler.parse_with_id(payload)?;
+        let entity = handler.update_if_match(payload, &headers).await?;
+        handler.notify("update", [&entity]);
+        handler.with_etag(entity)
+    }
+
+    pub(crate) async fn patch(
+        id: Result<Path<i64>, PathRejection>,
+        State(handler): State<Self>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<Response, Problem> {
+        let Path(id) = id?;
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .and_then(|content_type| content_type.split(';').next())
+            .unwrap_or_default()
+            .trim();
+        let is_json_patch = match content_type {
+            "application/merge-patch+json" | "application/json" => false,
+            "application/json-patch+json" => true,
+            _ => {
+                return Err(Problem::new(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                    .with_detail("expected a JSON merge patch or a JSON patch"))
+            }
+        };
+        let patch = serde_json::from_slice(&body).map_err(|err| {
+            Problem::new(StatusCode::BAD_REQUEST).with_detail(format!("invalid patch: {err}"))
+        })?;
+
+        let entity = handler
+            .store
+            .read(id)
+            .await
+            .map_err(|err| handler.problem(err))?;
+        let etag = etag::etag(&entity).map_err(serialization_problem)?;
+        if headers.contains_key(IF_MATCH) && !etag::matches(&headers, IF_MATCH, &etag) {
+            return Err(precondition_failed());
+        }
+        let mut json = serde_json::to_value(entity.inner()).map_err(serialization_problem)?;
+        let touched = if is_json_patch {
+            patch::json_patch(&mut json, patch).map_err(|err| match err {
+                PatchError::Invalid(detail) => {
+                    Problem::new(StatusCode::BAD_REQUEST).with_detail(detail)
+                }
+                PatchError::TestFailed(detail) => {
+                    Problem::new(StatusCode::CONFLICT).with_detail(detail)
+                }
+            })?
+        } else {
+            patch::merge_patch(&mut json, patch)
+        };
+        let columns = touched
+            .iter()
+            .map(|key| {
+                handler
+                    .columns
+                    .iter()
+                    .find(|col| *col == key)
+                    .copied()
+                    .ok_or_else(|| {
+                        Problem::new(StatusCode::BAD_REQUEST)
+                            .with_detail(format!("unknown column '{key}'"))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let entity = serde_json::from_value(json).map_err(|err| {
+            Problem::new(StatusCode::UNPROCESSABLE_ENTITY).with_detail(err.to_string())
+        })?;
+
+        // the entity may have been modified since it was read: with `If-Match`,
+        // the store checks again that it was not when updating it
+        let entity = WithId::new(entity, id);
+        let entity = match if_match(&headers) {
+            Some(check) => handler
+                .store
+                .update_if(entity, &columns, check)
+                .await
+                .map_err(|err| handler.problem(err))?
+                .ok_or_else(precondition_failed)?,
+            None => handler
+                .store
+                .update_columns(entity, &columns)
+                .await
+                .map_err(|err| handler.problem(err))?,
+        };
+        handler.notify("update", [&entity]);
+        handler.with_etag(entity)
+    }
+
+    pub(crate) async fn delete(
+        id: Result<Path<i64>, PathRejection>,
+        State(handler): State<Self>,
+        headers: HeaderMap,
+    ) -> Result<impl IntoResponse, Problem> {
+        let Path(id) = id?;
+        let deleted = match if_match(&headers) {
+            Some(check) => handler.store.delete_if(id, check).await,
+            None => handler.store.delete(id).await.map(|()| true),
+        };
+        if !deleted.map_err(|err| handler.problem(err))? {
+            return Err(precondition_failed());
+        }
+        handler.notify_deleted(&[id]);
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    pub(crate) async fn create_many(
+        State(handler): State<Self>,
+        payload: Result<Json<Vec<E>>, JsonRejection>,
+    ) -> Result<impl IntoResponse, Problem> {
+        let Json(payload) = payload?;
+        let entities = handler
+            .store
+            .create_many(payload)
+            .await
+            .map_err(|err| handler.problem(err))?;
+        handler.notify("create", &entities);
+        let entities: Vec<_> = entities.iter().map(|entity| handler.repr(entity)).collect();
+        Ok((StatusCode::CREATED, Json(entities)).into_response())
+    }
+
+    pub(crate) async fn update_many(
+        State(handler): State<Self>,
+        payload: Result<Json<Vec<Value>>, JsonRejection>,
+    ) -> Result<Response, Problem> {
+        let Json(payload) = payload?;
+        let payload = payload
+            .into_iter()
+            .map(|json| handler.parse_with_id(json))
+            .collect::<Result<_, _>>()?;
+        let entities = handler
+            .store
+            .update_many(payload)
+            .await
+            .map_err(|err| handler.problem(err))?;
+        handler.notify("update", &entities);
+        let entities: Vec<_> = entities.iter().map(|entity| handler.repr(entity)).collect();
+        Ok(Json(entities).into_response())
+    }
+
+    pub(crate) async fn delete_many(
+        State(handler): State<Self>,
+        payload: Result<Json<Vec<i64>>, JsonRejection>,
+    ) -> Result<impl IntoResponse, Problem> {
+        let Json(ids) = payload?;
+        handler
+            .store
+            .delete_many(&ids)
+            .await
+            .map_err(|err| handler.problem(err))?;
+        handler.notify_deleted(&ids);
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    pub(crate) async fn import(
+        State(handler): State<Self>,
+        headers: HeaderMap,
+        body: Body,
+    ) -> Result<impl IntoResponse, Problem> {
+        let format = headers
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .and_then(Format::of_content_type)
+            .ok_or_else(|| {
+                Problem::new(StatusCode::UNSUPPORTED_MEDIA_TYPE).with_detail(format!(
+                    "expected {} or {}",
+                    Format::NDJSON,
+                    Format::CSV
+                ))
+            })?;
+
+        let mut created = 0;
+        let failed = |problem: Problem, created: usize| {
+            let detail = problem.detail.clone().unwrap_or_default();
+            problem.with_detail(format!("{detail} ({created} entities were imported)"))
+        };
+        let mut decoder = Decoder::new(format, &handler.schema);
+        let mut chunks = body.into_data_stream();
+        let mut entities = Vec::new();
+        let mut done = false;
+        while !done {
+            let records = match chunks.next().await {
+                Some(Ok(chunk)) => decoder.feed(&chunk),
+                Some(Err(err)) => Err(err.to_string()),
+                None => {
+                    done = true;
+                    decoder.finish()
+                }
+            };
+            let records = records.map_err(|err| {
+                failed(
+                    Problem::new(StatusCode::BAD_REQUEST).with_detail(err),
+                    created,
+                )
+            })?;
+            for (line, json) in records {
+                // exported entities carry their id, which is ignored
+                let entity = serde_json::from_value::<E>(json.clone())
+                    .or_else(|_| serde_json::from_value::<WithId<E>>(json).map(WithId::into_inner))
+                    .map_err(|err| {
+                        let problem = Problem::new(StatusCode::UNPROCESSABLE_ENTITY)
+                            .with_detail(format!("line {line}: {err}"));
+                        failed(problem, created)
+                    })?;
+                entities.push(entity);
+            }
+            while entities.len() >= IMPORT_CHUNK_SIZE || (done && !entities.is_empty()) {
+                let rest = entities.split_off(entities.len().min(IMPORT_CHUNK_SIZE));
+                let chunk = std::mem::replace(&mut entities, rest);
+                let chunk = handler
+                    .store
+                    .create_many(chunk)
+                    .await
+                    .map_err(|err| failed(handler.problem(err), created))?;
+                handler.notify("create", &chunk);
+                created += chunk.len();
+            }
+        }
+        Ok(Json(serde_json::json!({ "created": created })))
+    }
+
+    pub(crate) async fn delete_all(
+        State(handler): State<Self>,
+    ) -> Result<impl IntoResponse, Problem> {
+        handler
+            .store
+            .delete_all()
+            .await
+            .map_err(|err| handler.problem(err))?;
+        if handler.events.is_listened() {
+            handler.events.send("delete_all", "{}".into());
+        }
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    pub(crate) async fn events(State(handler): State<Self>) -> impl IntoResponse {
+        let events = handler.events.subscribe();
+        #[cfg(feature = "changes")]
+        if let Some(feed) = handler.events.take_feed() {
+            tokio::spawn(handler.clone().forward(feed));
+        }
+        Sse::new(events).keep_alive(KeepAlive::default())
+    }
+}
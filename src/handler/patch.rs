@@ -0,0 +1,244 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// Error that can occur while applying a patch
+#[derive(Debug, PartialEq)]
+pub(crate) enum PatchError {
+    /// the patch itself is invalid
+    Invalid(String),
+    /// a `test` operation of a JSON patch failed
+    TestFailed(String),
+}
+
+/// Applies a JSON merge patch ([RFC 7396](https://www.rfc-editor.org/rfc/rfc7396))
+/// to `target`, and returns the top-level keys that were touched.
+pub(crate) fn merge_patch(target: &mut Value, patch: Value) -> Vec<String> {
+    let touched = match &patch {
+        Value::Object(patch) => patch.keys().cloned().collect(),
+        _ => Vec::new(),
+    };
+    merge(target, patch);
+    touched
+}
+
+fn merge(target: &mut Value, patch: Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            let target = target.as_object_mut().expect("target is an object");
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(&key);
+                } else {
+                    merge(target.entry(key).or_insert(Value::Null), value);
+                }
+            }
+        }
+        patch => *target = patch,
+    }
+}
+
+/// One operation of a JSON patch
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Operation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// Applies a JSON patch ([RFC 6902](https://www.rfc-editor.org/rfc/rfc6902))
+/// to `target`, and returns the top-level keys that were touched.
+///
+/// Operations are applied atomically: if one of them fails, `target` is left
+/// untouched.
+pub(crate) fn json_patch(target: &mut Value, patch: Value) -> Result<Vec<String>, PatchError> {
+    let operations: Vec<Operation> =
+        serde_json::from_value(patch).map_err(|err| PatchError::Invalid(err.to_string()))?;
+
+    let mut patched = target.clone();
+    let mut touched = Vec::new();
+    let mut touch = |path: &str| {
+        let key = parse_pointer(path).map(|tokens| tokens.into_iter().next())?;
+        if let Some(key) = key.filter(|key| !touched.contains(key)) {
+            touched.push(key);
+        }
+        Ok(())
+    };
+
+    for operation in operations {
+        match operation {
+            Operation::Add { path, value } => {
+                touch(&path)?;
+                add(&mut patched, &path, value)?;
+            }
+            Operation::Remove { path } => {
+                touch(&path)?;
+                remove(&mut patched, &path)?;
+            }
+            Operation::Replace { path, value } => {
+                touch(&path)?;
+                remove(&mut patched, &path)?;
+                add(&mut patched, &path, value)?;
+            }
+            Operation::Move { from, path } => {
+                touch(&from)?;
+                touch(&path)?;
+                let value = remove(&mut patched, &from)?;
+                add(&mut patched, &path, value)?;
+            }
+            Operation::Copy { from, path } => {
+                touch(&path)?;
+                let value = get(&patched, &from)?.clone();
+                add(&mut patched, &path, value)?;
+            }
+            Operation::Test { path, value } => {
+                if get(&patched, &path)? != &value {
+                    return Err(PatchError::TestFailed(format!("test failed for '{path}'")));
+                }
+            }
+        }
+    }
+
+    *target = patched;
+    Ok(touched)
+}
+
+/// Splits a JSON pointer ([RFC 6901](https://www.rfc-editor.org/rfc/rfc6901)) into tokens
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, PatchError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let tokens = pointer
+        .strip_prefix('/')
+        .ok_or_else(|| PatchError::Invalid(format!("invalid pointer '{pointer}'")))?;
+    Ok(tokens
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn missing(pointer: &str) -> PatchError {
+    PatchError::Invalid(format!("no value at '{pointer}'"))
+}
+
+fn get<'v>(target: &'v Value, pointer: &str) -> Result<&'v Value, PatchError> {
+    target.pointer(pointer).ok_or_else(|| missing(pointer))
+}
+
+/// Splits a pointer into the pointer to the parent and the last token
+fn split_parent(pointer: &str) -> Result<(&str, String), PatchError> {
+    let mut tokens = parse_pointer(pointer)?;
+    let last = tokens
+        .pop()
+        .ok_or_else(|| PatchError::Invalid("cannot patch the whole entity".into()))?;
+    let parent = &pointer[..pointer.rfind('/').unwrap_or(0)];
+    Ok((parent, last))
+}
+
+fn add(target: &mut Value, pointer: &str, value: Value) -> Result<(), PatchError> {
+    let (parent, last) = split_parent(pointer)?;
+    match target.pointer_mut(parent).ok_or_else(|| missing(parent))? {
+        Value::Object(object) => {
+            object.insert(last, value);
+            Ok(())
+        }
+        Value::Array(array) if last == "-" => {
+            array.push(value);
+            Ok(())
+        }
+        Value::Array(array) => match last.parse::<usize>() {
+            Ok(index) if index <= array.len() => {
+                array.insert(index, value);
+                Ok(())
+            }
+            _ => Err(missing(pointer)),
+        },
+        _ => Err(missing(pointer)),
+    }
+}
+
+fn remove(target: &mut Value, pointer: &str) -> Result<Value, PatchError> {
+    let (parent, last) = split_parent(pointer)?;
+    match target.pointer_mut(parent).ok_or_else(|| missing(parent))? {
+        Value::Object(object) => object.remove(&last).ok_or_else(|| missing(pointer)),
+        Value::Array(array) => match last.parse::<usize>() {
+            Ok(index) if index < array.len() => Ok(array.remove(index)),
+            _ => Err(missing(pointer)),
+        },
+        _ => Err(missing(pointer)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{json_patch, merge_patch, PatchError};
+    use serde_json::json;
+
+    #[test]
+    fn merge_patch_rfc7396_example() {
+        let mut target = json!({
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        });
+        let patch = json!({
+            "title": "Hello!",
+            "phoneNumber": "+01-123-456-7890",
+            "author": {"familyName": null},
+            "tags": ["example"]
+        });
+        let mut touched = merge_patch(&mut target, patch);
+        touched.sort();
+        assert_eq!(touched, ["author", "phoneNumber", "tags", "title"]);
+        assert_eq!(
+            target,
+            json!({
+                "title": "Hello!",
+                "author": {"givenName": "John"},
+                "tags": ["example"],
+                "content": "This will be unchanged",
+                "phoneNumber": "+01-123-456-7890"
+            })
+        );
+    }
+
+    #[test]
+    fn json_patch_operations() {
+        let mut target = json!({"description": "milk", "done": false, "tags": ["a"]});
+        let patch = json!([
+            {"op": "test", "path": "/done", "value": false},
+            {"op": "replace", "path": "/done", "value": true},
+            {"op": "add", "path": "/tags/-", "value": "b"},
+            {"op": "copy", "from": "/description", "path": "/tags/0"},
+        ]);
+        let touched = json_patch(&mut target, patch).unwrap();
+        assert_eq!(touched, ["done", "tags"]);
+        assert_eq!(
+            target,
+            json!({"description": "milk", "done": true, "tags": ["milk", "a", "b"]})
+        );
+    }
+
+    #[test]
+    fn json_patch_is_atomic() {
+        let mut target = json!({"description": "milk", "done": false});
+        let patch = json!([
+            {"op": "replace", "path": "/done", "value": true},
+            {"op": "test", "path": "/description", "value": "bread"},
+        ]);
+        let result = json_patch(&mut target, patch);
+        assert!(matches!(result, Err(PatchError::TestFailed(_))));
+        assert_eq!(target, json!({"description": "milk", "done": false}));
+
+        let patch = json!([{"op": "remove", "path": "/nope"}]);
+        let result = json_patch(&mut target, patch);
+        assert!(matches!(result, Err(PatchError::Invalid(_))));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::InvalidQuery;
+use axum::{
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::{
+        header::{ALLOW, CONTENT_TYPE},
+        HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::error::ErrorKind;
+use std::sync::Arc;
+
+/// An error returned by the axum router as an
+/// [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457) `application/problem+json` body.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">axum</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+///
+/// # Example
+///
+/// ```
+/// use axum::http::StatusCode;
+/// use miniorm::Problem;
+///
+/// let problem = Problem::new(StatusCode::NOT_FOUND).with_detail("no todo with id 12");
+/// assert_eq!(problem.r#type, "about:blank");
+/// assert_eq!(problem.title, "Not Found");
+/// assert_eq!(problem.status, 404);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Problem {
+    /// URI identifying the type of problem (`about:blank` by default)
+    pub r#type: String,
+    /// short, human-readable summary of the type of problem
+    pub title: String,
+    /// HTTP status code
+    pub status: u16,
+    /// human-readable explanation specific to this occurrence of the problem
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Function mapping the errors of a store to the [`Problem`] returned by the router.
+pub(crate) type ProblemMapper = Arc<dyn Fn(&sqlx::Error) -> Problem + Send + Sync>;
+
+impl Problem {
+    /// Creates a problem of type `about:blank`, whose title is the
+    /// canonical reason of the `status`.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            r#type: "about:blank".into(),
+            title: status.canonical_reason().unwrap_or_default().into(),
+            status: status.as_u16(),
+            detail: None,
+        }
+    }
+
+    /// Sets the type of the problem.
+    pub fn with_type(mut self, r#type: impl Into<String>) -> Self {
+        self.r#type = r#type.into();
+        self
+    }
+
+    /// Sets the title of the problem.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets the detail of the problem.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Returns the HTTP status code of the problem.
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Default mapping from the errors of a store to problems:
+    /// - [`sqlx::Error::RowNotFound`] results in a `404 Not Found`,
+    /// - unique and foreign key violations result in a `409 Conflict`,
+    /// - not null and check violations result in a `422 Unprocessable Entity`,
+    /// - an [`InvalidQuery`] results in a `400 Bad Request`,
+    /// - any other error results in a `500 Internal Server Error` without detail,
+    ///   so that nothing about the database leaks to the clients.
+    ///
+    /// The message of the database is used as the detail of the constraint violations,
+    /// and the message of the error as the one of an invalid query.
+    pub fn from_sqlx_error(err: &sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::RowNotFound => {
+                Self::new(StatusCode::NOT_FOUND).with_detail("entity not found")
+            }
+            sqlx::Error::Database(err) => {
+                let status = match err.kind() {
+                    ErrorKind::UniqueViolation | ErrorKind::ForeignKeyViolation => {
+                        StatusCode::CONFLICT
+                    }
+                    ErrorKind::NotNullViolation | ErrorKind::CheckViolation => {
+                        StatusCode::UNPROCESSABLE_ENTITY
+                    }
+                    _ => return Self::new(StatusCode::INTERNAL_SERVER_ERROR),
+                };
+                Self::new(status).with_detail(err.message())
+            }
+            sqlx::Error::Decode(err) if err.is::<InvalidQuery>() => {
+                Self::new(StatusCode::BAD_REQUEST).with_detail(err.to_string())
+            }
+            _ => Self::new(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        let mut response = (status, Json(self)).into_response();
+        response.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        response
+    }
+}
+
+impl From<JsonRejection> for Problem {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::new(rejection.status()).with_detail(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for Problem {
+    fn from(rejection: PathRejection) -> Self {
+        Self::new(rejection.status()).with_detail(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for Problem {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::new(rejection.status()).with_detail(rejection.body_text())
+    }
+}
+
+/// Turns the empty `405 Method Not Allowed` responses of the router into
+/// problems, keeping their `Allow` header.
+pub(crate) async fn method_not_allowed(response: Response) -> Response {
+    if response.status() != StatusCode::METHOD_NOT_ALLOWED {
+        return response;
+    }
+    let mut problem = Problem::new(StatusCode::METHOD_NOT_ALLOWED).into_response();
+    if let Some(allow) = response.headers().get(ALLOW) {
+        problem.headers_mut().insert(ALLOW, allow.clone());
+    }
+    problem
+}
+
+#[cfg(test)]
+mod test {
+    use super::Problem;
+    use crate::{ListQuery, WithId};
+    use axum::{
+        http::{header::CONTENT_TYPE, StatusCode},
+        response::IntoResponse,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn from_sqlx_error() {
+        let problem = Problem::from_sqlx_error(&sqlx::Error::RowNotFound);
+        assert_eq!(problem.status_code(), StatusCode::NOT_FOUND);
+        let problem = Problem::from_sqlx_error(&sqlx::Error::PoolTimedOut);
+        assert_eq!(problem.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(problem.detail, None);
+
+        let err = ListQuery::new()
+            .sort("nope")
+            .apply(vec![WithId::new(json!({}), 1)]);
+        let problem = Problem::from_sqlx_error(&err.unwrap_err());
+        assert_eq!(problem.status_code(), StatusCode::BAD_REQUEST);
+        let detail = "invalid query: unknown column 'nope'";
+        assert_eq!(problem.detail.as_deref(), Some(detail));
+    }
+
+    #[test]
+    fn into_response() {
+        let response = Problem::new(StatusCode::CONFLICT).into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/problem+json");
+    }
+}
@@ -0,0 +1,387 @@
+use super::{
+    problem::{self, Problem},
+    Handler,
+};
+use crate::{
+    prelude::IntoOpenApi,
+    traits::crud::{Batch, Crud},
+    OpenApi,
+};
+use axum::{
+    extract::Request,
+    middleware,
+    response::IntoResponse,
+    routing::{delete, get, patch, post, put, MethodRouter, Route},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::{convert::Infallible, sync::Arc};
+use tower::{Layer, Service};
+
+/// One of the operations served by the router built by a [`RouterBuilder`].
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">axum</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Operation {
+    /// `GET /` lists all entities
+    List,
+    /// `POST /` creates a new entity
+    Create,
+    /// `PUT /` updates an existing entity, whose id is part of the payload
+    UpdateWithId,
+    /// `DELETE /` deletes all entities
+    DeleteAll,
+    /// `GET /:id` reads one entity
+    Read,
+    /// `PUT /:id` updates one entity
+    Update,
+    /// `PATCH /:id` updates some of the columns of one entity
+    Patch,
+    /// `DELETE /:id` deletes one entity
+    Delete,
+    /// `POST /_bulk` creates several entities at once
+    CreateMany,
+    /// `PUT /_bulk` updates several entities at once
+    UpdateMany,
+    /// `DELETE /_bulk` deletes several entities at once
+    DeleteMany,
+    /// `POST /_import` creates the entities of an NDJSON or CSV body
+    Import,
+    /// `GET /events` streams the changes of the entities as server-sent events
+    /// (not served by default)
+    Events,
+}
+
+impl Operation {
+    /// All the operations
+    pub const ALL: [Operation; 13] = [
+        Operation::List,
+        Operation::Create,
+        Operation::UpdateWithId,
+        Operation::DeleteAll,
+        Operation::Read,
+        Operation::Update,
+        Operation::Patch,
+        Operation::Delete,
+        Operation::CreateMany,
+        Operation::UpdateMany,
+        Operation::DeleteMany,
+        Operation::Import,
+        Operation::Events,
+    ];
+
+    /// The operations served by default, i.e. all of them except [`Operation::Events`]
+    pub const DEFAULT: [Operation; 12] = [
+        Operation::List,
+        Operation::Create,
+        Operation::UpdateWithId,
+        Operation::DeleteAll,
+        Operation::Read,
+        Operation::Update,
+        Operation::Patch,
+        Operation::Delete,
+        Operation::CreateMany,
+        Operation::UpdateMany,
+        Operation::DeleteMany,
+        Operation::Import,
+    ];
+
+    /// The operations which do not modify the store
+    pub const READ_ONLY: [Operation; 2] = [Operation::List, Operation::Read];
+
+    /// HTTP method of the operation (e.g. `GET`)
+    pub fn method(&self) -> &'static str {
+        match self {
+            Operation::List | Operation::Read | Operation::Events => "GET",
+            Operation::Create | Operation::CreateMany | Operation::Import => "POST",
+            Operation::UpdateWithId | Operation::Update | Operation::UpdateMany => "PUT",
+            Operation::Patch => "PATCH",
+            Operation::DeleteAll | Operation::Delete | Operation::DeleteMany => "DELETE",
+        }
+    }
+
+    /// Path at which the operation is served: `/`, `/:id`, `/_bulk`, `/_import` or `/events`
+    pub fn path(&self) -> &'static str {
+        match self {
+            Operation::List
+            | Operation::Create
+            | Operation::UpdateWithId
+            | Operation::DeleteAll => "/",
+            Operation::Read | Operation::Update | Operation::Patch | Operation::Delete => "/:id",
+            Operation::CreateMany | Operation::UpdateMany | Operation::DeleteMany => "/_bulk",
+            Operation::Import => "/_import",
+            Operation::Events => "/events",
+        }
+    }
+}
+
+type Customization<E, S> =
+    Box<dyn FnOnce(MethodRouter<Handler<E, S>>) -> MethodRouter<Handler<E, S>> + Send>;
+
+/// Builder of the [`Router`] serving the CRUD operations of a store over a REST api,
+/// as described in [`IntoAxumRouter::into_axum_router`](crate::prelude::IntoAxumRouter::into_axum_router),
+/// which allows to:
+/// - choose which operations are served (e.g. to prevent `DELETE /` from wiping the whole table,
+///   or to stream the changes of the entities on `GET /events`),
+/// - rename the id path parameter,
+/// - flatten the id of the entities alongside their fields,
+/// - attach middleware layers to some of the operations,
+/// - customize the mapping from the errors of the store to the returned [`Problem`].
+///
+/// It is obtained using `Store::router_builder`.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">axum</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+///
+/// # Example
+///
+/// ```no_run
+/// use axum::{
+///     extract::Request,
+///     http::{header::AUTHORIZATION, StatusCode},
+///     middleware::{self, Next},
+///     response::Response,
+///     Router,
+/// };
+/// use miniorm::{prelude::*, Operation};
+/// use serde::{Deserialize, Serialize};
+/// use sqlx::FromRow;
+///
+/// #[derive(Debug, Clone, FromRow, Entity, Serialize, Deserialize)]
+/// struct Todo {
+///     #[postgres(TEXT NOT NULL)]
+///     description: String,
+/// }
+///
+/// async fn authorized(request: Request, next: Next) -> Result<Response, StatusCode> {
+///     match request.headers().get(AUTHORIZATION) {
+///         Some(token) if token == "Bearer secret" => Ok(next.run(request).await),
+///         _ => Err(StatusCode::UNAUTHORIZED),
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = sqlx::PgPool::connect("postgres://localhost/miniorm").await?;
+/// let todos = Store::<_, Todo>::new(db);
+///
+/// let app: Router = Router::new().nest(
+///     "/todos",
+///     todos
+///         .router_builder()
+///         .disable(Operation::DeleteAll)
+///         .layer(Operation::Delete, middleware::from_fn(authorized))
+///         .id_param("todo_id")
+///         .build(),
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub struct RouterBuilder<E, S> {
+    handler: Handler<E, S>,
+    operations: Vec<Operation>,
+    id_param: String,
+    customizations: Vec<(Operation, Customization<E, S>)>,
+}
+
+impl<E, S> RouterBuilder<E, S>
+where
+    S: Crud<E> + Batch<E> + Sync + Send + Clone + 'static,
+    E: Send + 'static,
+    E: Serialize + for<'de> Deserialize<'de>,
+{
+    pub(crate) fn new(handler: Handler<E, S>) -> Self {
+        Self {
+            handler,
+            operations: Operation::DEFAULT.to_vec(),
+            id_param: "id".into(),
+            customizations: Vec::new(),
+        }
+    }
+
+    /// Only serves the provided operations.
+    pub fn only(mut self, operations: impl IntoIterator<Item = Operation>) -> Self {
+        self.operations = operations.into_iter().collect();
+        self
+    }
+
+    /// Serves the operation.
+    pub fn enable(mut self, operation: Operation) -> Self {
+        if !self.operations.contains(&operation) {
+            self.operations.push(operation);
+        }
+        self
+    }
+
+    /// Does not serve the operation; requests to it result in a
+    /// `405 Method Not Allowed` (or `404 Not Found` if no operation is left on its path).
+    pub fn disable(mut self, operation: Operation) -> Self {
+        self.operations.retain(|op| *op != operation);
+        self
+    }
+
+    /// Only serves the operations which do not modify the store, i.e.
+    /// [`Operation::READ_ONLY`].
+    pub fn read_only(self) -> Self {
+        self.only(Operation::READ_ONLY)
+    }
+
+    /// Returns `true` if the operation is served.
+    pub fn is_enabled(&self, operation: Operation) -> bool {
+        self.operations.contains(&operation)
+    }
+
+    /// Renames the id path parameter (`id` by default), i.e. the entities are served
+    /// at `/:<name>` instead of `/:id`.
+    pub fn id_param(mut self, name: impl Into<String>) -> Self {
+        self.id_param = name.into();
+        self
+    }
+
+    /// Uses the flattened representation of the entities in the requests and
+    /// responses, i.e. `{"id": 1, "description": ...}` instead of
+    /// `{"id": 1, "inner": {"description": ...}}` (see [`Flat`](crate::Flat)).
+    pub fn flat_ids(mut self) -> Self {
+        self.handler = self.handler.with_flat_ids();
+        self
+    }
+
+    /// Uses `problems` to map the errors of the store to the returned [`Problem`]
+    /// instead of [`Problem::from_sqlx_error`].
+    pub fn problems(
+        mut self,
+        problems: impl Fn(&sqlx::Error) -> Problem + Send + Sync + 'static,
+    ) -> Self {
+        self.handler = self.handler.with_problems(Arc::new(problems));
+        self
+    }
+
+    /// Serves the changes notified by the database (see `Store::changes`) on
+    /// `GET /events`, instead of the ones made through the router, so that the
+    /// changes made by other processes are streamed as well. This also enables
+    /// [`Operation::Events`].
+    ///
+    /// The entities created or updated are read back from the store when their
+    /// change is notified; if that fails, e.g. because the entity was deleted in
+    /// the meantime or belongs to another tenant of a `TenantStore`, no event is
+    /// sent. Likewise, deletions are only sent for the entities which the store
+    /// could read, either when the router started or when they were notified.
+    ///
+    /// <table>
+    ///     <tr>
+    ///         <td style="background-color:green;color:black;">
+    ///         Requires the <span style="color:blue">changes</span>
+    ///         feature flag.
+    ///         </td>
+    ///     </tr>
+    /// </table>
+    #[cfg(feature = "changes")]
+    pub fn changes(
+        mut self,
+        changes: impl futures::Stream<Item = sqlx::Result<crate::Change>> + Send + 'static,
+    ) -> Self {
+        use futures::StreamExt;
+        self.handler = self.handler.with_changes(changes.boxed());
+        self.enable(Operation::Events)
+    }
+
+    /// Attaches a middleware layer to the route of one operation
+    /// (see [`MethodRouter::layer`]).
+    ///
+    /// Layers are applied in the order in which they are attached, so the last
+    /// one attached is the outermost one.
+    pub fn layer<L>(mut self, operation: Operation, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + 'static,
+        L::Service: Service<Request> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        let customization: Customization<E, S> = Box::new(move |route| route.layer(layer));
+        self.customizations.push((operation, customization));
+        self
+    }
+
+    /// Returns the [`OpenApi`] document describing the routes of the router,
+    /// assuming it is mounted at `path`.
+    pub fn openapi(&self, path: &str) -> OpenApi
+    where
+        S: IntoOpenApi,
+    {
+        let openapi = self
+            .handler
+            .store
+            .openapi(path)
+            .restrict(|method, path| {
+                self.operations
+                    .iter()
+                    .any(|op| op.method() == method && op.path() == path)
+            })
+            .rename_id_param(&self.id_param);
+        if self.handler.flat {
+            openapi.flat_ids()
+        } else {
+            openapi
+        }
+    }
+
+    fn method_router(operation: Operation) -> MethodRouter<Handler<E, S>> {
+        match operation {
+            Operation::List => get(Handler::list),
+            Operation::Create => post(Handler::create),
+            Operation::UpdateWithId => put(Handler::update_with_id),
+            Operation::DeleteAll => delete(Handler::delete_all),
+            Operation::Read => get(Handler::read),
+            Operation::Update => put(Handler::update),
+            Operation::Patch => patch(Handler::patch),
+            Operation::Delete => delete(Handler::delete),
+            Operation::CreateMany => post(Handler::create_many),
+            Operation::UpdateMany => put(Handler::update_many),
+            Operation::DeleteMany => delete(Handler::delete_many),
+            Operation::Import => post(Handler::import),
+            Operation::Events => get(Handler::events),
+        }
+    }
+
+    /// Builds the [`Router`].
+    pub fn build<R>(self) -> Router<R> {
+        let item = format!("/:{}", self.id_param);
+        let mut customizations = self.customizations;
+        let mut router = Router::new();
+        for operation in Operation::ALL {
+            if !self.operations.contains(&operation) {
+                continue;
+            }
+            let mut route = Self::method_router(operation);
+            for (op, customization) in std::mem::take(&mut customizations) {
+                if op == operation {
+                    route = customization(route);
+                } else {
+                    customizations.push((op, customization));
+                }
+            }
+            let path = match operation.path() {
+                "/:id" => &item,
+                path => path,
+            };
+            router = router.route(path, route);
+        }
+        router
+            .layer(middleware::map_response(problem::method_not_allowed))
+            .with_state(self.handler)
+    }
+}
@@ -0,0 +1,280 @@
+//! Instrumentation of the database operations of a [`Store`](crate::Store)
+//! (see its documentation for the recorded fields and metrics).
+use futures_core::{stream::BoxStream, Stream};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// One database operation on the table of a store.
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+pub(crate) struct Call {
+    system: &'static str,
+    table: &'static str,
+    operation: &'static str,
+    id: Option<i64>,
+    #[cfg(feature = "retry")]
+    retry: crate::RetryPolicy,
+}
+
+impl Call {
+    pub(crate) fn new<DB: sqlx::Database>(table: &'static str, operation: &'static str) -> Self {
+        Self {
+            system: system(DB::NAME),
+            table,
+            operation,
+            id: None,
+            #[cfg(feature = "retry")]
+            retry: crate::RetryPolicy::never(),
+        }
+    }
+
+    /// Sets the id of the entity the operation applies to.
+    pub(crate) fn id(self, id: i64) -> Self {
+        let id = Some(id);
+        Self { id, ..self }
+    }
+
+    /// Sets the policy retrying the operation on transient errors.
+    #[cfg(feature = "retry")]
+    pub(crate) fn retry(self, retry: crate::RetryPolicy) -> Self {
+        Self { retry, ..self }
+    }
+
+    /// Runs the operation, each call of `attempt` running it once, `rows` telling
+    /// how many rows its result returned or affected.
+    pub(crate) async fn run<T, F>(
+        self,
+        attempt: impl Fn() -> F,
+        rows: impl FnOnce(&T) -> u64,
+    ) -> sqlx::Result<T>
+    where
+        F: Future<Output = sqlx::Result<T>>,
+    {
+        #[cfg(feature = "retry")]
+        let retry = self.retry;
+        #[cfg(feature = "retry")]
+        let operation = retry.run(self.system, self.operation, attempt);
+        #[cfg(not(feature = "retry"))]
+        let operation = attempt();
+        self.once(operation, rows).await
+    }
+
+    /// Runs the operation once, e.g. because it consumes its input, `rows`
+    /// telling how many rows its result returned or affected.
+    pub(crate) async fn once<T>(
+        self,
+        operation: impl Future<Output = sqlx::Result<T>>,
+        rows: impl FnOnce(&T) -> u64,
+    ) -> sqlx::Result<T> {
+        let running = Running::start(self);
+        #[cfg(feature = "tracing")]
+        let operation = tracing::Instrument::instrument(operation, running.span.clone());
+        let result = operation.await;
+        running.done(result.as_ref().map(rows));
+        result
+    }
+
+    /// Runs the operation streaming the rows of `stream`, which lasts until the
+    /// stream ends, fails or is dropped. Since some rows may already have been
+    /// yielded, the operation is not retried.
+    pub(crate) fn stream<'s, T: 's>(
+        self,
+        stream: BoxStream<'s, sqlx::Result<T>>,
+    ) -> BoxStream<'s, sqlx::Result<T>> {
+        Box::pin(Streaming {
+            stream,
+            running: Some(Running::start(self)),
+            rows: 0,
+        })
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record(&self, result: Result<u64, &sqlx::Error>, duration: std::time::Duration) {
+        let labels = [("table", self.table), ("operation", self.operation)];
+        metrics::counter!("miniorm_operations_total", &labels).increment(1);
+        metrics::histogram!("miniorm_operation_duration_seconds", &labels).record(duration);
+        if let Err(err) = result {
+            metrics::counter!(
+                "miniorm_operation_errors_total",
+                "table" => self.table,
+                "operation" => self.operation,
+                "error" => error_type(err),
+            )
+            .increment(1);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn span(&self) -> tracing::Span {
+        let verb = verb(self.operation);
+        tracing::info_span!(
+            "miniorm",
+            otel.name = %format!("{verb} {}", self.table),
+            otel.kind = "client",
+            otel.status_code = tracing::field::Empty,
+            db.system = self.system,
+            db.collection.name = self.table,
+            db.operation.name = verb,
+            miniorm.operation = self.operation,
+            miniorm.id = self.id,
+            miniorm.rows_affected = tracing::field::Empty,
+            miniorm.duration_ms = tracing::field::Empty,
+            "error.type" = tracing::field::Empty,
+        )
+    }
+}
+
+/// A started operation, recording its outcome once done.
+struct Running {
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    call: Call,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(any(feature = "tracing", feature = "metrics"))]
+    start: std::time::Instant,
+}
+
+impl Running {
+    fn start(call: Call) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: call.span(),
+            call,
+            #[cfg(any(feature = "tracing", feature = "metrics"))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    /// Records the number of rows returned or affected, or the error.
+    fn done(self, result: Result<u64, &sqlx::Error>) {
+        #[cfg(any(feature = "tracing", feature = "metrics"))]
+        let duration = self.start.elapsed();
+        #[cfg(feature = "metrics")]
+        self.call.record(result, duration);
+        #[cfg(feature = "tracing")]
+        {
+            let span = &self.span;
+            span.record("miniorm.duration_ms", duration.as_secs_f64() * 1000.0);
+            match result {
+                Ok(rows) => {
+                    span.record("miniorm.rows_affected", rows);
+                }
+                Err(err) => {
+                    span.record("otel.status_code", "ERROR");
+                    span.record("error.type", error_type(err).as_str());
+                }
+            }
+        }
+        #[cfg(not(any(feature = "tracing", feature = "metrics")))]
+        let _ = result;
+    }
+}
+
+/// A stream of rows run as one operation (see [`Call::stream`]).
+struct Streaming<'s, T> {
+    stream: BoxStream<'s, sqlx::Result<T>>,
+    /// `None` once the operation is done
+    running: Option<Running>,
+    rows: u64,
+}
+
+impl<T> Stream for Streaming<'_, T> {
+    type Item = sqlx::Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        #[cfg(feature = "tracing")]
+        let entered = this.running.as_ref().map(|running| running.span.enter());
+        let next = this.stream.as_mut().poll_next(cx);
+        #[cfg(feature = "tracing")]
+        drop(entered);
+        match &next {
+            Poll::Ready(Some(Ok(_))) => this.rows += 1,
+            Poll::Ready(Some(Err(err))) => {
+                if let Some(running) = this.running.take() {
+                    running.done(Err(err));
+                }
+            }
+            Poll::Ready(None) => {
+                if let Some(running) = this.running.take() {
+                    running.done(Ok(this.rows));
+                }
+            }
+            Poll::Pending => {}
+        }
+        next
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+impl<T> Drop for Streaming<'_, T> {
+    fn drop(&mut self) {
+        // the stream was dropped before its end
+        if let Some(running) = self.running.take() {
+            running.done(Ok(self.rows));
+        }
+    }
+}
+
+/// Returns the OpenTelemetry name of a database system.
+fn system(name: &'static str) -> &'static str {
+    match name {
+        "PostgreSQL" => "postgresql",
+        "SQLite" => "sqlite",
+        "MySQL" => "mysql",
+        other => other,
+    }
+}
+
+/// Returns the SQL statement run by an operation.
+#[cfg(feature = "tracing")]
+fn verb(operation: &str) -> &'static str {
+    match operation {
+        "create" | "create_many" | "restore" => "INSERT",
+        "update" | "update_columns" | "update_if" | "update_many" => "UPDATE",
+        "delete" | "delete_all" | "delete_if" | "delete_many" => "DELETE",
+        "create_table" => "CREATE TABLE",
+        "drop_table" => "DROP TABLE",
+        _ => "SELECT",
+    }
+}
+
+/// Returns the type of an error: the SQLSTATE code of the errors returned by
+/// the database, or the kind of the error otherwise.
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+fn error_type(err: &sqlx::Error) -> String {
+    let kind = match err {
+        sqlx::Error::Database(err) => match err.code() {
+            Some(code) => return code.into_owned(),
+            None => "database",
+        },
+        sqlx::Error::RowNotFound => "row_not_found",
+        sqlx::Error::PoolTimedOut => "pool_timed_out",
+        sqlx::Error::PoolClosed => "pool_closed",
+        sqlx::Error::Io(_) => "io",
+        sqlx::Error::Tls(_) => "tls",
+        sqlx::Error::Protocol(_) => "protocol",
+        sqlx::Error::ColumnNotFound(_) | sqlx::Error::ColumnIndexOutOfBounds { .. } => "column",
+        sqlx::Error::ColumnDecode { .. } | sqlx::Error::Decode(_) => "decode",
+        _ => "other",
+    };
+    kind.into()
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod test {
+    use super::{error_type, system, verb};
+
+    #[test]
+    fn names() {
+        assert_eq!(system("PostgreSQL"), "postgresql");
+        assert_eq!(verb("list"), "SELECT");
+        assert_eq!(verb("create_many"), "INSERT");
+        assert_eq!(error_type(&sqlx::Error::RowNotFound), "row_not_found");
+    }
+}
@@ -0,0 +1,8 @@
+use serde_json::{json, Value};
+
+/// Returns the JSON schema of the entity given its
+/// [`Schema::MINIORM_JSON_SCHEMA`](crate::prelude::Schema::MINIORM_JSON_SCHEMA),
+/// or an unconstrained object if it is not valid.
+pub(crate) fn entity_schema(json_schema: &str) -> Value {
+    serde_json::from_str(json_schema).unwrap_or_else(|_| json!({"type": "object"}))
+}
@@ -5,26 +5,89 @@
 )]
 #![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
 
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "mysql"))]
+mod any;
+#[cfg(feature = "audit")]
+mod audit;
+mod builder;
+#[cfg(feature = "changes")]
+mod changes;
+#[cfg(any(feature = "axum", feature = "dump"))]
+mod csv;
+#[cfg(feature = "dump")]
+mod dump;
+#[cfg(feature = "factory")]
+pub mod factory;
+#[cfg(feature = "fixtures")]
+mod fixtures;
 #[cfg(feature = "axum")]
 mod handler;
+mod instrument;
+#[cfg(any(feature = "axum", feature = "dump"))]
+mod json_schema;
+mod memory;
+#[cfg(feature = "axum")]
+mod openapi;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+#[cfg(feature = "axum")]
+mod query;
+mod replicas;
+#[cfg(feature = "retry")]
+mod retry;
 mod store;
+mod tenant;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod traits;
 mod with_id;
 
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "mysql"))]
+pub use any::{AnyEntity, AnyStore, MySqlEntity, PostgresEntity, SqliteEntity};
+#[cfg(feature = "audit")]
+pub use audit::{AuditOperation, AuditedStore, HistoryEntry};
+pub use builder::{StoreBuilder, StoreConfig, Stores};
+#[cfg(feature = "changes")]
+pub use changes::{Change, ChangeOperation};
+#[cfg(feature = "dump")]
+pub use dump::DumpFormat;
+#[cfg(feature = "fixtures")]
+pub use fixtures::{FixtureFormat, Fixtures};
+#[cfg(feature = "axum")]
+pub use handler::problem::Problem;
+#[cfg(feature = "axum")]
+pub use handler::router::{Operation, RouterBuilder};
+pub use memory::MemoryStore;
 pub use miniorm_macros::Entity;
+#[cfg(feature = "axum")]
+pub use openapi::OpenApi;
+#[cfg(feature = "prometheus")]
+pub use prometheus::Metrics;
+#[cfg(feature = "axum")]
+pub use query::{FilterOp, InvalidQuery, ListQuery, Page};
+pub use replicas::ReplicaRouting;
+#[cfg(feature = "retry")]
+pub use retry::RetryPolicy;
 pub use store::Store;
+pub use tenant::TenantStore;
+#[cfg(feature = "serde")]
+pub use with_id::Flat;
 pub use with_id::WithId;
 
 /// Prelude including all the necessary traits for convenience
 pub mod prelude {
+    #[cfg(feature = "factory")]
+    pub use super::factory::Factory;
     pub use super::store::Store;
     #[cfg(feature = "axum")]
-    pub use super::traits::axum::IntoAxumRouter;
+    pub use super::traits::axum::{IntoAxumRouter, IntoOpenApi};
     pub use super::traits::bind_col::BindColumn;
-    pub use super::traits::crud::{Create, Crud, Delete, Read, Update};
+    pub use super::traits::crud::{Batch, Create, Crud, Delete, Read, Update};
     pub use super::traits::schema::Schema;
     pub use super::traits::sqlx::Bind;
     pub use super::traits::table::Table;
+    #[cfg(feature = "serde")]
+    pub use super::with_id::Flat;
     pub use super::with_id::WithId;
     pub use miniorm_macros::Entity;
 }
@@ -0,0 +1,336 @@
+use crate::{
+    prelude::{Batch, Create, Delete, Read, Table, Update},
+    WithId,
+};
+use async_trait::async_trait;
+use sqlx::Database;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// A `MemoryStore` is an in-memory stand-in for a [`Store`](crate::Store)
+/// which does not require any database.
+///
+/// It implements the same "CRUD" traits and behaves identically:
+/// - ids are auto-incremented starting from 1 and are never reused,
+/// - reading or deleting a missing id fails with [`sqlx::Error::RowNotFound`],
+/// - entities are listed by increasing id,
+/// - recreating the table clears all entities and resets the ids.
+///
+/// Entities are stored whole though, so that updating some columns of an
+/// entity, e.g. with [`Update::update_if`], writes all of them.
+///
+/// This makes it convenient to unit test code that is written against
+/// [`Crud`](crate::prelude::Crud).
+///
+/// # Example
+///
+/// ```
+/// use miniorm::{prelude::*, MemoryStore};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let store = MemoryStore::new();
+/// let todo = store.create("checkout miniorm").await.unwrap();
+/// assert_eq!(todo.id(), 1);
+/// assert_eq!(store.read(1).await.unwrap(), todo);
+/// # }
+/// ```
+pub struct MemoryStore<E> {
+    table: Arc<Mutex<MemoryTable<E>>>,
+}
+
+struct MemoryTable<E> {
+    rows: BTreeMap<i64, E>,
+    last_id: i64,
+}
+
+impl<E> MemoryStore<E> {
+    /// Create a new, empty, [`MemoryStore`]
+    pub fn new() -> Self {
+        let rows = BTreeMap::new();
+        let last_id = 0;
+        let table = Arc::new(Mutex::new(MemoryTable { rows, last_id }));
+        Self { table }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryTable<E>> {
+        // a panic while holding the lock cannot leave the table
+        // in an inconsistent state, so poisoning can be ignored.
+        self.table.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<E> Default for MemoryStore<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Clone for MemoryStore<E> {
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Table
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<DB: Database, E: Send> Table<DB> for MemoryStore<E>
+where
+    <DB as Database>::QueryResult: Default,
+{
+    async fn create_table(&self) -> sqlx::Result<<DB as Database>::QueryResult> {
+        Ok(Default::default())
+    }
+
+    async fn drop_table(&self) -> sqlx::Result<<DB as Database>::QueryResult> {
+        let mut table = self.lock();
+        table.rows.clear();
+        table.last_id = 0;
+        Ok(Default::default())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Create
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<E: Clone + Send + 'static> Create<E> for MemoryStore<E> {
+    async fn create(&self, entity: E) -> sqlx::Result<WithId<E>> {
+        let mut table = self.lock();
+        table.last_id += 1;
+        let id = table.last_id;
+        table.rows.insert(id, entity.clone());
+        Ok(WithId::new(entity, id))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Read
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<E: Clone + Send + 'static> Read<E> for MemoryStore<E> {
+    async fn read(&self, id: i64) -> sqlx::Result<WithId<E>> {
+        self.lock()
+            .rows
+            .get(&id)
+            .map(|entity| WithId::new(entity.clone(), id))
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn list(&self) -> sqlx::Result<Vec<WithId<E>>> {
+        let list = self
+            .lock()
+            .rows
+            .iter()
+            .map(|(id, entity)| WithId::new(entity.clone(), *id))
+            .collect();
+        Ok(list)
+    }
+
+    async fn count(&self) -> sqlx::Result<u64> {
+        Ok(self.lock().rows.len() as u64)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Update
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<E: Clone + Send + 'static> Update<E> for MemoryStore<E> {
+    async fn update(&self, entity: WithId<E>) -> sqlx::Result<WithId<E>> {
+        // just like `UPDATE ... WHERE id=...`, updating a missing
+        // id is not an error, it simply does not affect any row.
+        if let Some(row) = self.lock().rows.get_mut(&entity.id()) {
+            *row = entity.inner().clone();
+        }
+        Ok(entity)
+    }
+
+    // entities are stored whole, so that all the columns are written
+    // rather than only `_columns`, just like `update_columns` does.
+    async fn update_if<F>(
+        &self,
+        entity: WithId<E>,
+        _columns: &[&'static str],
+        check: F,
+    ) -> sqlx::Result<Option<WithId<E>>>
+    where
+        Self: Read<E> + Sync,
+        E: Send + 'async_trait,
+        F: Fn(&WithId<E>) -> bool + Send + Sync + 'async_trait,
+    {
+        let mut table = self.lock();
+        let row = table
+            .rows
+            .get_mut(&entity.id())
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if !check(&WithId::new(row.clone(), entity.id())) {
+            return Ok(None);
+        }
+        *row = entity.inner().clone();
+        Ok(Some(entity))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Delete
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<E: Send + 'static> Delete<E> for MemoryStore<E> {
+    async fn delete(&self, id: i64) -> sqlx::Result<()> {
+        self.lock()
+            .rows
+            .remove(&id)
+            .map(|_| ())
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn delete_all(&self) -> sqlx::Result<u64> {
+        let mut table = self.lock();
+        let count = table.rows.len() as u64;
+        table.rows.clear();
+        Ok(count)
+    }
+
+    async fn delete_if<F>(&self, id: i64, check: F) -> sqlx::Result<bool>
+    where
+        Self: Read<E> + Sync,
+        E: Send + 'async_trait,
+        F: Fn(&WithId<E>) -> bool + Send + Sync + 'async_trait,
+    {
+        let mut table = self.lock();
+        let entity = table.rows.remove(&id).ok_or(sqlx::Error::RowNotFound)?;
+        let entity = WithId::new(entity, id);
+        if check(&entity) {
+            return Ok(true);
+        }
+        table.rows.insert(id, entity.into_inner());
+        Ok(false)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Batch
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<E: Clone + Send + Sync + 'static> Batch<E> for MemoryStore<E> {
+    async fn create_many(&self, entities: Vec<E>) -> sqlx::Result<Vec<WithId<E>>> {
+        let mut table = self.lock();
+        let created = entities
+            .into_iter()
+            .map(|entity| {
+                table.last_id += 1;
+                let id = table.last_id;
+                table.rows.insert(id, entity.clone());
+                WithId::new(entity, id)
+            })
+            .collect();
+        Ok(created)
+    }
+
+    async fn update_many(&self, entities: Vec<WithId<E>>) -> sqlx::Result<Vec<WithId<E>>> {
+        let mut table = self.lock();
+        for entity in &entities {
+            if let Some(row) = table.rows.get_mut(&entity.id()) {
+                *row = entity.inner().clone();
+            }
+        }
+        Ok(entities)
+    }
+
+    async fn delete_many(&self, ids: &[i64]) -> sqlx::Result<()> {
+        let mut table = self.lock();
+        // check everything first, so that nothing is deleted on failure
+        let mut unique = ids.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+        if unique.len() != ids.len() || !ids.iter().all(|id| table.rows.contains_key(id)) {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        for id in ids {
+            table.rows.remove(id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{prelude::*, MemoryStore};
+
+    #[tokio::test]
+    async fn create_assigns_increasing_ids() {
+        let store = MemoryStore::new();
+        assert_eq!(store.create("todo1").await.unwrap().id(), 1);
+        assert_eq!(store.create("todo2").await.unwrap().id(), 2);
+        store.delete(2).await.unwrap();
+        assert_eq!(store.create("todo3").await.unwrap().id(), 3);
+    }
+
+    #[tokio::test]
+    async fn read_and_list() {
+        let store = MemoryStore::new();
+        let todo1 = store.create("todo1").await.unwrap();
+        let todo2 = store.create("todo2").await.unwrap();
+        assert_eq!(store.read(todo2.id()).await.unwrap(), todo2);
+        assert_eq!(store.list().await.unwrap(), [todo1, todo2]);
+        assert_eq!(store.count().await.unwrap(), 2);
+        assert!(matches!(store.read(3).await, Err(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn stream() {
+        use std::future::poll_fn;
+
+        let store = MemoryStore::new();
+        let todo1 = store.create("todo1").await.unwrap();
+        let todo2 = store.create("todo2").await.unwrap();
+        let mut stream = store.stream();
+        let mut streamed = Vec::new();
+        while let Some(todo) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+            streamed.push(todo.unwrap());
+        }
+        assert_eq!(streamed, [todo1, todo2]);
+    }
+
+    #[tokio::test]
+    async fn update() {
+        let store = MemoryStore::new();
+        let mut todo = store.create("todo").await.unwrap();
+        *todo.inner_mut() = "done";
+        store.update(todo).await.unwrap();
+        assert_eq!(store.read(1).await.unwrap().into_inner(), "done");
+
+        store.update(WithId::new("missing", 2)).await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn delete() {
+        let store = MemoryStore::new();
+        store.create("todo1").await.unwrap();
+        store.create("todo2").await.unwrap();
+        store.delete(1).await.unwrap();
+        assert!(matches!(
+            store.delete(1).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        assert_eq!(store.delete_all().await.unwrap(), 1);
+        assert_eq!(store.count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn recreate_table_resets_ids() {
+        let store = MemoryStore::new();
+        store.create("todo1").await.unwrap();
+        Table::<sqlx::Sqlite>::recreate_table(&store).await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 0);
+        assert_eq!(store.create("todo2").await.unwrap().id(), 1);
+    }
+}
@@ -0,0 +1,578 @@
+use axum::{routing::get, Json, Router};
+use serde_json::{json, Value};
+
+/// An [OpenAPI 3.1](https://spec.openapis.org/oas/v3.1.0) document describing
+/// the REST routes of one or several stores.
+///
+/// The document of a single store is obtained using
+/// [`IntoOpenApi::openapi`](crate::prelude::IntoOpenApi::openapi), and several
+/// documents can be combined using [`OpenApi::merge`].
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">axum</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+///
+/// # Example
+///
+/// ```no_run
+/// use axum::Router;
+/// use miniorm::{prelude::*, OpenApi};
+/// use serde::{Deserialize, Serialize};
+/// use sqlx::FromRow;
+///
+/// #[derive(Debug, Clone, FromRow, Entity, Serialize, Deserialize)]
+/// struct Todo {
+///     #[postgres(TEXT NOT NULL)]
+///     description: String,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = sqlx::PgPool::connect("postgres://localhost/miniorm").await?;
+/// let todos = Store::<_, Todo>::new(db);
+///
+/// let openapi = OpenApi::new("todo app", "1.0.0").merge(todos.openapi("/todos"));
+/// let app: Router = Router::new()
+///     .nest("/todos", todos.into_axum_router())
+///     .merge(openapi.into_axum_router());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenApi {
+    document: Value,
+}
+
+impl OpenApi {
+    /// Creates an empty document with the provided title and version.
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        let document = json!({
+            "openapi": "3.1.0",
+            "info": {
+                "title": title.into(),
+                "version": version.into(),
+            },
+            "paths": {},
+            "components": {
+                "schemas": {},
+            },
+        });
+        Self { document }
+    }
+
+    /// Creates the document describing the routes of
+    /// [`IntoAxumRouter::into_axum_router`](crate::prelude::IntoAxumRouter::into_axum_router)
+    /// for an entity, assuming that the router is mounted at `path`.
+    ///
+    /// The schema of the entity is its `json_schema`, i.e. its
+    /// [`Schema::MINIORM_JSON_SCHEMA`](crate::prelude::Schema::MINIORM_JSON_SCHEMA),
+    /// and its `columns` can be used to filter and sort the entities.
+    pub(crate) fn for_entity(name: &str, path: &str, json_schema: &str, columns: &[&str]) -> Self {
+        let mut openapi = Self::new(name, "0.0.0");
+        let schemas = &mut openapi.document["components"]["schemas"];
+        schemas[name] = crate::json_schema::entity_schema(json_schema);
+        schemas["Problem"] = json!({
+            "type": "object",
+            "properties": {
+                "type": {"type": "string", "format": "uri-reference"},
+                "title": {"type": "string"},
+                "status": {"type": "integer"},
+                "detail": {"type": "string"},
+            },
+            "required": ["type", "title", "status"],
+        });
+        schemas[format!("{name}WithId")] = json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer", "format": "int64"},
+                "inner": schema_ref(name),
+            },
+            "required": ["id", "inner"],
+        });
+
+        let root = match path.trim_end_matches('/') {
+            "" => "/".to_string(),
+            path => path.to_string(),
+        };
+        let item = format!("{}/{{id}}", root.trim_end_matches('/'));
+        let bulk = format!("{}/_bulk", root.trim_end_matches('/'));
+        let import = format!("{}/_import", root.trim_end_matches('/'));
+        let events = format!("{}/events", root.trim_end_matches('/'));
+        let paths = &mut openapi.document["paths"];
+        paths[root] = collection_path(name, columns);
+        paths[item] = item_path(name);
+        paths[bulk] = bulk_path(name);
+        paths[import] = import_path(name);
+        paths[events] = events_path(name);
+        openapi
+    }
+
+    /// Merges the paths and schemas of `other` into this document.
+    ///
+    /// The title and version of this document are kept; in case of
+    /// conflicting paths or schemas, those of `other` win.
+    pub fn merge(mut self, other: OpenApi) -> Self {
+        for section in ["paths", "components"] {
+            if let Value::Object(other) = &other.document[section] {
+                let this = self.document[section]
+                    .as_object_mut()
+                    .expect("section is an object");
+                for (key, value) in other {
+                    match (this.get_mut(key), value) {
+                        (Some(Value::Object(this)), Value::Object(other)) => {
+                            this.extend(other.clone())
+                        }
+                        _ => {
+                            this.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Only keeps the operations for which `keep(method, path)` is `true`,
+    /// where `method` is the uppercase HTTP method and `path` is the path of the
+    /// operation within the router (e.g. `/`, `/:id` or `/_bulk`). Paths without
+    /// operations are removed.
+    pub(crate) fn restrict(mut self, keep: impl Fn(&str, &str) -> bool) -> Self {
+        if let Value::Object(paths) = &mut self.document["paths"] {
+            // the collection path is a prefix of all the others
+            let root = paths.keys().min_by_key(|path| path.len()).cloned();
+            let root = root.unwrap_or_default();
+            for (path, item) in paths.iter_mut() {
+                let path = match path.strip_prefix(root.trim_end_matches('/')) {
+                    _ if *path == root => "/",
+                    Some("/{id}") => "/:id",
+                    Some(path) => path,
+                    None => continue,
+                };
+                if let Value::Object(item) = item {
+                    item.retain(|key, _| key == "parameters" || keep(&key.to_uppercase(), path));
+                }
+            }
+            paths.retain(|_, item| {
+                item.as_object()
+                    .is_some_and(|item| item.keys().any(|key| key != "parameters"))
+            });
+        }
+        self
+    }
+
+    /// Describes the entities with their id in the flattened representation
+    /// (see [`Flat`](crate::Flat)) instead of the nested one.
+    pub(crate) fn flat_ids(mut self) -> Self {
+        if let Value::Object(schemas) = &mut self.document["components"]["schemas"] {
+            for (key, schema) in schemas.iter_mut() {
+                let Some(name) = key.strip_suffix("WithId") else {
+                    continue;
+                };
+                *schema = json!({
+                    "allOf": [
+                        schema_ref(name),
+                        {
+                            "type": "object",
+                            "properties": {"id": {"type": "integer", "format": "int64"}},
+                            "required": ["id"],
+                        },
+                    ],
+                });
+            }
+        }
+        self
+    }
+
+    /// Renames the `id` path parameter.
+    pub(crate) fn rename_id_param(mut self, name: &str) -> Self {
+        if name == "id" {
+            return self;
+        }
+        if let Value::Object(paths) = &mut self.document["paths"] {
+            let renamed = std::mem::take(paths)
+                .into_iter()
+                .map(|(path, mut item)| match path.strip_suffix("/{id}") {
+                    Some(root) => {
+                        item["parameters"][0]["name"] = name.into();
+                        (format!("{root}/{{{name}}}"), item)
+                    }
+                    None => (path, item),
+                })
+                .collect();
+            *paths = renamed;
+        }
+        self
+    }
+
+    /// Returns the document as JSON.
+    pub fn as_json(&self) -> &Value {
+        &self.document
+    }
+
+    /// Converts the document into an [`Router`] serving it at `GET /openapi.json`.
+    pub fn into_axum_router<S: Clone + Send + Sync + 'static>(self) -> Router<S> {
+        let document = Json(self.document);
+        Router::new().route("/openapi.json", get(|| async move { document }))
+    }
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({"$ref": format!("#/components/schemas/{name}")})
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({"application/json": {"schema": schema}})
+}
+
+/// Response with one entity, along with its `ETag`
+fn ok_response(schema: Value) -> Value {
+    json!({
+        "description": "OK",
+        "headers": {
+            "ETag": {
+                "description": "entity tag of the returned entity",
+                "schema": {"type": "string"},
+            },
+        },
+        "content": json_content(schema),
+    })
+}
+
+/// Response with the created entity, along with its `ETag` and `Location`
+fn created_response(schema: Value) -> Value {
+    let mut response = ok_response(schema);
+    response["description"] = "Created".into();
+    response["headers"]["Location"] = json!({
+        "description": "path of the created entity",
+        "schema": {"type": "string"},
+    });
+    response
+}
+
+fn header_parameter(name: &str, description: &str) -> Value {
+    json!({
+        "name": name, "in": "header",
+        "description": description,
+        "schema": {"type": "string"},
+    })
+}
+
+fn if_match() -> Value {
+    header_parameter(
+        "If-Match",
+        "only proceed if the current entity tag matches one of the provided ones",
+    )
+}
+
+/// Response with an `application/problem+json` body
+fn error_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {"application/problem+json": {"schema": schema_ref("Problem")}},
+    })
+}
+
+fn collection_path(name: &str, columns: &[&str]) -> Value {
+    let with_id = schema_ref(&format!("{name}WithId"));
+    let mut parameters = vec![
+        json!({
+            "name": "limit", "in": "query",
+            "description": "maximum number of entities to return",
+            "schema": {"type": "integer", "minimum": 0},
+        }),
+        json!({
+            "name": "offset", "in": "query",
+            "description": "number of entities to skip",
+            "schema": {"type": "integer", "minimum": 0},
+        }),
+        json!({
+            "name": "sort", "in": "query",
+            "description": "comma-separated list of columns, prefixed with `-` for descending order",
+            "schema": {"type": "string"},
+        }),
+    ];
+    for column in std::iter::once("id").chain(columns.iter().copied()) {
+        parameters.push(json!({
+            "name": column, "in": "query",
+            "description": format!(
+                "only keep the entities whose `{column}` is equal to the value \
+                (`{column}__<op>` with `<op>` one of eq, ne, lt, lte, gt, gte, like \
+                is also accepted)"
+            ),
+            "schema": {"type": "string"},
+        }));
+    }
+
+    let precondition_failed = error_response("The entity was modified in the meantime");
+    json!({
+        "get": {
+            "summary": format!("List {name} entities"),
+            "parameters": parameters,
+            "responses": {
+                "200": {
+                    "description": "OK",
+                    "headers": {
+                        "X-Total-Count": {
+                            "description": "number of entities matching the filters",
+                            "schema": {"type": "integer"},
+                        },
+                        "Link": {
+                            "description": "links to the first, previous, next and last pages",
+                            "schema": {"type": "string"},
+                        },
+                    },
+                    "content": {
+                        "application/json": {"schema": {"type": "array", "items": with_id}},
+                        "application/x-ndjson": {"schema": with_id},
+                        "text/csv": {"schema": {"type": "string"}},
+                    },
+                },
+                "400": error_response("Invalid query parameters"),
+            },
+        },
+        "post": {
+            "summary": format!("Create a {name}"),
+            "requestBody": {"required": true, "content": json_content(schema_ref(name))},
+            "responses": {
+                "201": created_response(with_id.clone()),
+                "422": error_response("Invalid entity"),
+            },
+        },
+        "put": {
+            "summary": format!("Update a {name}"),
+            "parameters": [if_match()],
+            "requestBody": {"required": true, "content": json_content(with_id.clone())},
+            "responses": {
+                "200": ok_response(with_id),
+                "412": precondition_failed,
+                "422": error_response("Invalid entity"),
+            },
+        },
+        "delete": {
+            "summary": format!("Delete all {name} entities"),
+            "responses": {"204": {"description": "No Content"}},
+        },
+    })
+}
+
+fn bulk_path(name: &str) -> Value {
+    let with_id = schema_ref(&format!("{name}WithId"));
+    let all = |schema: Value| json!({"type": "array", "items": schema});
+    let ok = json!({"description": "OK", "content": json_content(all(with_id.clone()))});
+    let created = json!({"description": "Created", "content": json_content(all(with_id.clone()))});
+    json!({
+        "post": {
+            "summary": format!("Create several {name} entities at once"),
+            "requestBody": {"required": true, "content": json_content(all(schema_ref(name)))},
+            "responses": {
+                "201": created,
+                "422": error_response("Invalid entities"),
+            },
+        },
+        "put": {
+            "summary": format!("Update several {name} entities at once"),
+            "requestBody": {"required": true, "content": json_content(all(with_id))},
+            "responses": {
+                "200": ok,
+                "422": error_response("Invalid entities"),
+            },
+        },
+        "delete": {
+            "summary": format!("Delete several {name} entities at once"),
+            "requestBody": {
+                "required": true,
+                "content": json_content(all(json!({"type": "integer", "format": "int64"}))),
+            },
+            "responses": {
+                "204": {"description": "No Content"},
+                "404": error_response("One of the entities was not found, none was deleted"),
+            },
+        },
+    })
+}
+
+fn import_path(name: &str) -> Value {
+    json!({
+        "post": {
+            "summary": format!("Import {name} entities from NDJSON or CSV"),
+            "requestBody": {
+                "required": true,
+                "content": {
+                    "application/x-ndjson": {"schema": schema_ref(name)},
+                    "text/csv": {"schema": {"type": "string"}},
+                },
+            },
+            "responses": {
+                "200": {
+                    "description": "OK",
+                    "content": json_content(json!({
+                        "type": "object",
+                        "properties": {"created": {"type": "integer"}},
+                        "required": ["created"],
+                    })),
+                },
+                "400": error_response("Malformed body"),
+                "415": error_response("Unsupported content type"),
+                "422": error_response("Invalid entity"),
+            },
+        },
+    })
+}
+
+fn events_path(name: &str) -> Value {
+    json!({
+        "get": {
+            "summary": format!("Stream the changes of the {name} entities"),
+            "description": "Server-sent events named `create` and `update` (with the entity \
+                as data), `delete` (with the id of the entity as data) and `delete_all`.",
+            "responses": {
+                "200": {
+                    "description": "OK",
+                    "content": {"text/event-stream": {"schema": {"type": "string"}}},
+                },
+            },
+        },
+    })
+}
+
+fn item_path(name: &str) -> Value {
+    let with_id = schema_ref(&format!("{name}WithId"));
+    let not_found = error_response("Not found");
+    let precondition_failed = error_response("The entity was modified in the meantime");
+    json!({
+        "parameters": [{
+            "name": "id", "in": "path", "required": true,
+            "schema": {"type": "integer", "format": "int64"},
+        }],
+        "get": {
+            "summary": format!("Read a {name}"),
+            "parameters": [header_parameter(
+                "If-None-Match",
+                "only return the entity if its entity tag matches none of the provided ones",
+            )],
+            "responses": {
+                "200": ok_response(with_id.clone()),
+                "304": {"description": "Not modified"},
+                "404": not_found,
+            },
+        },
+        "put": {
+            "summary": format!("Update a {name}"),
+            "parameters": [if_match()],
+            "requestBody": {"required": true, "content": json_content(schema_ref(name))},
+            "responses": {
+                "200": ok_response(with_id.clone()),
+                "404": not_found,
+                "412": precondition_failed,
+                "422": error_response("Invalid entity"),
+            },
+        },
+        "patch": {
+            "summary": format!("Partially update a {name}"),
+            "parameters": [if_match()],
+            "requestBody": {
+                "required": true,
+                "content": {
+                    "application/merge-patch+json": {"schema": {"type": "object"}},
+                    "application/json-patch+json": {
+                        "schema": {"type": "array", "items": {"type": "object"}},
+                    },
+                },
+            },
+            "responses": {
+                "200": ok_response(with_id),
+                "400": error_response("Invalid patch"),
+                "404": not_found,
+                "409": error_response("A test operation of the patch failed"),
+                "412": precondition_failed,
+                "415": error_response("Unsupported patch format"),
+                "422": error_response("Invalid entity"),
+            },
+        },
+        "delete": {
+            "summary": format!("Delete a {name}"),
+            "parameters": [if_match()],
+            "responses": {
+                "204": {"description": "No Content"},
+                "404": not_found,
+                "412": precondition_failed,
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::OpenApi;
+
+    const JSON_SCHEMA: &str = r#"{
+        "type": "object",
+        "properties": {"description": {"type": "string"}},
+        "required": ["description"]
+    }"#;
+
+    #[test]
+    fn restrict() {
+        let openapi = OpenApi::for_entity("Todo", "/todos", JSON_SCHEMA, &["description"])
+            .restrict(|method, path| method == "GET" || (method == "PUT" && path == "/:id"))
+            .rename_id_param("todo_id");
+        let paths = &openapi.as_json()["paths"];
+        let methods = |path: &str| {
+            let mut methods: Vec<_> = paths[path].as_object().unwrap().keys().cloned().collect();
+            methods.sort();
+            methods
+        };
+        assert_eq!(methods("/todos"), ["get"]);
+        assert_eq!(methods("/todos/{todo_id}"), ["get", "parameters", "put"]);
+        assert_eq!(
+            paths["/todos/{todo_id}"]["parameters"][0]["name"],
+            "todo_id"
+        );
+
+        let openapi = OpenApi::for_entity("Todo", "/todos", JSON_SCHEMA, &["description"])
+            .restrict(|_, path| path == "/:id");
+        assert!(openapi.as_json()["paths"].get("/todos").is_none());
+        assert!(openapi.as_json()["paths"].get("/todos/events").is_none());
+    }
+
+    #[test]
+    fn merge() {
+        let todos = OpenApi::for_entity("Todo", "/todos", JSON_SCHEMA, &["description"]);
+        let users = OpenApi::for_entity("User", "/users/", JSON_SCHEMA, &["description"]);
+        let openapi = OpenApi::new("app", "1.0").merge(todos).merge(users);
+        let json = openapi.as_json();
+        assert_eq!(json["info"]["title"], "app");
+        let mut paths: Vec<_> = json["paths"].as_object().unwrap().keys().collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                "/todos",
+                "/todos/_bulk",
+                "/todos/_import",
+                "/todos/events",
+                "/todos/{id}",
+                "/users",
+                "/users/_bulk",
+                "/users/_import",
+                "/users/events",
+                "/users/{id}"
+            ]
+        );
+        let mut schemas: Vec<_> = json["components"]["schemas"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect();
+        schemas.sort();
+        assert_eq!(
+            schemas,
+            ["Problem", "Todo", "TodoWithId", "User", "UserWithId"]
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use axum::{http::header, routing::get, Router};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+
+/// Buckets of the `miniorm_operation_duration_seconds` histogram, from 1ms to 10s.
+const DURATION_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The metrics recorded by the stores, in the
+/// [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/).
+///
+/// The metrics themselves are listed in the documentation of [`Store`](crate::Store).
+/// They are recorded using the [`metrics`](https://docs.rs/metrics) facade, so
+/// [`Metrics::install`] should be called once, before using the stores, to install
+/// a Prometheus recorder, unless the application already installed one, in which
+/// case its handle can be converted into [`Metrics`].
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">prometheus</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+///
+/// # Example
+///
+/// ```no_run
+/// use axum::Router;
+/// use miniorm::{prelude::*, Metrics};
+/// use serde::{Deserialize, Serialize};
+/// use sqlx::FromRow;
+///
+/// #[derive(Debug, Clone, FromRow, Entity, Serialize, Deserialize)]
+/// struct Todo {
+///     #[postgres(TEXT NOT NULL)]
+///     description: String,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let metrics = Metrics::install()?;
+/// let db = sqlx::PgPool::connect("postgres://localhost/miniorm").await?;
+/// let todos = Store::<_, Todo>::new(db);
+///
+/// let app: Router = Router::new()
+///     .nest("/todos", todos.into_axum_router())
+///     .merge(metrics.into_axum_router());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    handle: PrometheusHandle,
+}
+
+impl Metrics {
+    /// Returns a [`PrometheusBuilder`] rendering the duration of the operations
+    /// as a histogram, to be further configured before installing it.
+    pub fn builder() -> PrometheusBuilder {
+        PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Full("miniorm_operation_duration_seconds".into()),
+                DURATION_BUCKETS,
+            )
+            .expect("buckets are not empty")
+    }
+
+    /// Installs the recorder returned by [`Metrics::builder`] as the global recorder.
+    ///
+    /// Fails if a global recorder was already installed.
+    pub fn install() -> Result<Self, BuildError> {
+        Ok(Self::builder().install_recorder()?.into())
+    }
+
+    /// Renders the metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        self.handle.run_upkeep();
+        self.handle.render()
+    }
+
+    /// Converts the metrics into an [`Router`] serving them at `GET /metrics`.
+    pub fn into_axum_router<S: Clone + Send + Sync + 'static>(self) -> Router<S> {
+        let content_type = [(header::CONTENT_TYPE, "text/plain; version=0.0.4")];
+        let render = move || async move { (content_type, self.render()) };
+        Router::new().route("/metrics", get(render))
+    }
+}
+
+impl From<PrometheusHandle> for Metrics {
+    fn from(handle: PrometheusHandle) -> Self {
+        Self { handle }
+    }
+}
@@ -0,0 +1,560 @@
+use crate::{traits::sqlx::Dialect, WithId};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{database::HasArguments, query::QueryAs, Database, Encode, Type};
+use std::cmp::Ordering;
+
+/// Filters, sort keys and pagination of a list of entities, run by
+/// [`Read::query`](crate::prelude::Read::query).
+///
+/// The columns are the [`Schema::MINIORM_COLUMNS`](crate::prelude::Schema::MINIORM_COLUMNS)
+/// (or `id`), and the entities are sorted by `id` after the sort keys.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">axum</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+///
+/// # Example
+///
+/// ```
+/// use miniorm::{FilterOp, ListQuery};
+///
+/// let query = ListQuery::new()
+///     .filter("done", FilterOp::Eq, false)
+///     .filter("description", FilterOp::Like, "buy %")
+///     .sort_desc("description")
+///     .limit(10)
+///     .offset(20);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListQuery {
+    pub(crate) limit: Option<u64>,
+    pub(crate) offset: u64,
+    sort: Vec<SortKey>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SortKey {
+    column: String,
+    descending: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Filter {
+    column: String,
+    op: FilterOp,
+    value: Value,
+}
+
+/// Comparison between a column and a value in a [`ListQuery`].
+///
+/// Just like in SQL, a `NULL` column only matches [`FilterOp::Eq`] with a null
+/// value (i.e. `IS NULL`), and [`FilterOp::Ne`] with a non null value does not
+/// match it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterOp {
+    /// `=`, or `IS NULL` with a null value
+    Eq,
+    /// `<>`, or `IS NOT NULL` with a null value
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Lte,
+    /// `>`
+    Gt,
+    /// `>=`
+    Gte,
+    /// `LIKE`, where `%` matches any sequence of characters and `_` any single
+    /// character; whether it is case sensitive depends on the database.
+    Like,
+}
+
+impl FilterOp {
+    /// Parses the suffix of a filter in a query string, e.g. `lte`.
+    pub(crate) fn parse(suffix: &str) -> Option<Self> {
+        match suffix {
+            "eq" => Some(FilterOp::Eq),
+            "ne" => Some(FilterOp::Ne),
+            "lt" => Some(FilterOp::Lt),
+            "lte" => Some(FilterOp::Lte),
+            "gt" => Some(FilterOp::Gt),
+            "gte" => Some(FilterOp::Gte),
+            "like" => Some(FilterOp::Like),
+            _ => None,
+        }
+    }
+
+    fn sql(&self) -> &'static str {
+        match self {
+            FilterOp::Eq => "=",
+            FilterOp::Ne => "<>",
+            FilterOp::Lt => "<",
+            FilterOp::Lte => "<=",
+            FilterOp::Gt => ">",
+            FilterOp::Gte => ">=",
+            FilterOp::Like => "LIKE",
+        }
+    }
+}
+
+/// One page of a list returned by [`Read::query`](crate::prelude::Read::query),
+/// along with the total number of entities matching the filters.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">axum</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<E> {
+    /// number of entities matching the filters, regardless of the pagination
+    pub total: u64,
+    /// entities of the page
+    pub items: Vec<WithId<E>>,
+}
+
+/// Error returned by [`Read::query`](crate::prelude::Read::query), as a
+/// [`sqlx::Error::Decode`], when the [`ListQuery`] cannot be run, e.g. because
+/// it refers to an unknown column.
+///
+/// [`Problem::from_sqlx_error`](crate::Problem::from_sqlx_error) maps it to a
+/// `400 Bad Request`.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">axum</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidQuery(String);
+
+impl InvalidQuery {
+    fn unknown_column(column: &str) -> sqlx::Error {
+        Self(format!("unknown column '{column}'")).into()
+    }
+}
+
+impl std::fmt::Display for InvalidQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid query: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidQuery {}
+
+impl From<InvalidQuery> for sqlx::Error {
+    fn from(err: InvalidQuery) -> Self {
+        sqlx::Error::Decode(Box::new(err))
+    }
+}
+
+/// Queries generated for a [`ListQuery`], along with the values to bind to
+/// their placeholders: the filters, then (for `select` only) the limit and
+/// the offset.
+pub(crate) struct Sql<'a> {
+    pub(crate) select: String,
+    pub(crate) count: String,
+    pub(crate) values: Vec<&'a Value>,
+}
+
+impl ListQuery {
+    /// Creates a query listing all the entities, ordered by `id`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keeps the entities whose `column` compares with `value` as `op`.
+    pub fn filter(
+        mut self,
+        column: impl Into<String>,
+        op: FilterOp,
+        value: impl Into<Value>,
+    ) -> Self {
+        let column = column.into();
+        let value = value.into();
+        self.filters.push(Filter { column, op, value });
+        self
+    }
+
+    /// Sorts the entities by increasing `column`, after the previous sort keys.
+    pub fn sort(mut self, column: impl Into<String>) -> Self {
+        let column = column.into();
+        self.sort.push(SortKey {
+            column,
+            descending: false,
+        });
+        self
+    }
+
+    /// Sorts the entities by decreasing `column`, after the previous sort keys.
+    pub fn sort_desc(mut self, column: impl Into<String>) -> Self {
+        let column = column.into();
+        self.sort.push(SortKey {
+            column,
+            descending: true,
+        });
+        self
+    }
+
+    /// Returns at most `limit` entities.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skips the first `offset` entities.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Filters, sorts and paginates the provided entities, ordered by id,
+    /// comparing the filters with the fields of the serialized entities, and
+    /// failing with an [`InvalidQuery`] if the query refers to a column which
+    /// is not one of their fields (or `id`).
+    pub(crate) fn apply<E: Serialize>(&self, entities: Vec<WithId<E>>) -> sqlx::Result<Page<E>> {
+        let mut rows = entities
+            .into_iter()
+            .map(|entity| Ok((serde_json::to_value(entity.inner())?, entity)))
+            .collect::<serde_json::Result<Vec<_>>>()
+            .map_err(|err| InvalidQuery(format!("cannot compare the entities: {err}")))?;
+
+        if let Some((json, _)) = rows.first() {
+            let columns = self.filters.iter().map(|filter| &filter.column);
+            let columns = columns.chain(self.sort.iter().map(|key| &key.column));
+            for column in columns {
+                if column != "id" && json.get(column).is_none() {
+                    return Err(InvalidQuery::unknown_column(column));
+                }
+            }
+        }
+
+        rows.retain(|(json, entity)| {
+            self.filters.iter().all(|filter| {
+                let value = field(json, entity.id(), &filter.column);
+                filter.matches(&value)
+            })
+        });
+
+        // `sort_by` is stable so entities remain ordered by id
+        // when they are equal according to the sort keys.
+        rows.sort_by(|(left_json, left), (right_json, right)| {
+            self.sort
+                .iter()
+                .map(|key| {
+                    let left = field(left_json, left.id(), &key.column);
+                    let right = field(right_json, right.id(), &key.column);
+                    let ordering = compare(&left, &right);
+                    if key.descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        let total = rows.len() as u64;
+        let items = rows
+            .into_iter()
+            .skip(self.offset as usize)
+            .take(self.limit.map_or(usize::MAX, |limit| limit as usize))
+            .map(|(_, entity)| entity)
+            .collect();
+        Ok(Page { total, items })
+    }
+
+    /// Returns the `SELECT` of the page and the `SELECT COUNT` of the entities
+    /// matching the filters in `table`, failing with an [`InvalidQuery`] if the
+    /// query refers to a column which is not one of `columns` (or `id`).
+    ///
+    /// `schema` is the JSON schema of the entity, whose `format`s tell how to
+    /// compare strings with the columns (e.g. `date-time`). The entities may be
+    /// restricted to the ones whose `scope` column (e.g. the tenant) is equal to
+    /// the provided value.
+    pub(crate) fn to_sql<'a, DB: Dialect>(
+        &'a self,
+        table: &str,
+        columns: &[&str],
+        schema: &Value,
+        scope: Option<(&str, &'a Value)>,
+    ) -> sqlx::Result<Sql<'a>> {
+        let check = |column: &str| {
+            if column == "id" || columns.contains(&column) {
+                Ok(())
+            } else {
+                Err(InvalidQuery::unknown_column(column))
+            }
+        };
+
+        let mut values = Vec::new();
+        let mut conditions = Vec::new();
+        if let Some((column, value)) = scope {
+            values.push(value);
+            conditions.push(format!("{column} = {}", DB::placeholder(1)));
+        }
+        for Filter { column, op, value } in &self.filters {
+            check(column)?;
+            let condition = match (value, op) {
+                (Value::Null, FilterOp::Eq) => format!("{column} IS NULL"),
+                (Value::Null, FilterOp::Ne) => format!("{column} IS NOT NULL"),
+                (Value::Null, _) => "1 = 0".into(),
+                (value, op) => {
+                    values.push(value);
+                    let mut placeholder = DB::placeholder(values.len());
+                    let format = schema["properties"][column]["format"].as_str();
+                    if let (Value::String(_), Some(format)) = (value, format) {
+                        placeholder = DB::cast_string(placeholder, format);
+                    }
+                    format!("{column} {} {placeholder}", op.sql())
+                }
+            };
+            conditions.push(condition);
+        }
+        let filters = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+
+        let mut sort = Vec::new();
+        for SortKey { column, descending } in &self.sort {
+            check(column)?;
+            sort.push(if *descending {
+                format!("{column} DESC")
+            } else {
+                column.clone()
+            });
+        }
+        sort.push("id".into());
+
+        let limit = DB::placeholder(values.len() + 1);
+        let offset = DB::placeholder(values.len() + 2);
+        let select = format!(
+            "SELECT {}, id FROM {table}{filters} ORDER BY {} LIMIT {limit} OFFSET {offset}",
+            columns.join(", "),
+            sort.join(", ")
+        );
+        let count = format!("SELECT COUNT(id) AS count FROM {table}{filters}");
+        Ok(Sql {
+            select,
+            count,
+            values,
+        })
+    }
+}
+
+/// Binds the value of a filter generated by [`ListQuery::to_sql`], see
+/// [`Dialect::bind_json`].
+#[cfg_attr(
+    not(any(feature = "postgres", feature = "sqlite", feature = "mysql")),
+    allow(dead_code)
+)]
+pub(crate) fn bind_json<'q, DB, O>(
+    query: QueryAs<'q, DB, O, <DB as HasArguments<'q>>::Arguments>,
+    value: &Value,
+) -> QueryAs<'q, DB, O, <DB as HasArguments<'q>>::Arguments>
+where
+    DB: Database,
+    bool: Type<DB> + Encode<'q, DB>,
+    i64: Type<DB> + Encode<'q, DB>,
+    f64: Type<DB> + Encode<'q, DB>,
+    String: Type<DB> + Encode<'q, DB>,
+{
+    match value {
+        Value::Bool(value) => query.bind(*value),
+        Value::Number(number) => match number.as_i64() {
+            Some(number) => query.bind(number),
+            None => query.bind(number.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(value) => query.bind(value.clone()),
+        other => query.bind(other.to_string()),
+    }
+}
+
+impl Filter {
+    fn matches(&self, value: &Value) -> bool {
+        match (&self.value, self.op) {
+            (Value::Null, FilterOp::Eq) => value.is_null(),
+            (Value::Null, FilterOp::Ne) => !value.is_null(),
+            (Value::Null, _) => false,
+            _ if value.is_null() => false,
+            (expected, FilterOp::Eq) => compare(value, expected).is_eq(),
+            (expected, FilterOp::Ne) => compare(value, expected).is_ne(),
+            (expected, FilterOp::Lt) => compare(value, expected).is_lt(),
+            (expected, FilterOp::Lte) => compare(value, expected).is_le(),
+            (expected, FilterOp::Gt) => compare(value, expected).is_gt(),
+            (expected, FilterOp::Gte) => compare(value, expected).is_ge(),
+            (pattern, FilterOp::Like) => like(&to_text(value), &to_text(pattern)),
+        }
+    }
+}
+
+/// Returns the value of the `column` of an entity serialized as JSON
+fn field(json: &Value, id: i64, column: &str) -> Value {
+    if column == "id" {
+        Value::from(id)
+    } else {
+        json.get(column).cloned().unwrap_or(Value::Null)
+    }
+}
+
+/// Renders a value the way it would appear in a query string
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Returns `true` if `text` matches the `LIKE` pattern, case sensitively.
+///
+/// Only the last `%` is ever backtracked to, so that this takes at most
+/// `text.len() * pattern.len()` steps, whatever the number of `%`.
+fn like(text: &str, pattern: &str) -> bool {
+    let text: Vec<_> = text.chars().collect();
+    let pattern: Vec<_> = pattern.chars().collect();
+    // position in the pattern after the last `%`, and in the text where
+    // the rest of the pattern is being matched
+    let mut backtrack = None;
+    let (mut t, mut p) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('%') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&c) if c == '_' || c == text[t] => {
+                t += 1;
+                p += 1;
+            }
+            // the last `%` matches one more character
+            _ => match backtrack {
+                Some((after, start)) => {
+                    p = after;
+                    t = start + 1;
+                    backtrack = Some((after, t));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '%')
+}
+
+/// Total order on JSON values: null < booleans < numbers < strings < others
+fn compare(left: &Value, right: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    match (left, right) {
+        (Value::Bool(left), Value::Bool(right)) => left.cmp(right),
+        (Value::Number(left), Value::Number(right)) => {
+            let left = left.as_f64().unwrap_or(f64::NAN);
+            let right = right.as_f64().unwrap_or(f64::NAN);
+            left.total_cmp(&right)
+        }
+        (Value::String(left), Value::String(right)) => left.cmp(right),
+        (left, right) if rank(left) == rank(right) => left.to_string().cmp(&right.to_string()),
+        (left, right) => rank(left).cmp(&rank(right)),
+    }
+}
+
+#[cfg(all(test, feature = "postgres"))]
+mod test {
+    use super::{like, FilterOp, ListQuery};
+    use serde_json::{json, Value};
+    use sqlx::Postgres;
+
+    #[test]
+    fn like_patterns() {
+        assert!(like("buy milk", "buy%"));
+        assert!(like("buy milk", "%milk"));
+        assert!(like("buy milk", "b_y %"));
+        assert!(like("buy milk", "%"));
+        assert!(!like("buy milk", "milk"));
+        assert!(!like("buy milk", "BUY%"));
+        assert!(!like("buy", "buy_"));
+        assert!(like("buy milk", "%m%k"));
+        assert!(like("buy milk", "b%%_"));
+        assert!(!like("buy milk", "%m%x"));
+        assert!(like("", "%"));
+        assert!(!like("", "_"));
+    }
+
+    #[test]
+    fn like_pathological_pattern() {
+        let text = "a".repeat(10_000);
+        let pattern = format!("{}b", "%a".repeat(20));
+        assert!(!like(&text, &pattern));
+        assert!(like(&format!("{text}b"), &pattern));
+    }
+
+    #[test]
+    fn to_sql() {
+        let schema = json!({"properties": {"due": {"type": "string", "format": "date"}}});
+        let query = ListQuery::new()
+            .filter("done", FilterOp::Eq, false)
+            .filter("due", FilterOp::Lt, "2024-01-01")
+            .filter("description", FilterOp::Ne, Value::Null)
+            .sort_desc("due")
+            .limit(10);
+        let sql = query
+            .to_sql::<Postgres>("todo", &["description", "done", "due"], &schema, None)
+            .unwrap();
+        let filters = "WHERE done = $1 AND due < CAST($2 AS DATE) AND description IS NOT NULL";
+        assert_eq!(
+            sql.select,
+            format!(
+                "SELECT description, done, due, id FROM todo {filters} \
+                ORDER BY due DESC, id LIMIT $3 OFFSET $4"
+            )
+        );
+        assert_eq!(
+            sql.count,
+            format!("SELECT COUNT(id) AS count FROM todo {filters}")
+        );
+        assert_eq!(sql.values, [&json!(false), &json!("2024-01-01")]);
+
+        let query = ListQuery::new().sort("nope");
+        let err = query.to_sql::<Postgres>("todo", &["done"], &schema, None);
+        let Err(sqlx::Error::Decode(err)) = err else {
+            panic!("expected an invalid query");
+        };
+        assert_eq!(err.to_string(), "invalid query: unknown column 'nope'");
+
+        let query = ListQuery::new().filter("done", FilterOp::Eq, true);
+        let tenant = json!(7);
+        let scope = Some(("tenant_id", &tenant));
+        let sql = query
+            .to_sql::<Postgres>("todo", &["done"], &schema, scope)
+            .unwrap();
+        assert_eq!(
+            sql.select,
+            "SELECT done, id FROM todo WHERE tenant_id = $1 AND done = $2 \
+            ORDER BY id LIMIT $3 OFFSET $4"
+        );
+    }
+}
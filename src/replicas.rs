@@ -0,0 +1,89 @@
+use sqlx::{Database, Pool};
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Strategy choosing the replica serving a read of a [`Store`](crate::Store)
+/// (see [`Store::with_replicas`](crate::Store::with_replicas)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplicaRouting {
+    /// Each replica in turn.
+    #[default]
+    RoundRobin,
+    /// The replica with the fewest connections in use, each replica in turn
+    /// in case of a tie.
+    LeastBusy,
+}
+
+/// The read replicas of a store.
+pub(crate) struct Replicas<DB: Database> {
+    pools: Arc<[Pool<DB>]>,
+    routing: ReplicaRouting,
+    next: Arc<AtomicUsize>,
+    /// Set once the session of the store wrote to the primary, if the store
+    /// is a session.
+    wrote: Option<Arc<AtomicBool>>,
+}
+
+impl<DB: Database> Replicas<DB> {
+    pub(crate) fn new(pools: impl IntoIterator<Item = Pool<DB>>, routing: ReplicaRouting) -> Self {
+        Self {
+            pools: pools.into_iter().collect(),
+            routing,
+            next: Arc::default(),
+            wrote: None,
+        }
+    }
+
+    /// Returns the same replicas, for a new session.
+    pub(crate) fn session(&self) -> Self {
+        let wrote = Some(Arc::default());
+        Self {
+            wrote,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the replica serving the next read, unless the read should be
+    /// served by the primary.
+    pub(crate) fn reader(&self) -> Option<&Pool<DB>> {
+        let len = self.pools.len();
+        let wrote = self.wrote.as_ref();
+        if len == 0 || wrote.is_some_and(|wrote| wrote.load(Ordering::Acquire)) {
+            return None;
+        }
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut replicas = (0..len).map(|i| &self.pools[(next + i) % len]);
+        match self.routing {
+            ReplicaRouting::RoundRobin => replicas.next(),
+            ReplicaRouting::LeastBusy => {
+                replicas.min_by_key(|pool| (pool.size() as usize).saturating_sub(pool.num_idle()))
+            }
+        }
+    }
+
+    /// Records that the session, if any, is about to write to the primary.
+    pub(crate) fn write(&self) {
+        if let Some(wrote) = &self.wrote {
+            wrote.store(true, Ordering::Release);
+        }
+    }
+}
+
+impl<DB: Database> Default for Replicas<DB> {
+    fn default() -> Self {
+        Self::new([], ReplicaRouting::default())
+    }
+}
+
+impl<DB: Database> Clone for Replicas<DB> {
+    fn clone(&self) -> Self {
+        Self {
+            pools: self.pools.clone(),
+            routing: self.routing,
+            next: self.next.clone(),
+            wrote: self.wrote.clone(),
+        }
+    }
+}
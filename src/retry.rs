@@ -0,0 +1,242 @@
+use std::{future::Future, time::Duration};
+
+/// Policy retrying the operations of a [`Store`](crate::Store) which fail
+/// because of a transient error, such as a deadlock or a serialization failure,
+/// after an exponential backoff with jitter.
+///
+/// The errors considered transient depend on the database:
+///
+/// | database   | errors                                                              |
+/// |------------|---------------------------------------------------------------------|
+/// | PostgreSQL | serialization failure (`40001`), deadlock (`40P01`), lock not available (`55P03`) |
+/// | MySQL      | deadlock (`1213`), lock wait timeout (`1205`)                       |
+/// | SQLite     | database busy (`SQLITE_BUSY`) or locked (`SQLITE_LOCKED`)           |
+///
+/// along with the I/O errors and the timeouts when acquiring a connection from
+/// the pool, for all the databases.
+///
+/// Note that an I/O error does not tell whether the operation was applied:
+/// the connection may have been lost after the database committed it. The
+/// operations are therefore only retried if running them again has the same
+/// outcome as running them once, which is not the case of:
+/// - `create` and `create_many`, which would insert the entities twice,
+/// - `delete` and `delete_many`, which would fail with
+///   [`sqlx::Error::RowNotFound`] after a successful first attempt.
+///
+/// Those are only retried if [`RetryPolicy::retry_non_idempotent`] is set.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">retry</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+///
+/// # Example
+///
+/// ```no_run
+/// use miniorm::{prelude::*, RetryPolicy};
+/// use sqlx::FromRow;
+/// use std::time::Duration;
+///
+/// #[derive(Debug, Clone, FromRow, Entity)]
+/// struct Todo {
+///     #[postgres(TEXT NOT NULL)]
+///     description: String,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = sqlx::PgPool::connect("postgres://localhost/miniorm").await?;
+/// let retry = RetryPolicy::new(5).initial_backoff(Duration::from_millis(20));
+/// let todos = Store::<_, Todo>::new(db).with_retry(retry);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    non_idempotent: bool,
+}
+
+impl RetryPolicy {
+    /// Creates a policy running an operation at most `max_attempts` times (including
+    /// the first one), waiting at most 10ms before the first retry, and doubling that
+    /// backoff after each retry, up to 1s.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            non_idempotent: false,
+        }
+    }
+
+    /// Creates a policy which never retries any operation.
+    pub fn never() -> Self {
+        Self::new(1)
+    }
+
+    /// Sets the maximum backoff before the first retry.
+    pub fn initial_backoff(self, initial_backoff: Duration) -> Self {
+        Self {
+            initial_backoff,
+            ..self
+        }
+    }
+
+    /// Sets the maximum backoff between two attempts.
+    pub fn max_backoff(self, max_backoff: Duration) -> Self {
+        Self {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Sets whether the operations which are not idempotent, i.e. `create`,
+    /// `create_many`, `delete`, `delete_many`, `update_if` and `delete_if`, are
+    /// also retried.
+    pub fn retry_non_idempotent(self, non_idempotent: bool) -> Self {
+        Self {
+            non_idempotent,
+            ..self
+        }
+    }
+
+    /// Returns the maximum number of attempts.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the maximum backoff after the `attempt`-th attempt, starting from 1,
+    /// the actual backoff being uniformly drawn between zero and that duration.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt - 1);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Runs `operation` of the database `system` until it succeeds, fails with a
+    /// permanent error, or the maximum number of attempts is reached.
+    pub(crate) async fn run<T, F>(
+        &self,
+        system: &str,
+        operation: &str,
+        attempt: impl Fn() -> F,
+    ) -> sqlx::Result<T>
+    where
+        F: Future<Output = sqlx::Result<T>>,
+    {
+        let retry = self.non_idempotent || is_idempotent(operation);
+        let mut attempts = 1;
+        loop {
+            match attempt().await {
+                Err(err) if retry && attempts < self.max_attempts && is_transient(system, &err) => {
+                    let backoff = self.backoff(attempts).mul_f64(fastrand::f64());
+                    tokio::time::sleep(backoff).await;
+                    attempts += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::never()
+    }
+}
+
+/// Returns `true` if running the operation several times has the same outcome
+/// as running it once.
+fn is_idempotent(operation: &str) -> bool {
+    !matches!(
+        operation,
+        "create" | "create_many" | "delete" | "delete_many" | "update_if" | "delete_if"
+    )
+}
+
+/// Returns `true` if the error returned by the database `system` (e.g. `postgresql`)
+/// is worth retrying.
+fn is_transient(system: &str, err: &sqlx::Error) -> bool {
+    let err = match err {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut => return true,
+        sqlx::Error::Database(err) => err,
+        _ => return false,
+    };
+    match system {
+        "postgresql" => matches!(err.code().as_deref(), Some("40001" | "40P01" | "55P03")),
+        #[cfg(feature = "mysql")]
+        "mysql" => err
+            .try_downcast_ref::<sqlx::mysql::MySqlDatabaseError>()
+            .is_some_and(|err| matches!(err.number(), 1205 | 1213)),
+        "sqlite" => err
+            .code()
+            .and_then(|code| code.parse::<i32>().ok())
+            .is_some_and(|code| matches!(code & 0xff, 5 | 6)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_idempotent, is_transient, RetryPolicy};
+    use std::{
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::new(10).max_backoff(Duration::from_millis(50));
+        assert_eq!(policy.backoff(1), Duration::from_millis(10));
+        assert_eq!(policy.backoff(2), Duration::from_millis(20));
+        assert_eq!(policy.backoff(3), Duration::from_millis(40));
+        assert_eq!(policy.backoff(4), Duration::from_millis(50));
+        assert_eq!(policy.backoff(64), Duration::from_millis(50));
+        assert_eq!(RetryPolicy::new(0).max_attempts(), 1);
+    }
+
+    #[test]
+    fn classification() {
+        assert!(is_idempotent("update"));
+        assert!(is_idempotent("delete_all"));
+        assert!(!is_idempotent("create_many"));
+        assert!(!is_idempotent("delete"));
+        assert!(!is_idempotent("update_if"));
+        assert!(is_transient("sqlite", &sqlx::Error::PoolTimedOut));
+        assert!(!is_transient("sqlite", &sqlx::Error::RowNotFound));
+    }
+
+    async fn attempts(policy: RetryPolicy, operation: &str, err: fn() -> sqlx::Error) -> u32 {
+        let attempts = AtomicU32::new(0);
+        let attempt = || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(err())
+        };
+        policy.run("sqlite", operation, attempt).await.unwrap_err();
+        attempts.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn run() {
+        let policy = RetryPolicy::new(3).initial_backoff(Duration::from_millis(1));
+        let timeout = || sqlx::Error::PoolTimedOut;
+        assert_eq!(attempts(policy, "read", timeout).await, 3);
+        assert_eq!(
+            attempts(policy, "read", || sqlx::Error::RowNotFound).await,
+            1
+        );
+        assert_eq!(attempts(policy, "create", timeout).await, 1);
+        assert_eq!(attempts(policy, "delete", timeout).await, 1);
+        let policy = policy.retry_non_idempotent(true);
+        assert_eq!(attempts(policy, "create", timeout).await, 3);
+        assert_eq!(attempts(RetryPolicy::never(), "read", timeout).await, 1);
+    }
+}
@@ -1,9 +1,12 @@
 use crate::{
-    prelude::{BindColumn, Create, Delete, Read, Schema, Table, Update},
-    traits::sqlx::{RowsAffected, SupportsReturning},
+    instrument::Call,
+    prelude::{Batch, BindColumn, Create, Delete, Read, Schema, Table, Update},
+    replicas::{ReplicaRouting, Replicas},
+    traits::sqlx::{Dialect, RowsAffected, SupportsReturning},
     WithId,
 };
 use async_trait::async_trait;
+use futures_core::stream::BoxStream;
 use sqlx::{
     database::HasArguments, ColumnIndex, Database, Decode, Encode, Executor, FromRow,
     IntoArguments, Pool, Type,
@@ -20,8 +23,67 @@ use std::marker::PhantomData;
 ///
 /// Note that both can be derived automatically; [FromRow] using sqlx
 /// and [Schema] using this crate.
+///
+/// # Tracing
+///
+/// With the `tracing` feature flag, each operation runs in a `miniorm` span at
+/// the `INFO` level, with the following fields, following the OpenTelemetry
+/// semantic conventions for database client spans:
+///
+/// | field                    | value                                           |
+/// |--------------------------|-------------------------------------------------|
+/// | `otel.name`              | e.g. `SELECT todo`                              |
+/// | `otel.kind`              | `client`                                        |
+/// | `otel.status_code`       | `ERROR` if the operation failed                 |
+/// | `db.system`              | `postgresql`, `sqlite` or `mysql`               |
+/// | `db.collection.name`     | the name of the table                           |
+/// | `db.operation.name`      | e.g. `SELECT`, `INSERT`, `UPDATE`, `DELETE`     |
+/// | `miniorm.operation`      | e.g. `read`, `list`, `create`, `delete_all`     |
+/// | `miniorm.id`             | the id of the entity, if any                    |
+/// | `miniorm.rows_affected`  | the number of rows returned or affected         |
+/// | `miniorm.duration_ms`    | the duration of the operation                   |
+/// | `error.type`             | the kind of error, or the SQLSTATE code         |
+///
+/// The span of `stream` lasts until the stream ends or is dropped, and records
+/// the number of rows it yielded.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">tracing</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+///
+/// # Metrics
+///
+/// With the `metrics` feature flag, each operation records the following metrics
+/// using the [`metrics`](https://docs.rs/metrics) facade, labelled with the
+/// `table` and the `operation` (e.g. `read`, `list`, `create`, `delete_all`):
+///
+/// | metric                               | type      | description                               |
+/// |--------------------------------------|-----------|-------------------------------------------|
+/// | `miniorm_operations_total`           | counter   | the number of operations                  |
+/// | `miniorm_operation_errors_total`     | counter   | the number of failed operations, also labelled with the `error` type (see `error.type` above) |
+/// | `miniorm_operation_duration_seconds` | histogram | the duration of the operations            |
+///
+/// With the `prometheus` feature flag, `miniorm::Metrics` installs a
+/// recorder and serves these metrics in the Prometheus text format.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">metrics</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
 pub struct Store<DB: Database, E> {
-    db: Pool<DB>,
+    pub(crate) db: Pool<DB>,
+    replicas: Replicas<DB>,
+    #[cfg(feature = "retry")]
+    retry: crate::RetryPolicy,
     entity: PhantomData<E>,
 }
 
@@ -29,7 +91,87 @@ impl<DB: Database, E> Store<DB, E> {
     /// Create a new [`Store`]
     pub fn new(db: Pool<DB>) -> Self {
         let entity = PhantomData;
-        Self { db, entity }
+        Self {
+            db,
+            replicas: Replicas::default(),
+            #[cfg(feature = "retry")]
+            retry: crate::RetryPolicy::never(),
+            entity,
+        }
+    }
+
+    /// Connects to the database at `url` and returns a store on it, using
+    /// the default pool options (see [`StoreBuilder`](crate::StoreBuilder)
+    /// to configure the pool, or to share it with the stores of other entities).
+    pub async fn connect(url: &str) -> sqlx::Result<Self> {
+        Ok(Self::new(Pool::connect(url).await?))
+    }
+
+    /// Routes the reads of the store (i.e. `read`, `list`, `stream` and `count`)
+    /// to the provided replicas of the database, chosen according to `routing`,
+    /// while all the other operations still go to the primary pool provided to
+    /// [`Store::new`].
+    ///
+    /// Since the replicas may lag behind the primary, a store does not always
+    /// read its own writes. Use [`Store::session`] when it should.
+    pub fn with_replicas(
+        self,
+        replicas: impl IntoIterator<Item = Pool<DB>>,
+        routing: ReplicaRouting,
+    ) -> Self {
+        let replicas = Replicas::new(replicas, routing);
+        Self { replicas, ..self }
+    }
+
+    /// Returns a new session on the store, i.e. a copy of the store which
+    /// reads from the replicas (see [`Store::with_replicas`]) until it writes
+    /// to the primary, and then only reads from the primary, so that it always
+    /// reads its own writes. The clones of a session share that state.
+    pub fn session(&self) -> Self {
+        Self {
+            db: self.db.clone(),
+            replicas: self.replicas.session(),
+            #[cfg(feature = "retry")]
+            retry: self.retry,
+            entity: PhantomData,
+        }
+    }
+
+    /// Returns the pool serving the next read.
+    pub(crate) fn reader(&self) -> &Pool<DB> {
+        self.replicas.reader().unwrap_or(&self.db)
+    }
+
+    /// Returns the pool serving the next write.
+    pub(crate) fn writer(&self) -> &Pool<DB> {
+        self.replicas.write();
+        &self.db
+    }
+
+    /// Retries the operations of the store failing with a transient error
+    /// according to the provided [`RetryPolicy`](crate::RetryPolicy).
+    ///
+    /// <table>
+    ///     <tr>
+    ///         <td style="background-color:green;color:black;">
+    ///         Requires the <span style="color:blue">retry</span>
+    ///         feature flag.
+    ///         </td>
+    ///     </tr>
+    /// </table>
+    #[cfg(feature = "retry")]
+    pub fn with_retry(self, retry: crate::RetryPolicy) -> Self {
+        Self { retry, ..self }
+    }
+}
+
+impl<DB: Database, E: Schema<DB>> Store<DB, E> {
+    /// Returns the call running `operation` on the table of the store.
+    pub(crate) fn call(&self, operation: &'static str) -> Call {
+        let call = Call::new::<DB>(E::MINIORM_TABLE_NAME, operation);
+        #[cfg(feature = "retry")]
+        let call = call.retry(self.retry);
+        call
     }
 }
 
@@ -39,15 +181,22 @@ impl<DB: Database, E> Store<DB, E> {
 #[async_trait]
 impl<DB: Database, E: Sync + Schema<DB>> Table<DB> for Store<DB, E>
 where
+    <DB as Database>::QueryResult: RowsAffected,
     for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
     for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
 {
     async fn create_table(&self) -> sqlx::Result<<DB as Database>::QueryResult> {
-        sqlx::query(E::MINIORM_CREATE_TABLE).execute(&self.db).await
+        let query = || sqlx::query(E::MINIORM_CREATE_TABLE).execute(self.writer());
+        self.call("create_table")
+            .run(query, |res| res.rows_affected())
+            .await
     }
 
     async fn drop_table(&self) -> sqlx::Result<<DB as Database>::QueryResult> {
-        sqlx::query(E::MINIORM_DROP_TABLE).execute(&self.db).await
+        let query = || sqlx::query(E::MINIORM_DROP_TABLE).execute(self.writer());
+        self.call("drop_table")
+            .run(query, |res| res.rows_affected())
+            .await
     }
 }
 
@@ -65,13 +214,21 @@ where
     usize: ColumnIndex<<DB as sqlx::Database>::Row>,
 {
     async fn create(&self, entity: E) -> sqlx::Result<WithId<E>> {
-        let (id,) = E::MINIORM_COLUMNS
-            .iter()
-            .fold(sqlx::query_as(E::MINIORM_CREATE), |query, col| {
-                entity.bind_column(query, col)
-            })
-            .fetch_one(&self.db)
+        // the query is run to completion: on SQLite, `fetch_one` would leave the
+        // statement pending, and the row uncommitted, until the connection is reused
+        let create = || {
+            E::MINIORM_COLUMNS
+                .iter()
+                .fold(sqlx::query_as(E::MINIORM_CREATE), |query, col| {
+                    entity.bind_column(query, col)
+                })
+                .fetch_all(self.writer())
+        };
+        let rows: Vec<(i64,)> = self
+            .call("create")
+            .run(create, |rows| rows.len() as u64)
             .await?;
+        let (id,) = rows.into_iter().next().ok_or(sqlx::Error::RowNotFound)?;
         Ok(WithId::new(entity, id))
     }
 }
@@ -92,13 +249,15 @@ mod mysql {
         E: for<'r> FromRow<'r, MySqlRow> + Schema<MySql> + BindColumn<MySql> + Sync + Send,
     {
         async fn create(&self, entity: E) -> sqlx::Result<WithId<E>> {
-            let res = E::MINIORM_COLUMNS
-                .iter()
-                .fold(sqlx::query(E::MINIORM_CREATE), |query, col| {
-                    entity.bind_column(query, col)
-                })
-                .execute(&self.db)
-                .await?;
+            let create = || {
+                E::MINIORM_COLUMNS
+                    .iter()
+                    .fold(sqlx::query(E::MINIORM_CREATE), |query, col| {
+                        entity.bind_column(query, col)
+                    })
+                    .execute(self.writer())
+            };
+            let res = self.call("create").run(create, |_| 1).await?;
             let id = res.last_insert_id() as i64;
             Ok(WithId::new(entity, id))
         }
@@ -112,7 +271,7 @@ mod mysql {
 #[async_trait]
 impl<DB, E> Read<E> for Store<DB, E>
 where
-    DB: Database,
+    DB: Database + Dialect,
     E: Unpin + Send + Sync + Send,
     E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB>,
     for<'c> &'c str: ColumnIndex<<DB as Database>::Row>,
@@ -121,25 +280,104 @@ where
     for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
 {
     async fn read(&self, id: i64) -> sqlx::Result<WithId<E>> {
-        sqlx::query_as(E::MINIORM_READ)
-            .bind(id)
-            .fetch_one(&self.db)
-            .await
+        let query = || {
+            sqlx::query_as(E::MINIORM_READ)
+                .bind(id)
+                .fetch_one(self.reader())
+        };
+        self.call("read").id(id).run(query, |_| 1).await
     }
 
     async fn list(&self) -> sqlx::Result<Vec<WithId<E>>> {
-        sqlx::query_as(E::MINIORM_LIST).fetch_all(&self.db).await
+        let query = || sqlx::query_as(E::MINIORM_LIST).fetch_all(self.reader());
+        self.call("list").run(query, |all| all.len() as u64).await
     }
 
-    async fn count(&self) -> sqlx::Result<u64> {
-        #[derive(FromRow)]
-        struct CountResult {
-            count: i64,
-        }
+    fn stream<'s>(&'s self) -> BoxStream<'s, sqlx::Result<WithId<E>>>
+    where
+        E: 's,
+    {
+        let stream = sqlx::query_as(E::MINIORM_LIST).fetch(self.reader());
+        self.call("stream").stream(stream)
+    }
 
-        let result: CountResult = sqlx::query_as(E::MINIORM_COUNT).fetch_one(&self.db).await?;
+    async fn count(&self) -> sqlx::Result<u64> {
+        let query = || sqlx::query_as(E::MINIORM_COUNT).fetch_one(self.reader());
+        let result: CountResult = self.call("count").run(query, |_| 1).await?;
         Ok(result.count as u64)
     }
+
+    #[cfg(feature = "axum")]
+    async fn query(&self, query: &crate::ListQuery) -> sqlx::Result<crate::Page<E>>
+    where
+        Self: Sync,
+        E: serde::Serialize + Send + 'async_trait,
+    {
+        self.run_query(query, None).await
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<DB, E> Store<DB, E>
+where
+    DB: Database + Dialect,
+    E: Unpin + Send + Sync,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB>,
+    for<'c> &'c str: ColumnIndex<<DB as Database>::Row>,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+{
+    /// Runs `query` in the database (see [`Read::query`]), only among the rows whose
+    /// `scope` column, if any, is equal to the provided value, e.g. the tenant.
+    pub(crate) async fn run_query(
+        &self,
+        query: &crate::ListQuery,
+        scope: Option<(&str, i64)>,
+    ) -> sqlx::Result<crate::Page<E>> {
+        let schema = crate::json_schema::entity_schema(E::MINIORM_JSON_SCHEMA);
+        let scope = scope.map(|(column, value)| (column, serde_json::Value::from(value)));
+        let scope = scope.as_ref().map(|(column, value)| (*column, value));
+        let sql = query.to_sql::<DB>(E::MINIORM_TABLE_NAME, E::MINIORM_COLUMNS, &schema, scope)?;
+        let limit = query
+            .limit
+            .map_or(i64::MAX, |limit| i64::try_from(limit).unwrap_or(i64::MAX));
+        let offset = i64::try_from(query.offset).unwrap_or(i64::MAX);
+        let page = || async {
+            // both queries are run on the same pool, so that the total
+            // matches the page when reading from the replicas
+            let reader = self.reader();
+            let total: CountResult = sql
+                .values
+                .iter()
+                .fold(sqlx::query_as(&sql.count), |query, value| {
+                    DB::bind_json(query, value)
+                })
+                .fetch_one(reader)
+                .await?;
+            let items = sql
+                .values
+                .iter()
+                .fold(sqlx::query_as(&sql.select), |query, value| {
+                    DB::bind_json(query, value)
+                })
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(reader)
+                .await?;
+            let total = total.count as u64;
+            Ok(crate::Page { total, items })
+        };
+        self.call("query")
+            .run(page, |page| page.items.len() as u64)
+            .await
+    }
+}
+
+/// Result of a `SELECT COUNT(id) AS count`
+#[derive(FromRow)]
+struct CountResult {
+    count: i64,
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
@@ -148,24 +386,128 @@ where
 #[async_trait]
 impl<DB, E> Update<E> for Store<DB, E>
 where
-    DB: Database,
+    DB: Database + Dialect,
+    <DB as Database>::QueryResult: RowsAffected,
     for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
     for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
-    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB> + BindColumn<DB> + Sync + Send,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB> + BindColumn<DB>,
+    E: Unpin + Sync + Send,
     for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
     usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+    for<'c> &'c str: ColumnIndex<<DB as Database>::Row>,
 {
     async fn update(&self, entity: WithId<E>) -> sqlx::Result<WithId<E>> {
-        E::MINIORM_COLUMNS
-            .iter()
-            .fold(sqlx::query(E::MINIORM_UPDATE), |query, col| {
-                entity.bind_column(query, col)
-            })
-            .bind(entity.id())
-            .execute(&self.db)
+        let query = || {
+            E::MINIORM_COLUMNS
+                .iter()
+                .fold(sqlx::query(E::MINIORM_UPDATE), |query, col| {
+                    entity.bind_column(query, col)
+                })
+                .bind(entity.id())
+                .execute(self.writer())
+        };
+        self.call("update")
+            .id(entity.id())
+            .run(query, |res| res.rows_affected())
+            .await?;
+        Ok(entity)
+    }
+
+    async fn update_columns(
+        &self,
+        entity: WithId<E>,
+        columns: &[&'static str],
+    ) -> sqlx::Result<WithId<E>> {
+        let Some((columns, query)) = update_columns_query::<DB, E>(columns) else {
+            return Ok(entity);
+        };
+        let query = || {
+            columns
+                .iter()
+                .fold(sqlx::query(&query), |query, col| {
+                    entity.bind_column(query, col)
+                })
+                .bind(entity.id())
+                .execute(self.writer())
+        };
+        self.call("update_columns")
+            .id(entity.id())
+            .run(query, |res| res.rows_affected())
             .await?;
         Ok(entity)
     }
+
+    async fn update_if<F>(
+        &self,
+        entity: WithId<E>,
+        columns: &[&'static str],
+        check: F,
+    ) -> sqlx::Result<Option<WithId<E>>>
+    where
+        Self: Read<E> + Sync,
+        E: Send + 'async_trait,
+        F: Fn(&WithId<E>) -> bool + Send + Sync + 'async_trait,
+    {
+        let update = update_columns_query::<DB, E>(columns);
+        let read = format!("{}{}", E::MINIORM_READ, DB::FOR_UPDATE);
+        let query = || async {
+            let mut tx = self.writer().begin().await?;
+            let current: WithId<E> = sqlx::query_as(&read)
+                .bind(entity.id())
+                .fetch_one(&mut *tx)
+                .await?;
+            if !check(&current) {
+                return Ok(false);
+            }
+            if let Some((columns, query)) = &update {
+                columns
+                    .iter()
+                    .fold(sqlx::query(query), |query, col| {
+                        entity.bind_column(query, col)
+                    })
+                    .bind(entity.id())
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+            Ok(true)
+        };
+        let updated = self
+            .call("update_if")
+            .id(entity.id())
+            .run(query, |updated| *updated as u64)
+            .await?;
+        Ok(updated.then_some(entity))
+    }
+}
+
+/// Returns the columns of `E` among `columns`, in the order of the schema, and the
+/// query updating them, or `None` if there is nothing to update.
+fn update_columns_query<DB, E>(columns: &[&'static str]) -> Option<(Vec<&'static str>, String)>
+where
+    DB: Database + Dialect,
+    E: Schema<DB>,
+{
+    let columns: Vec<_> = E::MINIORM_COLUMNS
+        .iter()
+        .copied()
+        .filter(|col| columns.contains(col))
+        .collect();
+    if columns.is_empty() {
+        return None;
+    }
+    let values = columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| format!("{col}={}", DB::placeholder(i + 1)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let id = DB::placeholder(columns.len() + 1);
+    let query = format!(
+        "UPDATE {} SET {values} WHERE id={id}",
+        E::MINIORM_TABLE_NAME
+    );
+    Some((columns, query))
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
@@ -174,29 +516,238 @@ where
 
 #[async_trait]
 impl<DB, E> Delete<E> for Store<DB, E>
+where
+    DB: Database + Dialect,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB> + Unpin + Send + Sync,
+    <DB as Database>::QueryResult: RowsAffected,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+    for<'c> &'c str: ColumnIndex<<DB as Database>::Row>,
+{
+    async fn delete(&self, id: i64) -> sqlx::Result<()> {
+        let delete = || async {
+            let res = sqlx::query(E::MINIORM_DELETE)
+                .bind(id)
+                .execute(self.writer())
+                .await?;
+            if res.rows_affected() == 0 {
+                Err(sqlx::Error::RowNotFound)
+            } else {
+                Ok(())
+            }
+        };
+        self.call("delete").id(id).run(delete, |_| 1).await
+    }
+
+    async fn delete_if<F>(&self, id: i64, check: F) -> sqlx::Result<bool>
+    where
+        Self: Read<E> + Sync,
+        E: Send + 'async_trait,
+        F: Fn(&WithId<E>) -> bool + Send + Sync + 'async_trait,
+    {
+        let read = format!("{}{}", E::MINIORM_READ, DB::FOR_UPDATE);
+        let delete = || async {
+            let mut tx = self.writer().begin().await?;
+            let current: WithId<E> = sqlx::query_as(&read).bind(id).fetch_one(&mut *tx).await?;
+            if !check(&current) {
+                return Ok(false);
+            }
+            sqlx::query(E::MINIORM_DELETE)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(true)
+        };
+        self.call("delete_if")
+            .id(id)
+            .run(delete, |deleted| *deleted as u64)
+            .await
+    }
+
+    async fn delete_all(&self) -> sqlx::Result<u64> {
+        let delete_all = || async {
+            let res = sqlx::query(E::MINIORM_DELETE_ALL)
+                .execute(self.writer())
+                .await?;
+            Ok(res.rows_affected() as u64)
+        };
+        self.call("delete_all")
+            .run(delete_all, |count| *count)
+            .await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Batch
+///////////////////////////////////////////////////////////////////////////////////////////////////
+async fn update_many_in_transaction<DB, E>(
+    store: &Store<DB, E>,
+    entities: Vec<WithId<E>>,
+) -> sqlx::Result<Vec<WithId<E>>>
 where
     DB: Database,
-    E: Schema<DB> + Sync,
+    E: Schema<DB> + BindColumn<DB>,
     <DB as Database>::QueryResult: RowsAffected,
     for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
     for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
     for<'c> i64: Type<DB> + Encode<'c, DB>,
 {
-    async fn delete(&self, id: i64) -> sqlx::Result<()> {
-        let res = sqlx::query(E::MINIORM_DELETE)
-            .bind(id)
-            .execute(&self.db)
-            .await?;
-        if res.rows_affected() == 0 {
-            Err(sqlx::Error::RowNotFound)
-        } else {
-            Ok(())
+    let update_many = || async {
+        let mut tx = store.writer().begin().await?;
+        let mut count = 0;
+        for entity in &entities {
+            let res = E::MINIORM_COLUMNS
+                .iter()
+                .fold(sqlx::query(E::MINIORM_UPDATE), |query, col| {
+                    entity.bind_column(query, col)
+                })
+                .bind(entity.id())
+                .execute(&mut *tx)
+                .await?;
+            count += res.rows_affected();
         }
+        tx.commit().await?;
+        Ok(count)
+    };
+    store
+        .call("update_many")
+        .run(update_many, |count| *count)
+        .await?;
+    Ok(entities)
+}
+
+async fn delete_many_in_transaction<DB, E>(store: &Store<DB, E>, ids: &[i64]) -> sqlx::Result<()>
+where
+    DB: Database,
+    E: Schema<DB>,
+    <DB as Database>::QueryResult: RowsAffected,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Encode<'c, DB>,
+{
+    let delete_many = || async {
+        let mut tx = store.writer().begin().await?;
+        let mut count = 0;
+        for id in ids {
+            let res = sqlx::query(E::MINIORM_DELETE)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            if res.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+            count += res.rows_affected();
+        }
+        tx.commit().await?;
+        Ok(count)
+    };
+    store
+        .call("delete_many")
+        .run(delete_many, |count| *count)
+        .await?;
+    Ok(())
+}
+
+#[async_trait]
+impl<DB, E> Batch<E> for Store<DB, E>
+where
+    DB: Database + SupportsReturning,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB> + BindColumn<DB> + Sync + Send,
+    <DB as Database>::QueryResult: RowsAffected,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    async fn create_many(&self, entities: Vec<E>) -> sqlx::Result<Vec<WithId<E>>> {
+        let create_many = || async {
+            let mut tx = self.writer().begin().await?;
+            let mut ids = Vec::with_capacity(entities.len());
+            for entity in &entities {
+                let (id,) = E::MINIORM_COLUMNS
+                    .iter()
+                    .fold(sqlx::query_as(E::MINIORM_CREATE), |query, col| {
+                        entity.bind_column(query, col)
+                    })
+                    .fetch_one(&mut *tx)
+                    .await?;
+                ids.push(id);
+            }
+            tx.commit().await?;
+            Ok(ids)
+        };
+        let ids = self
+            .call("create_many")
+            .run(create_many, |ids| ids.len() as u64)
+            .await?;
+        Ok(entities
+            .into_iter()
+            .zip(ids)
+            .map(|(entity, id)| WithId::new(entity, id))
+            .collect())
     }
 
-    async fn delete_all(&self) -> sqlx::Result<u64> {
-        let res = sqlx::query(E::MINIORM_DELETE_ALL).execute(&self.db).await?;
-        Ok(res.rows_affected() as u64)
+    async fn update_many(&self, entities: Vec<WithId<E>>) -> sqlx::Result<Vec<WithId<E>>> {
+        update_many_in_transaction(self, entities).await
+    }
+
+    async fn delete_many(&self, ids: &[i64]) -> sqlx::Result<()> {
+        delete_many_in_transaction(self, ids).await
+    }
+}
+
+#[cfg(feature = "mysql")]
+mod mysql_batch {
+    use async_trait::async_trait;
+    use sqlx::{mysql::MySqlRow, FromRow, MySql};
+
+    use crate::{
+        prelude::{Batch, BindColumn, Schema},
+        Store, WithId,
+    };
+
+    #[async_trait]
+    impl<E> Batch<E> for Store<MySql, E>
+    where
+        E: for<'r> FromRow<'r, MySqlRow> + Schema<MySql> + BindColumn<MySql> + Sync + Send,
+    {
+        async fn create_many(&self, entities: Vec<E>) -> sqlx::Result<Vec<WithId<E>>> {
+            let create_many = || async {
+                let mut tx = self.writer().begin().await?;
+                let mut ids = Vec::with_capacity(entities.len());
+                for entity in &entities {
+                    let res = E::MINIORM_COLUMNS
+                        .iter()
+                        .fold(sqlx::query(E::MINIORM_CREATE), |query, col| {
+                            entity.bind_column(query, col)
+                        })
+                        .execute(&mut *tx)
+                        .await?;
+                    ids.push(res.last_insert_id() as i64);
+                }
+                tx.commit().await?;
+                Ok(ids)
+            };
+            let ids = self
+                .call("create_many")
+                .run(create_many, |ids| ids.len() as u64)
+                .await?;
+            Ok(entities
+                .into_iter()
+                .zip(ids)
+                .map(|(entity, id)| WithId::new(entity, id))
+                .collect())
+        }
+
+        async fn update_many(&self, entities: Vec<WithId<E>>) -> sqlx::Result<Vec<WithId<E>>> {
+            super::update_many_in_transaction(self, entities).await
+        }
+
+        async fn delete_many(&self, ids: &[i64]) -> sqlx::Result<()> {
+            super::delete_many_in_transaction(self, ids).await
+        }
     }
 }
 
@@ -208,10 +759,104 @@ where
         + crate::traits::bind_col::BindColumn<DB>,
     E: serde::Serialize + for<'de> serde::Deserialize<'de>,
     E: Clone + Sync + Send + Unpin + 'static,
-    Store<DB, E>: crate::traits::crud::Crud<E> + Clone,
+    Store<DB, E>: crate::traits::crud::Crud<E> + Batch<E> + Clone,
 {
     fn into_axum_router<S>(self) -> axum::Router<S> {
-        crate::handler::Handler::new(self).into_axum_router()
+        self.router_builder().build()
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<DB: Database, E> Store<DB, E>
+where
+    E: Schema<DB>
+        + for<'r> FromRow<'r, <DB as Database>::Row>
+        + crate::traits::bind_col::BindColumn<DB>,
+    E: serde::Serialize + for<'de> serde::Deserialize<'de>,
+    E: Clone + Sync + Send + Unpin + 'static,
+    Store<DB, E>: crate::traits::crud::Crud<E> + Batch<E> + Clone,
+{
+    /// Returns a [`RouterBuilder`](crate::RouterBuilder) to configure the
+    /// [`Router`](axum::Router) serving the CRUD operations of the store,
+    /// e.g. to only serve some of the operations.
+    ///
+    /// <table>
+    ///     <tr>
+    ///         <td style="background-color:green;color:black;">
+    ///         Requires the <span style="color:blue">axum</span>
+    ///         feature flag.
+    ///         </td>
+    ///     </tr>
+    /// </table>
+    pub fn router_builder(self) -> crate::RouterBuilder<E, Self> {
+        let handler =
+            crate::handler::Handler::new(self, E::MINIORM_COLUMNS, E::MINIORM_JSON_SCHEMA);
+        crate::RouterBuilder::new(handler)
+    }
+
+    /// Same as [`IntoAxumRouter::into_axum_router`](crate::prelude::IntoAxumRouter::into_axum_router), but uses `problems` to map
+    /// the errors of the store to the returned [`Problem`](crate::Problem), e.g. to customize
+    /// or redact them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use axum::{http::StatusCode, Router};
+    /// use miniorm::{prelude::*, Problem};
+    /// use serde::{Deserialize, Serialize};
+    /// use sqlx::FromRow;
+    ///
+    /// #[derive(Debug, Clone, FromRow, Entity, Serialize, Deserialize)]
+    /// struct Todo {
+    ///     #[postgres(TEXT NOT NULL UNIQUE)]
+    ///     description: String,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let db = sqlx::PgPool::connect("postgres://localhost/miniorm").await?;
+    /// let todos = Store::<_, Todo>::new(db);
+    /// let app: Router = todos.into_axum_router_with_problems(|err| {
+    ///     let problem = Problem::from_sqlx_error(err);
+    ///     if problem.status_code() == StatusCode::CONFLICT {
+    ///         // do not leak the database message
+    ///         Problem::new(StatusCode::CONFLICT)
+    ///             .with_type("https://example.com/problems/duplicate-todo")
+    ///             .with_title("This todo already exists")
+    ///     } else {
+    ///         problem
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// <table>
+    ///     <tr>
+    ///         <td style="background-color:green;color:black;">
+    ///         Requires the <span style="color:blue">axum</span>
+    ///         feature flag.
+    ///         </td>
+    ///     </tr>
+    /// </table>
+    pub fn into_axum_router_with_problems<S>(
+        self,
+        problems: impl Fn(&sqlx::Error) -> crate::Problem + Send + Sync + 'static,
+    ) -> axum::Router<S> {
+        self.router_builder().problems(problems).build()
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<DB: Database, E: Schema<DB>> crate::traits::axum::IntoOpenApi for Store<DB, E> {
+    fn openapi(&self, path: &str) -> crate::OpenApi {
+        let name = E::MINIORM_ENTITY_NAME;
+        let (schema, columns) = (E::MINIORM_JSON_SCHEMA, E::MINIORM_COLUMNS);
+        crate::OpenApi::for_entity(name, path, schema, columns).restrict(|method, path| {
+            crate::Operation::DEFAULT
+                .iter()
+                .any(|op| op.method() == method && op.path() == path)
+        })
     }
 }
 
@@ -219,6 +864,9 @@ impl<DB: Database, E> Clone for Store<DB, E> {
     fn clone(&self) -> Self {
         Self {
             db: self.db.clone(),
+            replicas: self.replicas.clone(),
+            #[cfg(feature = "retry")]
+            retry: self.retry,
             entity: self.entity,
         }
     }
@@ -0,0 +1,415 @@
+use crate::{
+    prelude::{BindColumn, Create, Delete, Read, Schema, Update},
+    traits::sqlx::{Dialect, RowsAffected, SupportsReturning},
+    Store, WithId,
+};
+use async_trait::async_trait;
+use futures_core::stream::BoxStream;
+use sqlx::{
+    database::HasArguments, ColumnIndex, Database, Decode, Encode, Executor, FromRow,
+    IntoArguments, Type,
+};
+use std::sync::Arc;
+
+/// name of the column holding the tenant of each row
+const TENANT_COLUMN: &str = "tenant_id";
+
+/// A `TenantStore` is a view on a [`Store`] restricted to the entities of
+/// a single tenant, obtained using [`Store::for_tenant`].
+///
+/// The table of the store is expected to have an additional `tenant_id`
+/// column which is:
+/// - added to the table by [`TenantStore::create_table`],
+/// - set to the id of the tenant by [`Create::create`],
+/// - checked by every other operation, so that the entities of other
+///   tenants can neither be read, listed, counted, updated, nor deleted.
+///
+/// Note that the `tenant_id` column is not part of the entity itself.
+///
+/// The operations are run like those of the wrapped [`Store`]: they are
+/// traced, measured, retried and routed to its replicas the same way.
+///
+/// Since the table is shared by all the tenants, a `TenantStore` does not
+/// implement [`Table`](crate::prelude::Table): the table can only be dropped using the unscoped [`Store`].
+///
+/// # Example
+///
+/// ```
+/// use miniorm::prelude::*;
+/// use sqlx::FromRow;
+///
+/// #[derive(Debug, Clone, Eq, PartialEq, FromRow, Entity)]
+/// struct Todo {
+///     #[sqlite(TEXT NOT NULL)]
+///     description: String,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = sqlx::SqlitePool::connect(":memory:").await?;
+/// let store = Store::<_, Todo>::new(db);
+/// let acme = store.for_tenant(1);
+/// let initech = store.for_tenant(2);
+/// acme.create_table().await?;
+///
+/// let todo = acme.create(Todo { description: "checkout miniorm".into() }).await?;
+/// assert_eq!(acme.read(todo.id()).await?, todo);
+/// assert!(matches!(initech.read(todo.id()).await, Err(sqlx::Error::RowNotFound)));
+/// assert_eq!(initech.count().await?, 0);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TenantStore<DB: Database, E> {
+    store: Store<DB, E>,
+    tenant_id: i64,
+    queries: Arc<TenantQueries>,
+}
+
+struct TenantQueries {
+    create_table: String,
+    create: String,
+    read: String,
+    list: String,
+    count: String,
+    update: String,
+    delete: String,
+    delete_all: String,
+}
+
+impl TenantQueries {
+    fn new<DB: Database + Dialect, E: Schema<DB>>() -> Self {
+        let table = E::MINIORM_TABLE_NAME;
+        let tenant = TENANT_COLUMN;
+        let cols = E::MINIORM_COLUMNS.join(", ");
+        let n = E::MINIORM_COLUMNS.len();
+        let p = DB::placeholder;
+
+        // the tenant column is inserted right before the closing
+        // parenthesis of the generated `CREATE TABLE` statement
+        let create_table = {
+            let declaration = E::MINIORM_CREATE_TABLE.trim_end();
+            let declaration = declaration.strip_suffix(')').unwrap_or(declaration);
+            format!("{declaration}, {tenant} BIGINT NOT NULL)")
+        };
+        let create = {
+            let placeholders = (1..=n + 1).map(p).collect::<Vec<_>>().join(", ");
+            format!("INSERT INTO {table} ({cols}, {tenant}) VALUES ({placeholders})")
+        };
+        let read = format!(
+            "SELECT {cols}, id FROM {table} WHERE id={} AND {tenant}={}",
+            p(1),
+            p(2)
+        );
+        let list = format!(
+            "SELECT {cols}, id FROM {table} WHERE {tenant}={} ORDER BY id",
+            p(1)
+        );
+        let count = format!(
+            "SELECT COUNT(id) AS count FROM {table} WHERE {tenant}={}",
+            p(1)
+        );
+        let update = {
+            let values = E::MINIORM_COLUMNS
+                .iter()
+                .enumerate()
+                .map(|(i, col)| format!("{col}={}", p(i + 1)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "UPDATE {table} SET {values} WHERE id={} AND {tenant}={}",
+                p(n + 1),
+                p(n + 2)
+            )
+        };
+        let delete = format!(
+            "DELETE FROM {table} WHERE id={} AND {tenant}={}",
+            p(1),
+            p(2)
+        );
+        let delete_all = format!("DELETE FROM {table} WHERE {tenant}={}", p(1));
+
+        Self {
+            create_table,
+            create,
+            read,
+            list,
+            count,
+            update,
+            delete,
+            delete_all,
+        }
+    }
+}
+
+impl<DB: Database + Dialect, E: Schema<DB>> Store<DB, E> {
+    /// Returns a [`TenantStore`] restricted to the entities of the tenant
+    /// with the provided id.
+    pub fn for_tenant(&self, tenant_id: i64) -> TenantStore<DB, E> {
+        let store = self.clone();
+        let queries = Arc::new(TenantQueries::new::<DB, E>());
+        TenantStore {
+            store,
+            tenant_id,
+            queries,
+        }
+    }
+}
+
+impl<DB: Database, E> TenantStore<DB, E> {
+    /// Returns the id of the tenant
+    pub fn tenant_id(&self) -> i64 {
+        self.tenant_id
+    }
+
+    /// Returns a copy of this store restricted to another tenant
+    pub fn for_tenant(&self, tenant_id: i64) -> Self {
+        let store = self.store.clone();
+        let queries = self.queries.clone();
+        Self {
+            store,
+            tenant_id,
+            queries,
+        }
+    }
+}
+
+impl<DB: Database, E> Clone for TenantStore<DB, E> {
+    fn clone(&self) -> Self {
+        self.for_tenant(self.tenant_id)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Table
+///////////////////////////////////////////////////////////////////////////////////////////////////
+impl<DB: Database, E: Schema<DB>> TenantStore<DB, E>
+where
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+{
+    /// Creates the table shared by all the tenants, with its `tenant_id`
+    /// column, if it does not exist yet.
+    pub async fn create_table(&self) -> sqlx::Result<<DB as Database>::QueryResult> {
+        let query = || sqlx::query(&self.queries.create_table).execute(self.store.writer());
+        // creating a table does not affect any row
+        self.store.call("create_table").run(query, |_| 0).await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Create
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<DB, E> Create<E> for TenantStore<DB, E>
+where
+    DB: Database + SupportsReturning,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB> + BindColumn<DB> + Sync + Send,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    async fn create(&self, entity: E) -> sqlx::Result<WithId<E>> {
+        // the query is run to completion: on SQLite, `fetch_one` would leave the
+        // statement pending, and the row uncommitted, until the connection is reused
+        let query = format!("{} RETURNING id", self.queries.create);
+        let create = || {
+            E::MINIORM_COLUMNS
+                .iter()
+                .fold(sqlx::query_as(&query), |query, col| {
+                    entity.bind_column(query, col)
+                })
+                .bind(self.tenant_id)
+                .fetch_all(self.store.writer())
+        };
+        let rows: Vec<(i64,)> = self
+            .store
+            .call("create")
+            .run(create, |rows| rows.len() as u64)
+            .await?;
+        let (id,) = rows.into_iter().next().ok_or(sqlx::Error::RowNotFound)?;
+        Ok(WithId::new(entity, id))
+    }
+}
+
+#[cfg(feature = "mysql")]
+mod mysql {
+    use super::TenantStore;
+    use crate::{
+        prelude::{BindColumn, Create, Schema},
+        WithId,
+    };
+    use async_trait::async_trait;
+    use sqlx::{mysql::MySqlRow, FromRow, MySql};
+
+    #[async_trait]
+    impl<E> Create<E> for TenantStore<MySql, E>
+    where
+        E: for<'r> FromRow<'r, MySqlRow> + Schema<MySql> + BindColumn<MySql> + Sync + Send,
+    {
+        async fn create(&self, entity: E) -> sqlx::Result<WithId<E>> {
+            let create = || {
+                E::MINIORM_COLUMNS
+                    .iter()
+                    .fold(sqlx::query(&self.queries.create), |query, col| {
+                        entity.bind_column(query, col)
+                    })
+                    .bind(self.tenant_id)
+                    .execute(self.store.writer())
+            };
+            let res = self.store.call("create").run(create, |_| 1).await?;
+            let id = res.last_insert_id() as i64;
+            Ok(WithId::new(entity, id))
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Read
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<DB, E> Read<E> for TenantStore<DB, E>
+where
+    DB: Database + Dialect,
+    E: Unpin + Send + Sync,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB>,
+    for<'c> &'c str: ColumnIndex<<DB as Database>::Row>,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+{
+    async fn read(&self, id: i64) -> sqlx::Result<WithId<E>> {
+        let query = || {
+            sqlx::query_as(&self.queries.read)
+                .bind(id)
+                .bind(self.tenant_id)
+                .fetch_one(self.store.reader())
+        };
+        self.store.call("read").id(id).run(query, |_| 1).await
+    }
+
+    async fn list(&self) -> sqlx::Result<Vec<WithId<E>>> {
+        let query = || {
+            sqlx::query_as(&self.queries.list)
+                .bind(self.tenant_id)
+                .fetch_all(self.store.reader())
+        };
+        self.store
+            .call("list")
+            .run(query, |all| all.len() as u64)
+            .await
+    }
+
+    fn stream<'s>(&'s self) -> BoxStream<'s, sqlx::Result<WithId<E>>>
+    where
+        E: 's,
+    {
+        let stream = sqlx::query_as(&self.queries.list)
+            .bind(self.tenant_id)
+            .fetch(self.store.reader());
+        self.store.call("stream").stream(stream)
+    }
+
+    async fn count(&self) -> sqlx::Result<u64> {
+        #[derive(FromRow)]
+        struct CountResult {
+            count: i64,
+        }
+
+        let query = || {
+            sqlx::query_as(&self.queries.count)
+                .bind(self.tenant_id)
+                .fetch_one(self.store.reader())
+        };
+        let result: CountResult = self.store.call("count").run(query, |_| 1).await?;
+        Ok(result.count as u64)
+    }
+
+    #[cfg(feature = "axum")]
+    async fn query(&self, query: &crate::ListQuery) -> sqlx::Result<crate::Page<E>>
+    where
+        Self: Sync,
+        E: serde::Serialize + Send + 'async_trait,
+    {
+        let scope = (TENANT_COLUMN, self.tenant_id);
+        self.store.run_query(query, Some(scope)).await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Update
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<DB, E> Update<E> for TenantStore<DB, E>
+where
+    DB: Database,
+    <DB as Database>::QueryResult: RowsAffected,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    E: for<'r> FromRow<'r, <DB as Database>::Row> + Schema<DB> + BindColumn<DB> + Sync + Send,
+    for<'c> i64: Type<DB> + Decode<'c, DB> + Encode<'c, DB>,
+    usize: ColumnIndex<<DB as sqlx::Database>::Row>,
+{
+    async fn update(&self, entity: WithId<E>) -> sqlx::Result<WithId<E>> {
+        let query = || {
+            E::MINIORM_COLUMNS
+                .iter()
+                .fold(sqlx::query(&self.queries.update), |query, col| {
+                    entity.bind_column(query, col)
+                })
+                .bind(entity.id())
+                .bind(self.tenant_id)
+                .execute(self.store.writer())
+        };
+        self.store
+            .call("update")
+            .id(entity.id())
+            .run(query, |res| res.rows_affected())
+            .await?;
+        Ok(entity)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+/// Delete
+///////////////////////////////////////////////////////////////////////////////////////////////////
+#[async_trait]
+impl<DB, E> Delete<E> for TenantStore<DB, E>
+where
+    DB: Database,
+    E: Schema<DB> + Sync + Send,
+    <DB as Database>::QueryResult: RowsAffected,
+    for<'c> &'c mut <DB as sqlx::Database>::Connection: Executor<'c, Database = DB>,
+    for<'c> <DB as HasArguments<'c>>::Arguments: IntoArguments<'c, DB>,
+    for<'c> i64: Type<DB> + Encode<'c, DB>,
+{
+    async fn delete(&self, id: i64) -> sqlx::Result<()> {
+        let delete = || async {
+            let res = sqlx::query(&self.queries.delete)
+                .bind(id)
+                .bind(self.tenant_id)
+                .execute(self.store.writer())
+                .await?;
+            if res.rows_affected() == 0 {
+                Err(sqlx::Error::RowNotFound)
+            } else {
+                Ok(())
+            }
+        };
+        self.store.call("delete").id(id).run(delete, |_| 1).await
+    }
+
+    async fn delete_all(&self) -> sqlx::Result<u64> {
+        let delete_all = || async {
+            let res = sqlx::query(&self.queries.delete_all)
+                .bind(self.tenant_id)
+                .execute(self.store.writer())
+                .await?;
+            Ok(res.rows_affected())
+        };
+        self.store
+            .call("delete_all")
+            .run(delete_all, |count| *count)
+            .await
+    }
+}
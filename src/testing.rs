@@ -0,0 +1,285 @@
+//! Reusable conformance test suite for [`Crud`] implementations.
+//!
+//! Every check in this module panics (just like a failing `assert!`) if the
+//! provided store does not behave like a [`Store`](crate::Store) would:
+//! - ids are assigned on creation and increase with each new entity,
+//! - entities are listed by increasing id,
+//! - reading, or deleting, a missing id fails with [`sqlx::Error::RowNotFound`],
+//! - `delete_all` returns the number of deleted entities.
+//!
+//! Stores which also implement [`Batch`] can be checked using [`batch`],
+//! and the conditional writes of the stores shared between tasks using
+//! [`conditional`]; neither is part of [`run_all`].
+//!
+//! Each check expects a fresh, empty, store and a `factory` that builds the
+//! `n`-th sample entity. The factory should return different entities for
+//! different values of `n`.
+//!
+//! <table>
+//!     <tr>
+//!         <td style="background-color:green;color:black;">
+//!         Requires the <span style="color:blue">testing</span>
+//!         feature flag.
+//!         </td>
+//!     </tr>
+//! </table>
+//!
+//! # Example
+//!
+//! ```
+//! use miniorm::{testing, MemoryStore};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     testing::run_all(|| async { MemoryStore::new() }, |n| format!("todo #{n}")).await;
+//! }
+//! ```
+use crate::prelude::{Batch, Crud};
+use std::{fmt::Debug, future::Future};
+
+/// Runs all the checks of the conformance suite, each one against a
+/// new store obtained from `new_store`.
+pub async fn run_all<S, E, F, Fut>(mut new_store: F, factory: impl Fn(usize) -> E)
+where
+    S: Crud<E>,
+    E: Debug + PartialEq + Clone,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = S>,
+{
+    create(&new_store().await, &factory).await;
+    read(&new_store().await, &factory).await;
+    read_not_found(&new_store().await, &factory).await;
+    list(&new_store().await, &factory).await;
+    count(&new_store().await, &factory).await;
+    update(&new_store().await, &factory).await;
+    delete(&new_store().await, &factory).await;
+    delete_all(&new_store().await, &factory).await;
+}
+
+/// Checks that `create` returns the provided entity along with a new id.
+pub async fn create<E>(store: &impl Crud<E>, factory: impl Fn(usize) -> E)
+where
+    E: Debug + PartialEq + Clone,
+{
+    let first = store.create(factory(0)).await.expect("create failed");
+    assert_eq!(first.inner(), &factory(0), "create altered the entity");
+
+    let second = store.create(factory(1)).await.expect("create failed");
+    assert_eq!(second.inner(), &factory(1), "create altered the entity");
+    assert!(first.id() < second.id(), "ids should be increasing");
+}
+
+/// Checks that `read` returns the entity as it was created.
+pub async fn read<E>(store: &impl Crud<E>, factory: impl Fn(usize) -> E)
+where
+    E: Debug + PartialEq + Clone,
+{
+    let created = store.create(factory(0)).await.expect("create failed");
+    let fetched = store.read(created.id()).await.expect("read failed");
+    assert_eq!(created, fetched);
+}
+
+/// Checks that `read` fails with [`sqlx::Error::RowNotFound`] on a missing id.
+pub async fn read_not_found<E>(store: &impl Crud<E>, factory: impl Fn(usize) -> E)
+where
+    E: Debug + PartialEq + Clone,
+{
+    let created = store.create(factory(0)).await.expect("create failed");
+    let missing = store.read(created.id() + 1).await;
+    assert!(
+        matches!(missing, Err(sqlx::Error::RowNotFound)),
+        "expected RowNotFound, got {missing:?}"
+    );
+}
+
+/// Checks that `list` returns all the entities ordered by id.
+pub async fn list<E>(store: &impl Crud<E>, factory: impl Fn(usize) -> E)
+where
+    E: Debug + PartialEq + Clone,
+{
+    assert!(store.list().await.expect("list failed").is_empty());
+
+    let mut expected = Vec::new();
+    for n in 0..3 {
+        expected.push(store.create(factory(n)).await.expect("create failed"));
+    }
+    assert_eq!(store.list().await.expect("list failed"), expected);
+}
+
+/// Checks that `count` returns the number of entities.
+pub async fn count<E>(store: &impl Crud<E>, factory: impl Fn(usize) -> E)
+where
+    E: Debug + PartialEq + Clone,
+{
+    assert_eq!(store.count().await.expect("count failed"), 0);
+    for n in 0..3 {
+        store.create(factory(n)).await.expect("create failed");
+        assert_eq!(store.count().await.expect("count failed"), n as u64 + 1);
+    }
+}
+
+/// Checks that `update` replaces the entity and keeps its id.
+pub async fn update<E>(store: &impl Crud<E>, factory: impl Fn(usize) -> E)
+where
+    E: Debug + PartialEq + Clone,
+{
+    let mut entity = store.create(factory(0)).await.expect("create failed");
+    let other = store.create(factory(1)).await.expect("create failed");
+    let id = entity.id();
+
+    *entity.inner_mut() = factory(2);
+    assert_eq!(
+        store.read(id).await.expect("read failed").inner(),
+        &factory(0)
+    );
+
+    let updated = store.update(entity.clone()).await.expect("update failed");
+    assert_eq!(updated, entity);
+    assert_eq!(store.read(id).await.expect("read failed"), entity);
+    assert_eq!(store.read(other.id()).await.expect("read failed"), other);
+    assert_eq!(store.count().await.expect("count failed"), 2);
+}
+
+/// Checks that `delete` removes the entity and fails with
+/// [`sqlx::Error::RowNotFound`] when the id is missing.
+pub async fn delete<E>(store: &impl Crud<E>, factory: impl Fn(usize) -> E)
+where
+    E: Debug + PartialEq + Clone,
+{
+    let entity = store.create(factory(0)).await.expect("create failed");
+    let other = store.create(factory(1)).await.expect("create failed");
+
+    store.delete(entity.id()).await.expect("delete failed");
+
+    let deleted = store.delete(entity.id()).await;
+    assert!(
+        matches!(deleted, Err(sqlx::Error::RowNotFound)),
+        "expected RowNotFound, got {deleted:?}"
+    );
+    let read = store.read(entity.id()).await;
+    assert!(
+        matches!(read, Err(sqlx::Error::RowNotFound)),
+        "expected RowNotFound, got {read:?}"
+    );
+    assert_eq!(store.list().await.expect("list failed"), [other]);
+}
+
+/// Checks that `delete_all` removes all the entities and returns their number.
+pub async fn delete_all<E>(store: &impl Crud<E>, factory: impl Fn(usize) -> E)
+where
+    E: Debug + PartialEq + Clone,
+{
+    for n in 0..3 {
+        store.create(factory(n)).await.expect("create failed");
+    }
+    assert_eq!(store.delete_all().await.expect("delete_all failed"), 3);
+    assert!(store.list().await.expect("list failed").is_empty());
+    assert_eq!(store.delete_all().await.expect("delete_all failed"), 0);
+}
+
+/// Checks that `update_if` and `delete_if` only write the entity if the check
+/// accepts its current version, and fail with [`sqlx::Error::RowNotFound`] when
+/// the id is missing.
+///
+/// `columns` are the columns of the entity, which are all updated by `update_if`.
+pub async fn conditional<E>(
+    store: &(impl Crud<E> + Sync),
+    columns: &[&'static str],
+    factory: impl Fn(usize) -> E,
+) where
+    E: Debug + PartialEq + Clone + Send + Sync + 'static,
+{
+    let entity = store.create(factory(0)).await.expect("create failed");
+    let id = entity.id();
+    let updated = crate::WithId::new(factory(1), id);
+
+    let expected = entity.clone();
+    let is_current = move |current: &crate::WithId<E>| *current == expected;
+    let result = store
+        .update_if(updated.clone(), columns, is_current.clone())
+        .await
+        .expect("update_if failed");
+    assert_eq!(result, Some(updated.clone()));
+    assert_eq!(store.read(id).await.expect("read failed"), updated);
+
+    let result = store
+        .update_if(entity.clone(), columns, is_current.clone())
+        .await
+        .expect("update_if failed");
+    assert_eq!(
+        result, None,
+        "update_if should not update a modified entity"
+    );
+    assert_eq!(store.read(id).await.expect("read failed"), updated);
+
+    let deleted = store
+        .delete_if(id, is_current)
+        .await
+        .expect("delete_if failed");
+    assert!(!deleted, "delete_if should not delete a modified entity");
+    assert_eq!(store.read(id).await.expect("read failed"), updated);
+    let deleted = store
+        .delete_if(id, |_| true)
+        .await
+        .expect("delete_if failed");
+    assert!(deleted);
+
+    let missing = store.update_if(entity, columns, |_| true).await;
+    assert!(
+        matches!(missing, Err(sqlx::Error::RowNotFound)),
+        "expected RowNotFound, got {missing:?}"
+    );
+    let missing = store.delete_if(id, |_| true).await;
+    assert!(
+        matches!(missing, Err(sqlx::Error::RowNotFound)),
+        "expected RowNotFound, got {missing:?}"
+    );
+}
+
+/// Checks that the [`Batch`] operations behave like their single entity
+/// counterparts, and that `delete_many` does not delete anything if one of
+/// the ids does not exist.
+pub async fn batch<E>(store: &(impl Crud<E> + Batch<E>), factory: impl Fn(usize) -> E)
+where
+    E: Debug + PartialEq + Clone,
+{
+    let entities: Vec<_> = (0..3).map(&factory).collect();
+    let created = store
+        .create_many(entities.clone())
+        .await
+        .expect("create_many failed");
+    assert_eq!(created.len(), 3);
+    for (created, entity) in created.iter().zip(&entities) {
+        assert_eq!(created.inner(), entity);
+    }
+    assert!(created.windows(2).all(|w| w[0].id() < w[1].id()));
+    assert_eq!(store.list().await.expect("list failed"), created);
+
+    let updated: Vec<_> = created
+        .iter()
+        .enumerate()
+        .map(|(n, entity)| crate::WithId::new(factory(n + 3), entity.id()))
+        .collect();
+    store
+        .update_many(updated.clone())
+        .await
+        .expect("update_many failed");
+    assert_eq!(store.list().await.expect("list failed"), updated);
+
+    let missing = updated.iter().map(|entity| entity.id()).max().unwrap_or(0) + 1;
+    let result = store.delete_many(&[updated[0].id(), missing]).await;
+    assert!(
+        matches!(result, Err(sqlx::Error::RowNotFound)),
+        "delete_many of a missing id should fail with RowNotFound, got {result:?}"
+    );
+    assert_eq!(store.count().await.expect("count failed"), 3);
+
+    store
+        .delete_many(&[updated[0].id(), updated[2].id()])
+        .await
+        .expect("delete_many failed");
+    assert_eq!(
+        store.list().await.expect("list failed"),
+        [updated[1].clone()]
+    );
+}
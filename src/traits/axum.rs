@@ -19,23 +19,156 @@ pub trait IntoAxumRouter {
     /// - `GET /` will list all entities,
     ///   - expected request payload: none
     ///   - returned response body: `Json<Vec<WithId<E>>>`
+    ///   - accepted query parameters:
+    ///     - `limit` and `offset` to paginate the list,
+    ///     - `sort` with a comma-separated list of columns, each
+    ///       prefixed with `-` for descending order (e.g. `sort=-done,description`),
+    ///     - `<column>=<value>` to only keep the entities whose column
+    ///       has the provided value (e.g. `done=false`),
+    ///     - `<column>__<op>=<value>` where `<op>` is one of `eq`, `ne`,
+    ///       `lt`, `lte`, `gt`, `gte` to compare the column with the value,
+    ///       or `like` to match a text column with an SQL `LIKE` pattern
+    ///       (e.g. `description__like=%25milk%25` for `%milk%`).
+    ///
+    ///     Columns are validated against [`Schema::MINIORM_COLUMNS`](crate::prelude::Schema::MINIORM_COLUMNS)
+    ///     (or `id`), and the values are converted to the types of the columns given by
+    ///     [`Schema::MINIORM_JSON_SCHEMA`](crate::prelude::Schema::MINIORM_JSON_SCHEMA) (an empty
+    ///     value being null for a nullable column). The filtering, sorting and pagination
+    ///     run in the database (see [`Read::query`](crate::prelude::Read::query)).
+    ///     An invalid parameter results in a `400 Bad Request`.
+    ///   - returned headers:
+    ///     - `X-Total-Count` with the number of entities matching the filters,
+    ///     - `Link` with the `first`, `prev`, `next` and `last` pages when `limit` is provided.
+    ///
+    ///   The `Accept` header selects the format of the body:
+    ///     - `application/json` (default): one JSON array,
+    ///     - `application/x-ndjson`: one `WithId<E>` JSON object per line,
+    ///     - `text/csv`: a header line with `id` and the
+    ///       [`Schema::MINIORM_COLUMNS`](crate::prelude::Schema::MINIORM_COLUMNS),
+    ///       then one line per entity.
+    ///
+    ///   Without query parameters, NDJSON and CSV bodies are streamed from the
+    ///   database, so that large tables can be exported without loading them in memory.
     /// - `POST /` will create a new entity,
     ///   - expected request payload: `Json<E>`
-    ///   - returned response body: `Json<WithId<E>>`
+    ///   - returned response: `201 Created` with a `Json<WithId<E>>` body and a
+    ///     `Location` header with the path of the new entity (e.g. `/todos/12`)
     /// - `PUT /` will update an existing entity,
     ///   - expected request payload: `Json<WithId<E>>`
     ///   - returned response body: `Json<WithId<E>>`
     /// - `DELETE /` will delete all entities
     ///   - expected request payload: none
-    ///   - returned response body: none
+    ///   - returned response: `204 No Content`
     /// - `GET /:id` to retrieve one entity from the store
     ///   - expected request payload: none
     ///   - returned response body: `Json<E>`
     /// - `PUT /:id` to update one entity in the store
     ///   - expected request payload: `Json<E>`
     ///   - returned response body: `Json<WithId<E>>`
+    /// - `PATCH /:id` to update some of the columns of one entity in the store
+    ///   - expected request payload, depending on the `Content-Type`:
+    ///     - `application/merge-patch+json` (or `application/json`): a JSON merge patch
+    ///       ([RFC 7396](https://www.rfc-editor.org/rfc/rfc7396)),
+    ///     - `application/json-patch+json`: a JSON patch
+    ///       ([RFC 6902](https://www.rfc-editor.org/rfc/rfc6902)).
+    ///
+    ///     Only the columns touched by the patch are updated. Patching a field which is not
+    ///     one of the [`Schema::MINIORM_COLUMNS`](crate::prelude::Schema::MINIORM_COLUMNS)
+    ///     results in a `400 Bad Request`.
+    ///   - returned response body: `Json<WithId<E>>`
     /// - `DELETE /:id` to delete one entity from the store
     ///   - expected request payload: none
-    ///   - returned response body: none
+    ///   - returned response: `204 No Content`
+    /// - `POST /_bulk` to create several entities at once
+    ///   - expected request payload: `Json<Vec<E>>`
+    ///   - returned response: `201 Created` with a `Json<Vec<WithId<E>>>` body, in the same order
+    /// - `PUT /_bulk` to update several entities at once
+    ///   - expected request payload: `Json<Vec<WithId<E>>>`
+    ///   - returned response body: `Json<Vec<WithId<E>>>`
+    /// - `DELETE /_bulk` to delete several entities at once
+    ///   - expected request payload: `Json<Vec<i64>>` with the ids of the entities
+    ///   - returned response: `204 No Content`
+    /// - `POST /_import` to create the entities of an upload, e.g. an export of `GET /`
+    ///   - expected request payload, depending on the `Content-Type`:
+    ///     - `application/x-ndjson`: one `E` (or `WithId<E>`) JSON object per line,
+    ///     - `text/csv`: a header line with the names of the columns, then one
+    ///       line per entity; the values are converted according to the types of
+    ///       the columns and empty values of nullable columns become `null`.
+    ///
+    ///     The `id` of the uploaded entities, if any, is ignored.
+    ///   - returned response body: `{"created": <number of created entities>}`
+    ///
+    ///   The body is streamed into the store by chunks of 1000 entities, each chunk
+    ///   in its own transaction. An invalid line results in a `400 Bad Request` (or
+    ///   `422 Unprocessable Entity`) whose detail contains the line number, and the
+    ///   entities of the previous chunks remain created.
+    ///
+    /// The bulk operations run in one transaction (see [`Batch`](crate::prelude::Batch)):
+    /// either all the entities are processed, or the request fails and none is.
+    ///
+    /// Every response containing a single entity carries an `ETag` header, which is
+    /// a hash of the serialized entity (including its id). This enables conditional
+    /// requests:
+    /// - `GET /:id` with an `If-None-Match` header matching the current `ETag`
+    ///   results in a `304 Not Modified` without body,
+    /// - `PUT /`, `PUT /:id`, `PATCH /:id` and `DELETE /:id` with an `If-Match` header
+    ///   which does not match the current `ETag` result in a `412 Precondition Failed`,
+    ///   and leave the entity untouched.
+    ///
+    /// The store checks the `If-Match` header when writing the entity (see
+    /// [`Update::update_if`](crate::prelude::Update::update_if)): with a
+    /// [`Store`](crate::Store), the entity is read and written in one transaction
+    /// locking its row, so that of two concurrent requests with the same `If-Match`,
+    /// only the first one succeeds.
+    ///
+    /// `WithId<E>` bodies use the nested representation of [`WithId`](crate::WithId), i.e.
+    /// `{"id": 1, "inner": {...}}`; use [`RouterBuilder::flat_ids`](crate::RouterBuilder::flat_ids)
+    /// to flatten the id alongside the fields of the entity instead.
+    ///
+    /// Every `GET` route also answers `HEAD` requests, e.g. `HEAD /:id` to check whether
+    /// an entity exists without transferring it. A method which is not served on a path
+    /// results in a `405 Method Not Allowed` with an `Allow` header listing the served ones.
+    ///
+    /// Errors are returned as `application/problem+json` bodies (see [`Problem`](crate::Problem)),
+    /// and errors of the store are mapped using [`Problem::from_sqlx_error`](crate::Problem::from_sqlx_error).
+    ///
+    /// Use a [`RouterBuilder`](crate::RouterBuilder) to only serve some of these
+    /// operations, rename the id path parameter or attach middleware layers to some routes.
+    ///
+    /// It can also serve [`Operation::Events`](crate::Operation::Events), i.e. `GET /events`,
+    /// which is not served by default and streams the changes of the entities as
+    /// [server-sent events](https://html.spec.whatwg.org/multipage/server-sent-events.html):
+    /// - `create` and `update` with the `WithId<E>` JSON entity as data,
+    /// - `delete` with `{"id": <id>}` as data,
+    /// - `delete_all` after `DELETE /`.
+    ///
+    /// By default, only the changes made through the router are streamed; with the
+    /// `changes` feature, `RouterBuilder::changes` streams the changes notified by
+    /// the database instead, wherever they come from.
+    ///
+    /// Use [`Store::into_axum_router_with_problems`](crate::Store::into_axum_router_with_problems)
+    /// to customize the mapping of the errors of the store.
     fn into_axum_router<S>(self) -> Router<S>;
 }
+
+/// Trait representing a type whose [`IntoAxumRouter`] routes can be
+/// described by an [`OpenApi`](crate::OpenApi) document.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">axum</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+pub trait IntoOpenApi {
+    /// Returns the OpenAPI document describing the routes of
+    /// [`IntoAxumRouter::into_axum_router`], assuming the router is
+    /// mounted at `path` (e.g. `/todos`).
+    ///
+    /// The document includes the schemas of the entity (named after the
+    /// entity type, e.g. `Todo`) and of the entity along with its id
+    /// (e.g. `TodoWithId`).
+    fn openapi(&self, path: &str) -> crate::OpenApi;
+}
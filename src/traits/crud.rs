@@ -1,6 +1,16 @@
 use async_trait::async_trait;
+use futures_core::{future::BoxFuture, stream::BoxStream, Stream};
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+    vec,
+};
 
 use crate::WithId;
+#[cfg(feature = "axum")]
+use crate::{ListQuery, Page};
+#[cfg(feature = "axum")]
+use serde::Serialize;
 
 /// \[C\]reate CRUD operation
 #[async_trait]
@@ -20,6 +30,77 @@ pub trait Read<E> {
 
     /// Count and return the number of object in the database
     async fn count(&self) -> sqlx::Result<u64>;
+
+    /// Streams all object from the database, ordered by `id`,
+    /// without loading them all in memory at once.
+    ///
+    /// By default, this lists all objects first and then streams them.
+    fn stream<'s>(&'s self) -> BoxStream<'s, sqlx::Result<WithId<E>>>
+    where
+        Self: Sync,
+        E: Send + 's,
+    {
+        Box::pin(ListStream::Listing(self.list()))
+    }
+
+    /// Lists the objects matching the filters of `query`, sorted and paginated
+    /// accordingly, along with the total number of objects matching the filters.
+    ///
+    /// By default, this lists all objects and then filters, sorts and paginates
+    /// them in memory, comparing the filters with the fields of the serialized
+    /// objects; a [`Store`](crate::Store) or a [`TenantStore`](crate::TenantStore)
+    /// runs the query in the database instead.
+    ///
+    /// Fails with an [`InvalidQuery`](crate::InvalidQuery) if the query refers to
+    /// an unknown column.
+    ///
+    /// <table>
+    ///     <tr>
+    ///         <td style="background-color:green;color:black;">
+    ///         Requires the <span style="color:blue">axum</span>
+    ///         feature flag.
+    ///         </td>
+    ///     </tr>
+    /// </table>
+    #[cfg(feature = "axum")]
+    async fn query(&self, query: &ListQuery) -> sqlx::Result<Page<E>>
+    where
+        Self: Sync,
+        E: Serialize + Send + 'async_trait,
+    {
+        let all = self.list().await?;
+        query.apply(all)
+    }
+}
+
+/// Stream of the objects returned by [`Read::list`].
+enum ListStream<'s, E> {
+    Listing(BoxFuture<'s, sqlx::Result<Vec<WithId<E>>>>),
+    Streaming(vec::IntoIter<WithId<E>>),
+}
+
+// the objects are never pinned
+impl<E> Unpin for ListStream<'_, E> {}
+
+impl<E> Stream for ListStream<'_, E> {
+    type Item = sqlx::Result<WithId<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let ListStream::Listing(list) = this {
+            match ready!(list.as_mut().poll(cx)) {
+                Ok(list) => *this = ListStream::Streaming(list.into_iter()),
+                Err(err) => {
+                    *this = ListStream::Streaming(Vec::new().into_iter());
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+        }
+        match this {
+            ListStream::Streaming(list) => Poll::Ready(list.next().map(Ok)),
+            ListStream::Listing(_) => unreachable!(),
+        }
+    }
 }
 
 /// \[U\]pdate CRUD operation
@@ -27,6 +108,51 @@ pub trait Read<E> {
 pub trait Update<E> {
     /// Update an object in the database and returns its `id`.
     async fn update(&self, entity: WithId<E>) -> sqlx::Result<WithId<E>>;
+
+    /// Update only the provided `columns` of an object in the database,
+    /// leaving the other columns untouched.
+    ///
+    /// By default, `columns` is ignored and all the columns are written, just
+    /// like [`Update::update`].
+    async fn update_columns(
+        &self,
+        entity: WithId<E>,
+        _columns: &[&'static str],
+    ) -> sqlx::Result<WithId<E>>
+    where
+        E: Send + 'async_trait,
+    {
+        self.update(entity).await
+    }
+
+    /// Update the provided `columns` of an object, just like [`Update::update_columns`],
+    /// provided that `check` accepts the version of the object currently in the
+    /// database, e.g. to make sure that it was not modified in the meantime.
+    ///
+    /// Returns `None`, without updating anything, if `check` rejects the current
+    /// version, and fails with [`sqlx::Error::RowNotFound`] if the object does not
+    /// exist.
+    ///
+    /// By default, the object is read and then updated, so that it may still be
+    /// modified in between; a [`Store`](crate::Store) reads and updates it in one
+    /// transaction, locking its row.
+    async fn update_if<F>(
+        &self,
+        entity: WithId<E>,
+        columns: &[&'static str],
+        check: F,
+    ) -> sqlx::Result<Option<WithId<E>>>
+    where
+        Self: Read<E> + Sync,
+        E: Send + 'async_trait,
+        F: Fn(&WithId<E>) -> bool + Send + Sync + 'async_trait,
+    {
+        let current = self.read(entity.id()).await?;
+        if !check(&current) {
+            return Ok(None);
+        }
+        self.update_columns(entity, columns).await.map(Some)
+    }
 }
 
 /// \[D\]elete CRUD operation
@@ -37,6 +163,49 @@ pub trait Delete<E> {
 
     /// Delete all objects of type E and return the number of deleted rows
     async fn delete_all(&self) -> sqlx::Result<u64>;
+
+    /// Delete the object corresponding to the provided `id`, provided that `check`
+    /// accepts the version of the object currently in the database, and returns
+    /// whether it was deleted.
+    ///
+    /// By default, the object is read and then deleted, so that it may still be
+    /// modified in between; a [`Store`](crate::Store) reads and deletes it in one
+    /// transaction, locking its row.
+    async fn delete_if<F>(&self, id: i64, check: F) -> sqlx::Result<bool>
+    where
+        Self: Read<E> + Sync,
+        E: Send + 'async_trait,
+        F: Fn(&WithId<E>) -> bool + Send + Sync + 'async_trait,
+    {
+        let current = self.read(id).await?;
+        if !check(&current) {
+            return Ok(false);
+        }
+        self.delete(id).await?;
+        Ok(true)
+    }
+}
+
+/// Batch CRUD operations, which either succeed or fail as a whole
+#[async_trait]
+pub trait Batch<E> {
+    /// Create all the objects in the database, and returns them along
+    /// with their `id`, in the same order.
+    ///
+    /// If one of them cannot be created, none of them is.
+    async fn create_many(&self, entities: Vec<E>) -> sqlx::Result<Vec<WithId<E>>>;
+
+    /// Update all the objects in the database.
+    ///
+    /// Just like [`Update::update`], updating a missing `id` does not affect any row.
+    /// If one of them cannot be updated, none of them is.
+    async fn update_many(&self, entities: Vec<WithId<E>>) -> sqlx::Result<Vec<WithId<E>>>;
+
+    /// Delete the objects corresponding to the provided `ids`.
+    ///
+    /// If one of them does not exist, this fails with [`sqlx::Error::RowNotFound`]
+    /// and none of them is deleted.
+    async fn delete_many(&self, ids: &[i64]) -> sqlx::Result<()>;
 }
 
 /// CRUD operations
@@ -38,6 +38,14 @@ use sqlx::Database;
 ///     const MINIORM_DELETE_ALL: &'static str = r#"
 ///         DELETE FROM todo"#;
 ///     const MINIORM_TABLE_NAME: &'static str = "todo";
+///     const MINIORM_JSON_SCHEMA: &'static str = r#"{
+///         "type": "object",
+///         "properties": {
+///             "description": {"type": "string"},
+///             "done": {"type": "boolean"}
+///         },
+///         "required": ["description", "done"]
+///     }"#;
 ///     const MINIORM_COLUMNS: &'static [&'static str] = &[
 ///         "description",
 ///         "done",
@@ -81,6 +89,13 @@ pub trait Schema<DB: Database> {
     /// name of the table in the database
     const MINIORM_TABLE_NAME: &'static str;
 
+    /// name of the entity, e.g. in the generated OpenAPI documents
+    const MINIORM_ENTITY_NAME: &'static str = Self::MINIORM_TABLE_NAME;
+
+    /// JSON schema of the serialized entity, giving the type of each column
+    /// and whether it may be null, e.g. in the generated OpenAPI documents
+    const MINIORM_JSON_SCHEMA: &'static str;
+
     /// list of all the columns and their postgress types
     const MINIORM_COLUMNS: &'static [&'static str];
 }
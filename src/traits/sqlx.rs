@@ -80,3 +80,116 @@ impl SupportsReturning for sqlx::Postgres {}
 
 #[cfg(feature = "sqlite")]
 impl SupportsReturning for sqlx::Sqlite {}
+
+/// Trait providing the bits of SQL syntax which differ from one database
+/// to another, for the queries that need to be built at runtime.
+pub trait Dialect {
+    /// declaration of an auto-incremented `id` primary key
+    const ID_DECLARATION: &'static str;
+
+    /// placeholder for the `index`-th bound value, starting from 1
+    fn placeholder(index: usize) -> String;
+
+    /// suffix of a `SELECT` locking the rows it reads until the end of the
+    /// transaction
+    const FOR_UPDATE: &'static str = " FOR UPDATE";
+
+    /// query resetting the sequence generating the ids of `table` past the
+    /// largest id, once rows were inserted with explicit ids, if the database
+    /// does not do it by itself
+    fn reset_id_sequence(_table: &str) -> Option<String> {
+        None
+    }
+
+    /// `placeholder` of a string compared with a column whose values have the
+    /// given JSON schema `format` (e.g. `date-time`), if the database does not
+    /// convert the string by itself
+    #[cfg(feature = "axum")]
+    fn cast_string(placeholder: String, _format: &str) -> String {
+        placeholder
+    }
+
+    /// binds a JSON value compared with a column, as a value of the matching
+    /// SQL type
+    #[cfg(feature = "axum")]
+    fn bind_json<'q, O>(
+        query: QueryAs<'q, Self, O, <Self as HasArguments<'q>>::Arguments>,
+        value: &serde_json::Value,
+    ) -> QueryAs<'q, Self, O, <Self as HasArguments<'q>>::Arguments>
+    where
+        Self: Database;
+}
+
+#[cfg(feature = "postgres")]
+impl Dialect for sqlx::Postgres {
+    const ID_DECLARATION: &'static str = "id BIGSERIAL PRIMARY KEY";
+
+    fn placeholder(index: usize) -> String {
+        format!("${index}")
+    }
+
+    #[cfg(feature = "axum")]
+    fn bind_json<'q, O>(
+        query: QueryAs<'q, Self, O, <Self as HasArguments<'q>>::Arguments>,
+        value: &serde_json::Value,
+    ) -> QueryAs<'q, Self, O, <Self as HasArguments<'q>>::Arguments> {
+        crate::query::bind_json(query, value)
+    }
+
+    fn reset_id_sequence(table: &str) -> Option<String> {
+        Some(format!(
+            "SELECT setval(pg_get_serial_sequence('{table}', 'id'), \
+            COALESCE(MAX(id), 0) + 1, false) FROM {table}"
+        ))
+    }
+
+    #[cfg(feature = "axum")]
+    fn cast_string(placeholder: String, format: &str) -> String {
+        let ty = match format {
+            "date-time" => "TIMESTAMPTZ",
+            "date" => "DATE",
+            "time" => "TIME",
+            "uuid" => "UUID",
+            _ => return placeholder,
+        };
+        format!("CAST({placeholder} AS {ty})")
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Dialect for sqlx::Sqlite {
+    const ID_DECLARATION: &'static str = "id INTEGER PRIMARY KEY AUTOINCREMENT";
+
+    // SQLite cannot lock rows, but a transaction which read a row written in
+    // the meantime by another one fails to write instead of overwriting it
+    const FOR_UPDATE: &'static str = "";
+
+    fn placeholder(index: usize) -> String {
+        format!("${index}")
+    }
+
+    #[cfg(feature = "axum")]
+    fn bind_json<'q, O>(
+        query: QueryAs<'q, Self, O, <Self as HasArguments<'q>>::Arguments>,
+        value: &serde_json::Value,
+    ) -> QueryAs<'q, Self, O, <Self as HasArguments<'q>>::Arguments> {
+        crate::query::bind_json(query, value)
+    }
+}
+
+#[cfg(feature = "mysql")]
+impl Dialect for sqlx::MySql {
+    const ID_DECLARATION: &'static str = "id INT AUTO_INCREMENT NOT NULL PRIMARY KEY";
+
+    fn placeholder(_index: usize) -> String {
+        "?".into()
+    }
+
+    #[cfg(feature = "axum")]
+    fn bind_json<'q, O>(
+        query: QueryAs<'q, Self, O, <Self as HasArguments<'q>>::Arguments>,
+        value: &serde_json::Value,
+    ) -> QueryAs<'q, Self, O, <Self as HasArguments<'q>>::Arguments> {
+        crate::query::bind_json(query, value)
+    }
+}
@@ -236,6 +236,132 @@ impl<'de, E: serde::Deserialize<'de>> serde::Deserialize<'de> for WithId<E> {
     }
 }
 
+/// Flattened serde representation of a [`WithId`], where the `id` is
+/// serialized alongside the fields of the entity, e.g. `{"id": 1, "name": "miniorm"}`,
+/// instead of the nested `{"id": 1, "inner": {"name": "miniorm"}}` of [`WithId`].
+///
+/// The entity must be serialized as a map (e.g. a struct with named fields),
+/// which should not have an `id` field of its own.
+///
+/// <table>
+///     <tr>
+///         <td style="background-color:green;color:black;">
+///         Requires the <span style="color:blue">serde</span>
+///         feature flag.
+///         </td>
+///     </tr>
+/// </table>
+///
+/// # Example
+///
+/// ```
+/// use miniorm::{Flat, WithId};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Todo {
+///     description: String,
+/// }
+///
+/// let todo = WithId::new(Todo { description: "checkout miniorm".into() }, 1);
+/// let json = serde_json::to_string(&Flat(todo)).unwrap();
+/// assert_eq!(json, r#"{"id":1,"description":"checkout miniorm"}"#);
+///
+/// let Flat(todo): Flat<Todo> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(todo.id(), 1);
+/// assert_eq!(todo.description, "checkout miniorm");
+/// ```
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Flat<E>(pub WithId<E>);
+
+#[cfg(feature = "serde")]
+impl<E> From<WithId<E>> for Flat<E> {
+    fn from(with_id: WithId<E>) -> Self {
+        Flat(with_id)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<E> From<Flat<E>> for WithId<E> {
+    fn from(flat: Flat<E>) -> Self {
+        flat.0
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<E> Deref for Flat<E> {
+    type Target = WithId<E>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<E> DerefMut for Flat<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "serde")]
+mod flat {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize)]
+    pub(crate) struct FlatRef<'a, E> {
+        pub(crate) id: i64,
+        #[serde(flatten)]
+        pub(crate) inner: &'a E,
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct FlatOwned<E> {
+        pub(crate) id: i64,
+        #[serde(flatten)]
+        pub(crate) inner: E,
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<E> WithId<E> {
+    /// Serializes the entity in its [`Flat`] representation.
+    pub(crate) fn serialize_flat<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        E: serde::Serialize,
+        S: serde::Serializer,
+    {
+        use serde::Serialize;
+        let flat = flat::FlatRef {
+            id: self.id,
+            inner: &self.inner,
+        };
+        flat.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<E: serde::Serialize> serde::Serialize for Flat<E> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.serialize_flat(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, E: serde::Deserialize<'de>> serde::Deserialize<'de> for Flat<E> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let flat = flat::FlatOwned::deserialize(deserializer)?;
+        Ok(Flat(WithId::new(flat.inner, flat.id)))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::WithId;
@@ -288,5 +414,21 @@ mod test {
             assert_eq!(with_id.inner.0, 420);
             assert_eq!(with_id.id, 69);
         }
+
+        #[test]
+        fn flat_is_serialize_and_deserialize() {
+            use crate::Flat;
+
+            #[derive(Debug, PartialEq, Serialize, Deserialize)]
+            struct Foo {
+                x: u32,
+            }
+
+            let flat = Flat(WithId::new(Foo { x: 420 }, 69));
+            let json = serde_json::to_string(&flat).unwrap();
+            assert_eq!(json, r#"{"id":69,"x":420}"#);
+            assert_eq!(serde_json::from_str::<Flat<Foo>>(&json).unwrap(), flat);
+            assert!(serde_json::from_str::<Flat<Foo>>(r#"{"x":420}"#).is_err());
+        }
     }
 }
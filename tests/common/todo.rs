@@ -2,7 +2,10 @@ use miniorm::Entity;
 use sqlx::FromRow;
 
 #[derive(Debug, Clone, Eq, PartialEq, FromRow, Entity, Hash)]
-#[cfg_attr(feature = "axum", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    any(feature = "axum", feature = "dump", feature = "fixtures"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct Todo {
     #[column(TEXT NOT NULL)]
     description: String,
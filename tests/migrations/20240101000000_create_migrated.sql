@@ -0,0 +1 @@
+CREATE TABLE IF NOT EXISTS migrated (id INTEGER PRIMARY KEY);
@@ -0,0 +1,93 @@
+mod common;
+
+use common::Todo;
+use miniorm::{prelude::*, AnyStore};
+use serial_test::serial;
+use std::error::Error;
+
+#[macro_export]
+macro_rules! test_any {
+    ($backend: literal, $url: block) => {
+        async fn get_clean_store() -> Result<AnyStore<Todo>, Box<dyn Error>> {
+            let url: String = $url;
+            let store = AnyStore::connect(&url).await?;
+            store.recreate_table().await?;
+            Ok(store)
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn backend() {
+            let store = get_clean_store().await.unwrap();
+            assert_eq!(store.backend(), $backend);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn crud() {
+            let store = get_clean_store().await.unwrap();
+            let mut todo = store.create(Todo::new("checkout miniorm")).await.unwrap();
+            assert_eq!(store.read(todo.id()).await.unwrap(), todo);
+
+            todo.mark_as_done();
+            store.update(todo.clone()).await.unwrap();
+            assert_eq!(store.list().await.unwrap(), [todo.clone()]);
+
+            store.delete(todo.id()).await.unwrap();
+            assert_eq!(store.count().await.unwrap(), 0);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn batch() {
+            let store = get_clean_store().await.unwrap();
+            let todos = vec![Todo::new("buy milk"), Todo::new("wash the dishes")];
+            let todos = store.create_many(todos).await.unwrap();
+            assert_eq!(store.list().await.unwrap(), todos);
+
+            let ids: Vec<_> = todos.iter().map(|todo| todo.id()).collect();
+            store.delete_many(&ids).await.unwrap();
+            assert_eq!(store.count().await.unwrap(), 0);
+        }
+    };
+}
+
+mod test_any {
+    use super::*;
+
+    #[cfg(feature = "mysql")]
+    mod mysql {
+        use super::*;
+
+        test_any!("MySQL", {
+            dotenv::dotenv()?;
+            std::env::var("MYSQL_URL").expect("missing MYSQL_URL env")
+        });
+    }
+
+    #[cfg(feature = "postgres")]
+    mod postgres {
+        use super::*;
+
+        test_any!("PostgreSQL", {
+            dotenv::dotenv()?;
+            std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env")
+        });
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod sqlite {
+        use super::*;
+
+        test_any!("SQLite", { "sqlite::memory:".into() });
+    }
+}
+
+#[tokio::test]
+async fn unsupported_backend() {
+    let res = AnyStore::<Todo>::connect("oracle://localhost/miniorm").await;
+    assert!(matches!(res, Err(sqlx::Error::Configuration(_))));
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use common::Todo;
+use miniorm::{prelude::*, AuditOperation, AuditedStore};
+use serial_test::serial;
+use std::error::Error;
+
+#[macro_export]
+macro_rules! test_audit {
+    ($backend: ty, $db: block) => {
+        async fn get_clean_store() -> Result<AuditedStore<$backend, Todo>, Box<dyn Error>> {
+            let pool = $db;
+            let store = Store::new(pool).audited();
+            store.recreate_table().await?;
+            Ok(store)
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn history() {
+            let store = get_clean_store().await.unwrap().with_actor("alice");
+            let mut todo = store.create(Todo::new("checkout miniorm")).await.unwrap();
+            let created = todo.clone();
+            todo.mark_as_done();
+            store.update(todo.clone()).await.unwrap();
+            store.delete(todo.id()).await.unwrap();
+
+            let history = store.history(todo.id()).await.unwrap();
+            let operations: Vec<_> = history.iter().map(|entry| entry.operation).collect();
+            assert_eq!(
+                operations,
+                [
+                    AuditOperation::Create,
+                    AuditOperation::Update,
+                    AuditOperation::Delete
+                ]
+            );
+            for entry in &history {
+                assert_eq!(entry.entity_id, todo.id());
+                assert_eq!(entry.actor.as_deref(), Some("alice"));
+            }
+            assert_eq!(history[0].before, None);
+            assert_eq!(history[0].after.as_ref(), Some(created.inner()));
+            assert_eq!(history[1].before.as_ref(), Some(created.inner()));
+            assert_eq!(history[1].after.as_ref(), Some(todo.inner()));
+            assert_eq!(history[2].before.as_ref(), Some(todo.inner()));
+            assert_eq!(history[2].after, None);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn update_missing() {
+            let store = get_clean_store().await.unwrap();
+            store
+                .update(WithId::new(Todo::new("missing"), 3))
+                .await
+                .unwrap();
+            assert!(store.history(3).await.unwrap().is_empty());
+            assert_eq!(store.count().await.unwrap(), 0);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn delete_all() {
+            let store = get_clean_store().await.unwrap();
+            let todo1 = store.create(Todo::new("todo1")).await.unwrap();
+            let todo2 = store.create(Todo::new("todo2")).await.unwrap();
+            assert_eq!(store.delete_all().await.unwrap(), 2);
+
+            for todo in [todo1, todo2] {
+                let history = store.history(todo.id()).await.unwrap();
+                assert_eq!(history.len(), 2);
+                assert_eq!(history[1].operation, AuditOperation::Delete);
+                assert_eq!(history[1].actor, None);
+            }
+        }
+    };
+}
+
+mod test_audit {
+    use super::*;
+
+    #[cfg(feature = "mysql")]
+    mod mysql {
+        use super::*;
+        use sqlx::{MySql, MySqlPool};
+
+        test_audit!(MySql, {
+            dotenv::dotenv()?;
+            let url = std::env::var("MYSQL_URL").expect("missing MYSQL_URL env");
+            MySqlPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "postgres")]
+    mod postgres {
+        use super::*;
+        use sqlx::{PgPool, Postgres};
+
+        test_audit!(Postgres, {
+            dotenv::dotenv()?;
+            let url = std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env");
+            PgPool::connect(&url).await?
+        });
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn concurrent_updates() {
+            let store = get_clean_store().await.unwrap();
+            let todo = store.create(Todo::new("todo")).await.unwrap();
+            let updates: Vec<_> = (0..8)
+                .map(|i| {
+                    let store = store.clone();
+                    let todo = WithId::new(Todo::new(format!("todo{i}")), todo.id());
+                    tokio::spawn(async move { store.update(todo).await })
+                })
+                .collect();
+            for update in updates {
+                update.await.unwrap().unwrap();
+            }
+
+            // each update must record the entity written by the previous one
+            let history = store.history(todo.id()).await.unwrap();
+            assert_eq!(history.len(), 9);
+            for entries in history.windows(2) {
+                assert_eq!(entries[1].before, entries[0].after);
+            }
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod sqlite {
+        use super::*;
+        use sqlx::{Sqlite, SqlitePool};
+
+        test_audit!(Sqlite, { SqlitePool::connect(":memory:").await? });
+    }
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use common::Todo;
+use miniorm::{prelude::*, StoreBuilder, StoreConfig};
+use serial_test::serial;
+use sqlx::FromRow;
+use std::{error::Error, time::Duration};
+
+#[derive(Debug, Clone, Eq, PartialEq, FromRow, Entity)]
+struct Tag {
+    #[column(TEXT NOT NULL)]
+    name: String,
+}
+
+#[macro_export]
+macro_rules! test_builder {
+    ($backend: ty, $url: block) => {
+        fn url() -> Result<String, Box<dyn Error>> {
+            Ok($url)
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn connect() {
+            let store = Store::<$backend, Todo>::connect(&url().unwrap())
+                .await
+                .unwrap();
+            store.recreate_table().await.unwrap();
+            store.create(Todo::new("buy milk")).await.unwrap();
+            assert_eq!(store.count().await.unwrap(), 1);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn shared_pool() {
+            let stores = StoreBuilder::<$backend>::new(&url().unwrap())
+                .unwrap()
+                .max_connections(3)
+                .acquire_timeout(Duration::from_secs(5))
+                .create_tables(true)
+                .connect()
+                .await
+                .unwrap();
+            assert_eq!(stores.pool().options().get_max_connections(), 3);
+
+            let todos: Store<_, Todo> = stores.store().await.unwrap();
+            let tags: Store<_, Tag> = stores.store().await.unwrap();
+            todos.delete_all().await.unwrap();
+            tags.delete_all().await.unwrap();
+
+            todos.create(Todo::new("buy milk")).await.unwrap();
+            let name = "groceries".into();
+            tags.create(Tag { name }).await.unwrap();
+            assert_eq!(todos.count().await.unwrap(), 1);
+            assert_eq!(tags.count().await.unwrap(), 1);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn from_config() {
+            let config = StoreConfig {
+                url: url().unwrap(),
+                max_connections: Some(2),
+                idle_timeout: Some(Duration::from_secs(60)),
+                create_tables: true,
+                ..Default::default()
+            };
+            let stores = StoreBuilder::<$backend>::from_config(&config)
+                .unwrap()
+                .connect()
+                .await
+                .unwrap();
+            assert_eq!(stores.pool().options().get_max_connections(), 2);
+            let idle_timeout = stores.pool().options().get_idle_timeout();
+            assert_eq!(idle_timeout, Some(Duration::from_secs(60)));
+
+            let tags: Store<_, Tag> = stores.store().await.unwrap();
+            tags.count().await.unwrap();
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn migrations() {
+            let stores = StoreBuilder::<$backend>::new(&url().unwrap())
+                .unwrap()
+                .migrate(sqlx::migrate!("tests/migrations"))
+                .connect()
+                .await
+                .unwrap();
+            sqlx::query("SELECT id FROM migrated")
+                .fetch_all(stores.pool())
+                .await
+                .unwrap();
+        }
+    };
+}
+
+mod test_builder {
+    use super::*;
+
+    #[cfg(feature = "mysql")]
+    mod mysql {
+        use super::*;
+        use sqlx::MySql;
+
+        test_builder!(MySql, {
+            dotenv::dotenv()?;
+            std::env::var("MYSQL_URL").expect("missing MYSQL_URL env")
+        });
+    }
+
+    #[cfg(feature = "postgres")]
+    mod postgres {
+        use super::*;
+        use sqlx::Postgres;
+
+        test_builder!(Postgres, {
+            dotenv::dotenv()?;
+            std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env")
+        });
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod sqlite {
+        use super::*;
+        use sqlx::{sqlite::SqliteJournalMode, Sqlite};
+
+        test_builder!(Sqlite, {
+            let path = std::env::temp_dir().join("miniorm_test_builder.db");
+            format!("sqlite://{}?mode=rwc", path.display())
+        });
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn pragmas() {
+            let stores = StoreBuilder::<Sqlite>::new(&url().unwrap())
+                .unwrap()
+                .journal_mode(SqliteJournalMode::Wal)
+                .foreign_keys(true)
+                .busy_timeout(Duration::from_millis(250))
+                .pragma("cache_size", "-4000")
+                .connect()
+                .await
+                .unwrap();
+
+            let pool = stores.pool();
+            let pragma = |name: &str| format!("PRAGMA {name}");
+            let (mode,): (String,) = sqlx::query_as(&pragma("journal_mode"))
+                .fetch_one(pool)
+                .await
+                .unwrap();
+            assert_eq!(mode, "wal");
+            for (name, expected) in [
+                ("foreign_keys", 1),
+                ("busy_timeout", 250),
+                ("cache_size", -4000),
+            ] {
+                let (value,): (i64,) = sqlx::query_as(&pragma(name)).fetch_one(pool).await.unwrap();
+                assert_eq!(value, expected, "{name}");
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "changes")]
+mod common;
+
+use axum::{
+    body::{Body, BodyDataStream},
+    http::{self, header::CONTENT_TYPE},
+};
+use common::Todo;
+use futures::StreamExt;
+use miniorm::{prelude::*, Change, ChangeOperation};
+use serial_test::serial;
+use sqlx::{PgPool, SqlitePool};
+use std::time::Duration;
+use tower::Service;
+
+#[cfg_attr(not(feature = "integration_tests"), ignore)]
+#[serial]
+#[tokio::test]
+async fn changes() {
+    dotenv::dotenv().unwrap();
+    let url = std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env");
+    let store = Store::new(PgPool::connect(&url).await.unwrap());
+    store.recreate_table().await.unwrap();
+    store.install_change_trigger().await.unwrap();
+
+    let mut changes = store.changes().await.unwrap();
+    let mut todo = store.create(Todo::new("checkout miniorm")).await.unwrap();
+    todo.mark_as_done();
+    store.update(todo.clone()).await.unwrap();
+    store.delete(todo.id()).await.unwrap();
+
+    let id = todo.id();
+    for op in [
+        ChangeOperation::Create,
+        ChangeOperation::Update,
+        ChangeOperation::Delete,
+    ] {
+        let change = changes.next().await.unwrap().unwrap();
+        assert_eq!(change, Change { op, id });
+    }
+}
+
+/// Appends the chunks of `events` to `received` until it holds `count` events.
+async fn receive(events: &mut BodyDataStream, received: &mut String, count: usize) {
+    while received.matches("\n\n").count() < count {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), events.next());
+        *received += &String::from_utf8_lossy(&chunk.await.unwrap().unwrap().unwrap());
+    }
+}
+
+#[cfg_attr(not(feature = "integration_tests"), ignore)]
+#[serial]
+#[tokio::test]
+async fn changes_as_server_sent_events() {
+    dotenv::dotenv().unwrap();
+    let url = std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env");
+    let store = Store::new(PgPool::connect(&url).await.unwrap());
+    store.recreate_table().await.unwrap();
+    store.install_change_trigger().await.unwrap();
+
+    let changes = store.changes().await.unwrap();
+    let mut router = store.clone().router_builder().changes(changes).build();
+    let request = http::Request::get("/events").body(Body::empty()).unwrap();
+    let response = router.call(request).await.unwrap();
+    assert_eq!(response.headers()[CONTENT_TYPE], "text/event-stream");
+    let mut events = response.into_body().into_data_stream();
+
+    // made without going through the router
+    let todo = store.create(Todo::new("checkout miniorm")).await.unwrap();
+    let mut received = String::new();
+    receive(&mut events, &mut received, 1).await;
+    assert!(received.starts_with(&format!("event: create\ndata: {{\"id\":{}", todo.id())));
+
+    store.delete(todo.id()).await.unwrap();
+    receive(&mut events, &mut received, 2).await;
+    assert!(received.ends_with(&format!(
+        "event: delete\ndata: {{\"id\":{}}}\n\n",
+        todo.id()
+    )));
+}
+
+#[cfg_attr(not(feature = "integration_tests"), ignore)]
+#[serial]
+#[tokio::test]
+async fn changes_of_entities_not_visible_to_the_store() {
+    let store = Store::new(SqlitePool::connect(":memory:").await.unwrap());
+    store.recreate_table().await.unwrap();
+    let todo = store.create(Todo::new("checkout miniorm")).await.unwrap();
+
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let changes = futures::stream::unfold(receiver, |mut receiver| async move {
+        let change = receiver.recv().await?;
+        Some((Ok(change), receiver))
+    });
+    let mut router = store.clone().router_builder().changes(changes).build();
+    let request = http::Request::get("/events").body(Body::empty()).unwrap();
+    let response = router.call(request).await.unwrap();
+    let mut events = response.into_body().into_data_stream();
+
+    // e.g. an entity of another tenant sharing the table, which cannot be read
+    for op in [
+        ChangeOperation::Create,
+        ChangeOperation::Update,
+        ChangeOperation::Delete,
+    ] {
+        sender.send(Change { op, id: 42 }).unwrap();
+    }
+    let op = ChangeOperation::Delete;
+    sender.send(Change { op, id: todo.id() }).unwrap();
+
+    let mut received = String::new();
+    receive(&mut events, &mut received, 1).await;
+    assert_eq!(
+        received,
+        format!("event: delete\ndata: {{\"id\":{}}}\n\n", todo.id())
+    );
+}
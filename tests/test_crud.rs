@@ -1,14 +1,42 @@
 mod common;
 
 use common::Todo;
-use miniorm::prelude::*;
+use miniorm::{prelude::*, FilterOp, ListQuery};
 use serial_test::serial;
 use std::error::Error;
 
+/// Checks that `query` filters, sorts and paginates like the default
+/// implementation of `Read::query` does.
+async fn check_query(store: &(impl Crud<Todo> + Sync)) {
+    for n in 1..=5 {
+        let mut todo = Todo::new(format!("todo{n}"));
+        if n % 2 == 0 {
+            todo.mark_as_done();
+        }
+        store.create(todo).await.unwrap();
+    }
+    let query = ListQuery::new()
+        .filter("done", FilterOp::Eq, false)
+        .filter("description", FilterOp::Like, "todo%")
+        .sort_desc("description")
+        .limit(2)
+        .offset(1);
+    let page = store.query(&query).await.unwrap();
+    let ids: Vec<_> = page.items.iter().map(|todo| todo.id()).collect();
+    assert_eq!(ids, [3, 1]);
+    assert_eq!(page.total, 3);
+
+    let query = ListQuery::new().filter("id", FilterOp::Gte, 4).sort("done");
+    let page = store.query(&query).await.unwrap();
+    let ids: Vec<_> = page.items.iter().map(|todo| todo.id()).collect();
+    assert_eq!(ids, [5, 4]);
+    assert_eq!(page.total, 2);
+}
+
 #[macro_export]
 macro_rules! test_crud {
     ($db: block) => {
-        async fn get_clean_store() -> Result<impl Crud<Todo>, Box<dyn Error>> {
+        async fn get_clean_store() -> Result<impl Crud<Todo> + Batch<Todo> + Sync, Box<dyn Error>> {
             let pool = $db;
             let store = Store::new(pool);
             store.recreate_table().await?;
@@ -118,6 +146,42 @@ macro_rules! test_crud {
             assert_eq!(result, 3);
             assert!(store.list().await.unwrap().is_empty());
         }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn conformance() {
+            miniorm::testing::run_all(
+                || async { get_clean_store().await.unwrap() },
+                |n| Todo::new(format!("todo{n}")),
+            )
+            .await;
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn batch() {
+            let store = get_clean_store().await.unwrap();
+            miniorm::testing::batch(&store, |n| Todo::new(format!("todo{n}"))).await;
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn query() {
+            let store = get_clean_store().await.unwrap();
+            check_query(&store).await;
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn conditional() {
+            let store = get_clean_store().await.unwrap();
+            let columns = &["description", "done"];
+            miniorm::testing::conditional(&store, columns, |n| Todo::new(format!("todo{n}"))).await;
+        }
     };
 }
 
@@ -151,8 +215,66 @@ mod test_crud {
     #[cfg(feature = "sqlite")]
     mod sqlite {
         use super::*;
-        use sqlx::SqlitePool;
+        use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
+        use std::{str::FromStr, time::Duration};
 
         test_crud!({ SqlitePool::connect(":memory:").await? });
+
+        /// the created rows are committed as soon as `create` returns, and
+        /// are thus visible from other connections
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn create_commits() {
+            let path = std::env::temp_dir().join("miniorm_test_crud.db");
+            let url = format!("sqlite://{}?mode=rwc", path.display());
+            let store = Store::new(SqlitePool::connect(&url).await.unwrap());
+            store.recreate_table().await.unwrap();
+            // fails right away if the database is still locked by `create`
+            let options = SqliteConnectOptions::from_str(&url)
+                .unwrap()
+                .busy_timeout(Duration::ZERO);
+            let other = SqlitePool::connect_with(options).await.unwrap();
+            for n in 1..=10 {
+                store.create(Todo::new(format!("todo{n}"))).await.unwrap();
+                sqlx::query("UPDATE todo SET done = true")
+                    .execute(&other)
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    mod memory {
+        use super::*;
+        use miniorm::{testing, MemoryStore};
+
+        #[tokio::test]
+        async fn conformance() {
+            testing::run_all(
+                || async { MemoryStore::new() },
+                |n| Todo::new(format!("todo{n}")),
+            )
+            .await;
+        }
+
+        #[tokio::test]
+        async fn batch() {
+            testing::batch(&MemoryStore::new(), |n| Todo::new(format!("todo{n}"))).await;
+        }
+
+        #[tokio::test]
+        async fn query() {
+            check_query(&MemoryStore::new()).await;
+        }
+
+        #[tokio::test]
+        async fn conditional() {
+            let columns = &["description", "done"];
+            testing::conditional(&MemoryStore::new(), columns, |n| {
+                Todo::new(format!("todo{n}"))
+            })
+            .await;
+        }
     }
 }
@@ -0,0 +1,118 @@
+#![cfg(feature = "dump")]
+mod common;
+
+use common::Todo;
+use miniorm::{prelude::*, DumpFormat};
+use serial_test::serial;
+use std::error::Error;
+
+#[macro_export]
+macro_rules! test_dump {
+    ($backend: ty, $db: block) => {
+        async fn get_clean_store() -> Result<Store<$backend, Todo>, Box<dyn Error>> {
+            let pool = $db;
+            let store = Store::new(pool);
+            store.recreate_table().await?;
+            Ok(store)
+        }
+
+        async fn dump_and_restore(format: DumpFormat) {
+            let store = get_clean_store().await.unwrap();
+            let todo1 = store.create(Todo::new("buy milk")).await.unwrap();
+            let todo2 = store.create(Todo::new("with, \"quotes\"")).await.unwrap();
+            let mut todo3 = store.create(Todo::new("multi\nline")).await.unwrap();
+            let todo4 = store.create(Todo::new("deleted")).await.unwrap();
+            todo3.mark_as_done();
+            store.update(todo3.clone()).await.unwrap();
+            store.delete(todo2.id()).await.unwrap();
+            store.delete(todo4.id()).await.unwrap();
+
+            let mut dump = Vec::new();
+            assert_eq!(store.dump(format, &mut dump).await.unwrap(), 2);
+
+            store.recreate_table().await.unwrap();
+            assert_eq!(store.restore(format, dump.as_slice()).await.unwrap(), 2);
+            assert_eq!(store.list().await.unwrap(), [todo1, todo3]);
+
+            // the ids of the restored rows are not reused
+            let todo5 = store.create(Todo::new("after restore")).await.unwrap();
+            assert_eq!(todo5.id(), 4);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn json_lines() {
+            dump_and_restore(DumpFormat::JsonLines).await;
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn csv() {
+            dump_and_restore(DumpFormat::Csv).await;
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn restore_into_non_empty_table() {
+            let store = get_clean_store().await.unwrap();
+            let todo = store.create(Todo::new("buy milk")).await.unwrap();
+            let mut dump = Vec::new();
+            store.dump(DumpFormat::JsonLines, &mut dump).await.unwrap();
+
+            let res = store.restore(DumpFormat::JsonLines, dump.as_slice()).await;
+            assert!(matches!(res, Err(sqlx::Error::Configuration(_))));
+            assert_eq!(store.list().await.unwrap(), [todo]);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn restore_invalid_dump() {
+            let store = get_clean_store().await.unwrap();
+            let dump = "{\"id\":1,\"description\":\"buy milk\",\"done\":false}\n{\"id\":2}\n";
+
+            let err = store.restore(DumpFormat::JsonLines, dump.as_bytes()).await;
+            assert!(err.unwrap_err().to_string().contains("line 2"));
+            assert_eq!(store.count().await.unwrap(), 0);
+        }
+    };
+}
+
+mod test_dump {
+    use super::*;
+
+    #[cfg(feature = "mysql")]
+    mod mysql {
+        use super::*;
+        use sqlx::{MySql, MySqlPool};
+
+        test_dump!(MySql, {
+            dotenv::dotenv()?;
+            let url = std::env::var("MYSQL_URL").expect("missing MYSQL_URL env");
+            MySqlPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "postgres")]
+    mod postgres {
+        use super::*;
+        use sqlx::{PgPool, Postgres};
+
+        test_dump!(Postgres, {
+            dotenv::dotenv()?;
+            let url = std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env");
+            PgPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod sqlite {
+        use super::*;
+        use sqlx::{Sqlite, SqlitePool};
+
+        test_dump!(Sqlite, { SqlitePool::connect(":memory:").await? });
+    }
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "factory")]
+
+use miniorm::prelude::*;
+use serial_test::serial;
+use sqlx::{types::chrono::NaiveDate, FromRow};
+use std::error::Error;
+
+#[derive(Debug, Clone, PartialEq, FromRow, Entity, Factory)]
+struct Product {
+    #[column(VARCHAR(12) NOT NULL)]
+    name: String,
+
+    #[column(VARCHAR(20) NOT NULL)]
+    #[factory(sequence = "SKU-{n:04}")]
+    sku: String,
+
+    #[column(TEXT)]
+    description: Option<String>,
+
+    #[column(SMALLINT NOT NULL)]
+    stock: i16,
+
+    #[column(DOUBLE PRECISION NOT NULL)]
+    price: f64,
+
+    #[column(BOOLEAN NOT NULL)]
+    #[factory(value = true)]
+    available: bool,
+
+    #[column(DATE NOT NULL)]
+    released: NaiveDate,
+
+    #[sqlx(json)]
+    #[postgres(JSONB NOT NULL)]
+    #[sqlite(TEXT NOT NULL)]
+    #[mysql(JSON NOT NULL)]
+    tags: Vec<String>,
+
+    #[sqlx(skip)]
+    cached: Option<String>,
+}
+
+#[macro_export]
+macro_rules! test_factory {
+    ($backend: ty, $db: block) => {
+        async fn get_clean_store() -> Result<Store<$backend, Product>, Box<dyn Error>> {
+            let pool = $db;
+            let store = Store::new(pool);
+            store.recreate_table().await?;
+            Ok(store)
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn create_many() {
+            let store = get_clean_store().await.unwrap();
+            let created = Product::factory().create_many(&store, 50).await.unwrap();
+            assert_eq!(store.list().await.unwrap(), created);
+            assert_eq!(created[0].sku, "SKU-0001");
+            assert_eq!(created[49].sku, "SKU-0050");
+            assert!(created.iter().all(|product| product.available));
+            assert!(created.iter().all(|product| product.cached.is_none()));
+            assert!(created.iter().all(|product| product.tags.is_empty()));
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn overrides() {
+            let store = get_clean_store().await.unwrap();
+            let mut products = Product::factory()
+                .with(|product| product.description = None)
+                .sequence(|product, n| product.stock = n as i16 * 10);
+            products.create_many(&store, 2).await.unwrap();
+            let product = products.create(&store).await.unwrap();
+            assert_eq!(product.id(), 3);
+            assert_eq!(product.stock, 30);
+            assert_eq!(product.description, None);
+        }
+    };
+}
+
+mod test_factory {
+    use super::*;
+
+    #[cfg(feature = "mysql")]
+    mod mysql {
+        use super::*;
+        use sqlx::{MySql, MySqlPool};
+
+        test_factory!(MySql, {
+            dotenv::dotenv()?;
+            let url = std::env::var("MYSQL_URL").expect("missing MYSQL_URL env");
+            MySqlPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "postgres")]
+    mod postgres {
+        use super::*;
+        use sqlx::{PgPool, Postgres};
+
+        test_factory!(Postgres, {
+            dotenv::dotenv()?;
+            let url = std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env");
+            PgPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod sqlite {
+        use super::*;
+        use sqlx::{Sqlite, SqlitePool};
+
+        test_factory!(Sqlite, { SqlitePool::connect(":memory:").await? });
+    }
+
+    mod memory {
+        use super::*;
+        use miniorm::MemoryStore;
+
+        #[tokio::test]
+        async fn create() {
+            let store = MemoryStore::new();
+            let product = Product::factory().create(&store).await.unwrap();
+            assert_eq!(store.read(product.id()).await.unwrap(), product);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+#![cfg(feature = "fixtures")]
+mod common;
+
+use common::Todo;
+use miniorm::{prelude::*, FixtureFormat, Fixtures};
+use serde::{Deserialize, Serialize};
+use serial_test::serial;
+use sqlx::FromRow;
+use std::error::Error;
+
+#[derive(Debug, Clone, Eq, PartialEq, FromRow, Entity, Serialize, Deserialize)]
+struct Comment {
+    #[column(BIGINT NOT NULL)]
+    todo_id: i64,
+
+    #[column(TEXT NOT NULL)]
+    text: String,
+}
+
+impl Comment {
+    fn new(todo_id: i64, text: &str) -> Self {
+        let text = text.into();
+        Self { todo_id, text }
+    }
+}
+
+#[macro_export]
+macro_rules! test_fixtures {
+    ($backend: ty, $db: block) => {
+        async fn get_clean_stores(
+        ) -> Result<(Fixtures, Store<$backend, Todo>, Store<$backend, Comment>), Box<dyn Error>> {
+            let pool = $db;
+            let todos = Store::new(pool.clone());
+            let comments = Store::new(pool);
+            let fixtures = Fixtures::new()
+                .with_store(todos.clone())
+                .with_store(comments.clone());
+            fixtures.reset().await?;
+            Ok((fixtures, todos, comments))
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn load() {
+            let (fixtures, todos, comments) = get_clean_stores().await.unwrap();
+            let ids = fixtures.load("tests/fixtures/todos.yaml").await.unwrap();
+            assert_eq!(ids.len(), 2);
+            assert_eq!(fixtures.id("laundry"), Some(1));
+            assert_eq!(fixtures.id("dishes"), Some(2));
+
+            let mut dishes = Todo::new("wash the dishes");
+            dishes.mark_as_done();
+            assert_eq!(todos.read(2).await.unwrap(), WithId::new(dishes, 2));
+            assert_eq!(
+                comments.list().await.unwrap(),
+                [
+                    WithId::new(Comment::new(1, "@home"), 1),
+                    WithId::new(Comment::new(2, "the blue plate is chipped"), 2),
+                ]
+            );
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn load_several_files() {
+            let (fixtures, todos, comments) = get_clean_stores().await.unwrap();
+            fixtures.load("tests/fixtures/todos.yaml").await.unwrap();
+            fixtures
+                .load("tests/fixtures/more_todos.toml")
+                .await
+                .unwrap();
+            fixtures
+                .load("tests/fixtures/more_todos.json")
+                .await
+                .unwrap();
+            assert_eq!(fixtures.id("dog"), Some(3));
+            assert_eq!(fixtures.id("groceries"), Some(4));
+            assert_eq!(todos.count().await.unwrap(), 4);
+
+            let texts = comments.list().await.unwrap();
+            let texts = texts.iter().map(|c| (c.todo_id, c.text.as_str()));
+            assert_eq!(
+                texts.collect::<Vec<_>>(),
+                [
+                    (1, "@home"),
+                    (2, "the blue plate is chipped"),
+                    (3, "before dinner"),
+                    (1, "whites only"),
+                    (4, "no sugar"),
+                ]
+            );
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn reset() {
+            let (fixtures, todos, comments) = get_clean_stores().await.unwrap();
+            fixtures.load("tests/fixtures/todos.yaml").await.unwrap();
+            fixtures.reset().await.unwrap();
+            assert_eq!(todos.count().await.unwrap(), 0);
+            assert_eq!(comments.count().await.unwrap(), 0);
+            assert_eq!(fixtures.id("laundry"), None);
+
+            // the ids start from 1 again
+            let ids = fixtures.load("tests/fixtures/todos.yaml").await.unwrap();
+            assert_eq!(ids["laundry"], 1);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn invalid_fixtures() {
+            let (fixtures, todos, _) = get_clean_stores().await.unwrap();
+            for (yaml, error) in [
+                ("unknown: [{}]", "unknown table 'unknown'"),
+                (
+                    "comment: [{todo_id: '@nope', text: a}]",
+                    "unknown record '@nope'",
+                ),
+                (
+                    "todo: {a: {description: a, done: false}, a: {description: b, done: false}}",
+                    "duplicate",
+                ),
+                (
+                    "comment: {a: {todo_id: '@b', text: a}, b: {todo_id: '@a', text: b}}",
+                    "circular references between a, b",
+                ),
+                ("todo: [{description: a}]", "invalid record of 'todo'"),
+            ] {
+                let err = fixtures.load_str(FixtureFormat::Yaml, yaml).await;
+                let err = err.unwrap_err().to_string();
+                assert!(err.contains(error), "{err}");
+            }
+            assert_eq!(todos.count().await.unwrap(), 0);
+            assert!(fixtures.load("tests/fixtures/todos.txt").await.is_err());
+        }
+    };
+}
+
+mod test_fixtures {
+    use super::*;
+
+    #[cfg(feature = "mysql")]
+    mod mysql {
+        use super::*;
+        use sqlx::{MySql, MySqlPool};
+
+        test_fixtures!(MySql, {
+            dotenv::dotenv()?;
+            let url = std::env::var("MYSQL_URL").expect("missing MYSQL_URL env");
+            MySqlPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "postgres")]
+    mod postgres {
+        use super::*;
+        use sqlx::{PgPool, Postgres};
+
+        test_fixtures!(Postgres, {
+            dotenv::dotenv()?;
+            let url = std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env");
+            PgPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod sqlite {
+        use super::*;
+        use sqlx::{Sqlite, SqlitePool};
+
+        test_fixtures!(Sqlite, { SqlitePool::connect(":memory:").await? });
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(all(feature = "prometheus", feature = "sqlite"))]
+mod common;
+
+use axum::{http::header, Router};
+use axum_test::TestServer;
+use common::Todo;
+use miniorm::{prelude::*, Metrics};
+use sqlx::{Sqlite, SqlitePool};
+use std::sync::OnceLock;
+
+/// The global recorder, shared by all the tests.
+fn metrics() -> Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(|| Metrics::install().unwrap()).clone()
+}
+
+/// Returns the value of the sample of a metric having all the provided labels.
+fn sample(metrics: &Metrics, name: &str, labels: &[&str]) -> f64 {
+    metrics
+        .render()
+        .lines()
+        .filter(|line| line.starts_with(&format!("{name}{{")))
+        .find(|line| labels.iter().all(|label| line.contains(label)))
+        .and_then(|line| line.rsplit(' ').next())
+        .map_or(0.0, |value| value.parse().unwrap())
+}
+
+async fn get_clean_store() -> Store<Sqlite, Todo> {
+    let db = SqlitePool::connect(":memory:").await.unwrap();
+    let store = Store::new(db);
+    store.recreate_table().await.unwrap();
+    store
+}
+
+#[tokio::test]
+async fn operations_are_counted() {
+    let metrics = metrics();
+    let store = get_clean_store().await;
+    let table = "table=\"todo\"";
+    let count = |operation: &str| {
+        let operation = format!("operation=\"{operation}\"");
+        sample(&metrics, "miniorm_operations_total", &[table, &operation])
+    };
+    let (creates, reads) = (count("create"), count("read"));
+
+    let todo = store.create(Todo::new("buy milk")).await.unwrap();
+    store.read(todo.id()).await.unwrap();
+    store.read(todo.id() + 1).await.unwrap_err();
+
+    assert_eq!(count("create"), creates + 1.0);
+    assert_eq!(count("read"), reads + 2.0);
+
+    let labels = [table, "operation=\"read\"", "error=\"row_not_found\""];
+    assert!(sample(&metrics, "miniorm_operation_errors_total", &labels) >= 1.0);
+
+    let labels = [table, "operation=\"create\"", "le=\"+Inf\""];
+    assert!(
+        sample(
+            &metrics,
+            "miniorm_operation_duration_seconds_bucket",
+            &labels
+        ) >= 1.0
+    );
+}
+
+#[tokio::test]
+async fn metrics_are_served() {
+    let metrics = metrics();
+    let store = get_clean_store().await;
+    store.list().await.unwrap();
+
+    let app = Router::new()
+        .nest("/todos", store.into_axum_router())
+        .merge(metrics.into_axum_router());
+    let server = TestServer::new(app).unwrap();
+    server.get("/todos").await.assert_status_ok();
+
+    let response = server.get("/metrics").await;
+    response.assert_status_ok();
+    assert!(response
+        .header(header::CONTENT_TYPE)
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+    let text = response.text();
+    assert!(text.contains("# TYPE miniorm_operations_total counter"));
+    assert!(text.contains("# TYPE miniorm_operation_duration_seconds histogram"));
+    assert!(text.contains("operation=\"list\""));
+}
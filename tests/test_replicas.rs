@@ -0,0 +1,86 @@
+#![cfg(feature = "sqlite")]
+mod common;
+
+use common::Todo;
+use miniorm::{prelude::*, ReplicaRouting};
+use sqlx::{Sqlite, SqlitePool};
+use std::time::Duration;
+
+/// Returns a pool on a new database holding a single todo, so that each
+/// database can be told apart from the others.
+async fn database(description: &str) -> SqlitePool {
+    let pool = SqlitePool::connect(":memory:").await.unwrap();
+    let store = Store::<_, Todo>::new(pool.clone());
+    store.recreate_table().await.unwrap();
+    store.create(Todo::new(description)).await.unwrap();
+
+    // the connections are released in the background
+    while pool.num_idle() < pool.size() as usize {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    pool
+}
+
+/// Returns the description of the only todo of the database serving a read.
+async fn read(store: &Store<Sqlite, Todo>) -> String {
+    store.read(1).await.unwrap().description().into()
+}
+
+async fn get_store(routing: ReplicaRouting) -> (SqlitePool, Vec<SqlitePool>, Store<Sqlite, Todo>) {
+    let primary = database("primary").await;
+    let replicas = vec![database("replica 1").await, database("replica 2").await];
+    let store = Store::new(primary.clone()).with_replicas(replicas.clone(), routing);
+    (primary, replicas, store)
+}
+
+#[tokio::test]
+async fn reads_from_primary_without_replicas() {
+    let store = Store::new(database("primary").await);
+    assert_eq!(read(&store).await, "primary");
+}
+
+#[tokio::test]
+async fn round_robin() {
+    let (_, _, store) = get_store(ReplicaRouting::RoundRobin).await;
+    assert_eq!(read(&store).await, "replica 1");
+    assert_eq!(read(&store).await, "replica 2");
+    assert_eq!(read(&store).await, "replica 1");
+    assert_eq!(store.count().await.unwrap(), 1);
+    assert_eq!(store.list().await.unwrap()[0].description(), "replica 1");
+}
+
+#[tokio::test]
+async fn least_busy() {
+    let (_, replicas, store) = get_store(ReplicaRouting::LeastBusy).await;
+    let busy = replicas[0].acquire().await.unwrap();
+    assert_eq!(read(&store).await, "replica 2");
+    assert_eq!(read(&store).await, "replica 2");
+    drop(busy);
+}
+
+#[tokio::test]
+async fn writes_go_to_primary() {
+    let (primary, _, store) = get_store(ReplicaRouting::RoundRobin).await;
+    let todo = store.create(Todo::new("new")).await.unwrap();
+    assert_eq!(todo.id(), 2);
+
+    let primary = Store::<_, Todo>::new(primary);
+    assert_eq!(primary.read(2).await.unwrap(), todo);
+    assert!(store.read(2).await.is_err());
+}
+
+#[tokio::test]
+async fn session_reads_its_own_writes() {
+    let (_, _, store) = get_store(ReplicaRouting::RoundRobin).await;
+    let session = store.session();
+    assert_eq!(read(&session).await, "replica 1");
+
+    let todo = session.create(Todo::new("new")).await.unwrap();
+    assert_eq!(read(&session).await, "primary");
+    assert_eq!(read(&session.clone()).await, "primary");
+    assert_eq!(session.read(todo.id()).await.unwrap(), todo);
+
+    // neither the store nor its other sessions are pinned to the primary
+    assert_eq!(read(&store).await, "replica 2");
+    assert_eq!(read(&store.session()).await, "replica 1");
+}
@@ -1,17 +1,25 @@
 mod common;
 
-use axum::http::StatusCode;
+use axum::{
+    body::Body,
+    http::{
+        self,
+        header::{ACCEPT, ALLOW, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, LOCATION},
+        HeaderValue, StatusCode,
+    },
+};
 use axum_test::TestServer;
 use common::Todo;
-use miniorm::prelude::*;
+use miniorm::{prelude::*, OpenApi, Problem};
 use serial_test::serial;
 use std::error::Error;
+use tower::Service;
 
 #[macro_export]
 macro_rules! test_rest {
     ($db: block) => {
         async fn get_store_with_sample_data(
-        ) -> Result<impl Clone + Crud<Todo> + IntoAxumRouter, Box<dyn Error>> {
+        ) -> Result<impl Clone + Crud<Todo> + IntoAxumRouter + IntoOpenApi, Box<dyn Error>> {
             let pool = $db;
             let store = Store::new(pool);
             store.recreate_table().await?;
@@ -22,6 +30,15 @@ macro_rules! test_rest {
             Ok(store)
         }
 
+        async fn get_clean_router_with_problems(
+            problems: impl Fn(&sqlx::Error) -> Problem + Send + Sync + 'static,
+        ) -> Result<axum::Router, Box<dyn Error>> {
+            let pool = $db;
+            let store = Store::<_, Todo>::new(pool);
+            store.recreate_table().await?;
+            Ok(store.into_axum_router_with_problems(problems))
+        }
+
         #[cfg_attr(not(feature = "integration_tests"), ignore)]
         #[serial]
         #[tokio::test]
@@ -59,6 +76,67 @@ macro_rules! test_rest {
             assert_eq!(actual, expected);
         }
 
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn list_filtered_and_sorted() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let mut todo = store.read(2).await.unwrap();
+            todo.mark_as_done();
+            store.update(todo).await.unwrap();
+
+            let response = server
+                .get("/")
+                .add_query_param("done", false)
+                .add_query_param("description__like", "%the%")
+                .add_query_param("sort", "-description")
+                .await;
+            let ids: Vec<_> = response
+                .json::<Vec<WithId<Todo>>>()
+                .iter()
+                .map(|todo| todo.id())
+                .collect();
+            assert_eq!(ids, [3, 1]);
+            assert_eq!(response.header("x-total-count"), "2");
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn list_paginated() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let response = server
+                .get("/")
+                .add_query_param("limit", 2)
+                .add_query_param("offset", 1)
+                .await;
+            let actual = response.json::<Vec<WithId<Todo>>>();
+            let expected = store.list().await.unwrap()[1..3].to_vec();
+            assert_eq!(actual, expected);
+            assert_eq!(response.header("x-total-count"), "4");
+            let link = response.header("link");
+            let link = link.to_str().unwrap();
+            assert!(link.contains("</?limit=2&offset=3>; rel=\"next\""));
+            assert!(link.contains("</?limit=2&offset=0>; rel=\"prev\""));
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn list_bad_request() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            for (key, value) in [("unknown", "1"), ("sort", "-unknown"), ("limit", "x")] {
+                server
+                    .get("/")
+                    .add_query_param(key, value)
+                    .await
+                    .assert_status(StatusCode::BAD_REQUEST);
+            }
+        }
+
         #[cfg_attr(not(feature = "integration_tests"), ignore)]
         #[serial]
         #[tokio::test]
@@ -66,11 +144,10 @@ macro_rules! test_rest {
             let store = get_store_with_sample_data().await.unwrap();
             let server = TestServer::new(store.clone().into_axum_router()).unwrap();
             let before = store.count().await.unwrap();
-            let actual = server
-                .post("/")
-                .json(&Todo::new("new one"))
-                .await
-                .json::<WithId<Todo>>();
+            let response = server.post("/").json(&Todo::new("new one")).await;
+            response.assert_status(StatusCode::CREATED);
+            let actual = response.json::<WithId<Todo>>();
+            assert_eq!(response.header(LOCATION), format!("/{}", actual.id()));
             let after = store.count().await.unwrap();
             let expected = store.read(actual.id()).await.unwrap();
             assert_eq!(before + 1, after);
@@ -115,6 +192,425 @@ macro_rules! test_rest {
             assert_eq!(actual, expected);
         }
 
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn patch_merge() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let before = store.count().await.unwrap();
+            let actual = server
+                .patch("/3")
+                .bytes(r#"{"done": true}"#.into())
+                .content_type("application/merge-patch+json")
+                .await
+                .json::<WithId<Todo>>();
+            let after = store.count().await.unwrap();
+            let expected = store.read(3).await.unwrap();
+            assert_eq!(before, after);
+            assert_eq!(actual, expected);
+            assert!(expected.is_done());
+            assert_eq!(expected.description(), "go walk the dog");
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn patch_json_patch() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let patch = r#"[
+                {"op": "test", "path": "/done", "value": false},
+                {"op": "replace", "path": "/description", "value": "walk the cat"}
+            ]"#;
+            let actual = server
+                .patch("/3")
+                .bytes(patch.into())
+                .content_type("application/json-patch+json")
+                .await
+                .json::<WithId<Todo>>();
+            let expected = store.read(3).await.unwrap();
+            assert_eq!(actual, expected);
+            assert_eq!(expected.description(), "walk the cat");
+
+            let patch = r#"[{"op": "test", "path": "/description", "value": "nope"}]"#;
+            server
+                .patch("/3")
+                .bytes(patch.into())
+                .content_type("application/json-patch+json")
+                .await
+                .assert_status(StatusCode::CONFLICT);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn patch_errors() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let expected = store.read(3).await.unwrap();
+            let patch = |body: &'static str, content_type: &str| {
+                server.patch("/3").bytes(body.into()).content_type(content_type)
+            };
+            patch(r#"{"nope": 1}"#, "application/merge-patch+json")
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+            patch(r#"{"done": "yes"}"#, "application/merge-patch+json")
+                .await
+                .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+            patch(r#"{"done": true}"#, "text/plain")
+                .await
+                .assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            server
+                .patch("/23")
+                .json(&serde_json::json!({"done": true}))
+                .await
+                .assert_status(StatusCode::NOT_FOUND);
+            assert_eq!(store.read(3).await.unwrap(), expected);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn openapi() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let openapi = OpenApi::new("todo app", "1.0.0").merge(store.openapi("/todos"));
+            let app = axum::Router::new()
+                .nest("/todos", store.clone().into_axum_router())
+                .merge(openapi.into_axum_router());
+            let server = TestServer::new(app).unwrap();
+            let document = server.get("/openapi.json").await.json::<serde_json::Value>();
+            assert_eq!(document["openapi"], "3.1.0");
+            assert_eq!(document["info"]["title"], "todo app");
+            let todo = &document["components"]["schemas"]["Todo"];
+            assert_eq!(todo["properties"]["description"]["type"], "string");
+            assert_eq!(todo["properties"]["done"]["type"], "boolean");
+            assert_eq!(todo["required"], serde_json::json!(["description", "done"]));
+            assert!(document["paths"]["/todos"]["get"].is_object());
+            assert!(document["paths"]["/todos/{id}"]["patch"].is_object());
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn etag_not_modified() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let response = server.get("/3").await;
+            let etag = response.header(ETAG);
+            let response = server.get("/3").add_header(IF_NONE_MATCH, etag.clone()).await;
+            response.assert_status(StatusCode::NOT_MODIFIED);
+            assert!(response.as_bytes().is_empty());
+
+            let mut todo = store.read(3).await.unwrap();
+            todo.mark_as_done();
+            store.update(todo).await.unwrap();
+            let response = server.get("/3").add_header(IF_NONE_MATCH, etag.clone()).await;
+            response.assert_status_ok();
+            assert_ne!(response.header(ETAG), etag);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn etag_precondition_failed() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let etag = server.get("/3").await.header(ETAG);
+            let mut todo = store.read(3).await.unwrap();
+            todo.mark_as_done();
+
+            let response = server
+                .put("/3")
+                .add_header(IF_MATCH, etag.clone())
+                .json(todo.inner())
+                .await;
+            response.assert_status_ok();
+            let new_etag = response.header(ETAG);
+            assert_ne!(new_etag, etag);
+
+            // the entity was modified in the meantime
+            server
+                .put("/3")
+                .add_header(IF_MATCH, etag.clone())
+                .json(todo.inner())
+                .await
+                .assert_status(StatusCode::PRECONDITION_FAILED);
+            server
+                .patch("/3")
+                .add_header(IF_MATCH, etag.clone())
+                .json(&serde_json::json!({"description": "nope"}))
+                .await
+                .assert_status(StatusCode::PRECONDITION_FAILED);
+            server
+                .delete("/3")
+                .add_header(IF_MATCH, etag)
+                .await
+                .assert_status(StatusCode::PRECONDITION_FAILED);
+            assert_eq!(store.read(3).await.unwrap(), todo);
+
+            // a weak tag never matches `If-Match`
+            let weak_etag = format!("W/{}", new_etag.to_str().unwrap());
+            server
+                .delete("/3")
+                .add_header(IF_MATCH, HeaderValue::from_str(&weak_etag).unwrap())
+                .await
+                .assert_status(StatusCode::PRECONDITION_FAILED);
+
+            server
+                .delete("/3")
+                .add_header(IF_MATCH, new_etag)
+                .await
+                .assert_status(StatusCode::NO_CONTENT);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn problem_json() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let response = server.get("/23").await;
+            response.assert_status(StatusCode::NOT_FOUND);
+            assert_eq!(response.header(CONTENT_TYPE), "application/problem+json");
+            let problem = response.json::<Problem>();
+            assert_eq!(problem, Problem::new(StatusCode::NOT_FOUND).with_detail("entity not found"));
+
+            let response = server.get("/nope").await;
+            response.assert_status(StatusCode::BAD_REQUEST);
+            assert_eq!(response.json::<Problem>().status, 400);
+
+            let response = server.post("/").json(&serde_json::json!({"done": 1})).await;
+            response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+            let problem = response.json::<Problem>();
+            assert_eq!(problem.status, 422);
+            assert!(problem.detail.is_some());
+
+            let response = server.get("/").add_query_param("nope", 1).await;
+            response.assert_status(StatusCode::BAD_REQUEST);
+            let problem = response.json::<Problem>();
+            assert_eq!(problem.detail.as_deref(), Some("unknown column 'nope'"));
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn problem_json_custom_mapping() {
+            let router = get_clean_router_with_problems(|err| {
+                Problem::from_sqlx_error(err).with_type("https://example.com/problems/todo")
+            })
+            .await
+            .unwrap();
+            let server = TestServer::new(router).unwrap();
+            let response = server.delete("/23").await;
+            response.assert_status(StatusCode::NOT_FOUND);
+            let problem = response.json::<Problem>();
+            assert_eq!(problem.r#type, "https://example.com/problems/todo");
+            assert_eq!(problem.title, "Not Found");
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn bulk() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let todos = vec![Todo::new("buy milk"), Todo::new("buy bread")];
+            let created = server
+                .post("/_bulk")
+                .json(&todos)
+                .await
+                .json::<Vec<WithId<Todo>>>();
+            assert_eq!(created.len(), 2);
+            assert_eq!(created[0].inner(), &todos[0]);
+            assert_eq!(created[1].inner(), &todos[1]);
+            assert_eq!(store.count().await.unwrap(), 6);
+
+            let mut updated = created.clone();
+            updated.iter_mut().for_each(|todo| todo.mark_as_done());
+            let actual = server
+                .put("/_bulk")
+                .json(&updated)
+                .await
+                .json::<Vec<WithId<Todo>>>();
+            assert_eq!(actual, updated);
+            assert_eq!(store.read(created[0].id()).await.unwrap(), updated[0]);
+
+            let ids = [created[0].id(), created[1].id()];
+            server
+                .delete("/_bulk")
+                .json(&ids)
+                .await
+                .assert_status(StatusCode::NO_CONTENT);
+            assert_eq!(store.count().await.unwrap(), 4);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn bulk_all_or_nothing() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            server
+                .delete("/_bulk")
+                .json(&[1, 2, 23])
+                .await
+                .assert_status(StatusCode::NOT_FOUND);
+            assert_eq!(store.count().await.unwrap(), 4);
+
+            let todos = serde_json::json!([{"description": "buy milk", "done": false}, {}]);
+            server
+                .post("/_bulk")
+                .json(&todos)
+                .await
+                .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(store.count().await.unwrap(), 4);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn export_ndjson() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let response = server
+                .get("/")
+                .add_header(ACCEPT, HeaderValue::from_static("application/x-ndjson"))
+                .await;
+            response.assert_status_ok();
+            assert_eq!(response.header(CONTENT_TYPE), "application/x-ndjson");
+            let actual = response
+                .text()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect::<Vec<WithId<Todo>>>();
+            assert_eq!(actual, store.list().await.unwrap());
+
+            let response = server
+                .get("/")
+                .add_query_param("description__like", "%dishes")
+                .add_header(ACCEPT, HeaderValue::from_static("application/x-ndjson"))
+                .await;
+            assert_eq!(response.text().lines().count(), 1);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn export_csv() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let response = server
+                .get("/")
+                .add_header(ACCEPT, HeaderValue::from_static("text/csv"))
+                .await;
+            assert_eq!(response.header(CONTENT_TYPE), "text/csv");
+            let expected = "id,description,done\n\
+                1,do the laundry,false\n\
+                2,wash the dishes,false\n\
+                3,go walk the dog,false\n\
+                4,groceries,false\n";
+            assert_eq!(response.text(), expected);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn import() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let export = server
+                .get("/")
+                .add_header(ACCEPT, HeaderValue::from_static("application/x-ndjson"))
+                .await
+                .text();
+            let response = server
+                .post("/_import")
+                .bytes(export.into())
+                .content_type("application/x-ndjson")
+                .await;
+            response.assert_status_ok();
+            assert_eq!(response.json::<serde_json::Value>()["created"], 4);
+            assert_eq!(store.count().await.unwrap(), 8);
+
+            let csv = "description,done\n\"buy milk, bread\",true\n\"multi\nline\",false";
+            let response = server
+                .post("/_import")
+                .bytes(csv.into())
+                .content_type("text/csv")
+                .await;
+            assert_eq!(response.json::<serde_json::Value>()["created"], 2);
+            let mut expected = Todo::new("buy milk, bread");
+            expected.mark_as_done();
+            assert_eq!(store.read(9).await.unwrap().inner(), &expected);
+            assert_eq!(store.read(10).await.unwrap().description(), "multi\nline");
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn import_errors() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            server
+                .post("/_import")
+                .json(&[Todo::new("nope")])
+                .await
+                .assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            let response = server
+                .post("/_import")
+                .bytes("description,done\nbuy milk,maybe\n".into())
+                .content_type("text/csv")
+                .await;
+            response.assert_status(StatusCode::BAD_REQUEST);
+            let problem = response.json::<Problem>();
+            assert!(problem.detail.unwrap().starts_with("line 2:"));
+            server
+                .post("/_import")
+                .bytes("{\"description\": \"buy milk\"}\n".into())
+                .content_type("application/x-ndjson")
+                .await
+                .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(store.count().await.unwrap(), 4);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn head() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let mut router = store.clone().into_axum_router();
+            let mut head = |path: &str| {
+                let request = http::Request::head(path).body(Body::empty()).unwrap();
+                router.call(request)
+            };
+            let response = head("/3").await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(response.headers().contains_key(ETAG));
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await;
+            assert!(body.unwrap().is_empty());
+            let response = head("/23").await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn method_not_allowed() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            let response = server.patch("/").await;
+            response.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+            assert_eq!(response.header(CONTENT_TYPE), "application/problem+json");
+            let allow = response.header(ALLOW);
+            let allow = allow.to_str().unwrap();
+            for method in ["GET", "HEAD", "POST", "PUT", "DELETE"] {
+                assert!(allow.contains(method), "{allow}");
+            }
+            let problem = response.json::<Problem>();
+            assert_eq!(problem.status, 405);
+        }
+
         #[cfg_attr(not(feature = "integration_tests"), ignore)]
         #[serial]
         #[tokio::test]
@@ -122,7 +618,10 @@ macro_rules! test_rest {
             let store = get_store_with_sample_data().await.unwrap();
             let server = TestServer::new(store.clone().into_axum_router()).unwrap();
             let before = store.count().await.unwrap();
-            server.delete("/3").await.assert_status_ok();
+            server
+                .delete("/3")
+                .await
+                .assert_status(StatusCode::NO_CONTENT);
             let after = store.count().await.unwrap();
             assert!(matches!(store.read(3).await, Err(sqlx::Error::RowNotFound)));
             assert_eq!(before, after + 1);
@@ -147,7 +646,10 @@ macro_rules! test_rest {
             let store = get_store_with_sample_data().await.unwrap();
             let server = TestServer::new(store.clone().into_axum_router()).unwrap();
             let before = store.count().await.unwrap();
-            server.delete("/").await.assert_status_ok();
+            server
+                .delete("/")
+                .await
+                .assert_status(StatusCode::NO_CONTENT);
             let after = store.count().await.unwrap();
             assert_eq!(before, 4);
             assert_eq!(after, 0);
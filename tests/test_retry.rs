@@ -0,0 +1,120 @@
+#![cfg(feature = "retry")]
+mod common;
+
+use common::Todo;
+use miniorm::{prelude::*, RetryPolicy};
+use serial_test::serial;
+use std::{error::Error, time::Duration};
+
+#[macro_export]
+macro_rules! test_retry {
+    ($backend: ty, $db: block) => {
+        /// Returns a store on a pool whose connections fail as soon as they
+        /// wait for a lock, along with that pool.
+        async fn get_clean_store(
+        ) -> Result<(sqlx::Pool<$backend>, Store<$backend, Todo>), Box<dyn Error>> {
+            let pool = $db;
+            let store = Store::new(pool.clone());
+            store.recreate_table().await?;
+            Ok((pool, store))
+        }
+
+        /// Starts a transaction holding a lock on all the todos.
+        async fn lock(pool: &sqlx::Pool<$backend>) -> sqlx::Transaction<'static, $backend> {
+            let mut tx = pool.begin().await.unwrap();
+            sqlx::query("UPDATE todo SET done = true")
+                .execute(&mut *tx)
+                .await
+                .unwrap();
+            tx
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn not_retried_by_default() {
+            let (pool, store) = get_clean_store().await.unwrap();
+            let todo = store.create(Todo::new("buy milk")).await.unwrap();
+
+            let tx = lock(&pool).await;
+            let err = store.update(todo).await.unwrap_err();
+            assert!(matches!(err, sqlx::Error::Database(_)), "{err:?}");
+            tx.rollback().await.unwrap();
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn retried_until_unlocked() {
+            let (pool, store) = get_clean_store().await.unwrap();
+            let retry = RetryPolicy::new(50)
+                .initial_backoff(Duration::from_millis(10))
+                .max_backoff(Duration::from_millis(50));
+            let store = store.with_retry(retry);
+            let todo = store.create(Todo::new("buy milk")).await.unwrap();
+
+            let tx = lock(&pool).await;
+            let unlock = tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                tx.commit().await.unwrap();
+            });
+            store.update(todo.clone()).await.unwrap();
+            unlock.await.unwrap();
+
+            // the update was applied after the transaction holding the lock
+            assert!(!store.read(todo.id()).await.unwrap().is_done());
+        }
+    };
+}
+
+mod test_retry {
+    use super::*;
+
+    #[cfg(feature = "mysql")]
+    mod mysql {
+        use super::*;
+        use sqlx::{mysql::MySqlPoolOptions, Executor, MySql};
+
+        test_retry!(MySql, {
+            dotenv::dotenv()?;
+            let url = std::env::var("MYSQL_URL").expect("missing MYSQL_URL env");
+            MySqlPoolOptions::new()
+                .after_connect(|conn, _| {
+                    Box::pin(async move {
+                        conn.execute("SET innodb_lock_wait_timeout = 1").await?;
+                        Ok(())
+                    })
+                })
+                .connect(&url)
+                .await?
+        });
+    }
+
+    #[cfg(feature = "postgres")]
+    mod postgres {
+        use super::*;
+        use sqlx::{postgres::PgConnectOptions, PgPool, Postgres};
+        use std::str::FromStr;
+
+        test_retry!(Postgres, {
+            dotenv::dotenv()?;
+            let url = std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env");
+            let options = PgConnectOptions::from_str(&url)?.options([("lock_timeout", "10")]);
+            PgPool::connect_with(options).await?
+        });
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod sqlite {
+        use super::*;
+        use sqlx::{sqlite::SqliteConnectOptions, Sqlite, SqlitePool};
+
+        test_retry!(Sqlite, {
+            let options = SqliteConnectOptions::new()
+                .filename(std::env::temp_dir().join("miniorm_test_retry.db"))
+                .create_if_missing(true)
+                .busy_timeout(Duration::ZERO);
+            SqlitePool::connect_with(options).await?
+        });
+    }
+}
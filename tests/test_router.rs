@@ -0,0 +1,289 @@
+mod common;
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{
+        self,
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderValue, StatusCode,
+    },
+    middleware::{self, Next},
+    response::Response,
+    Router,
+};
+use axum_test::TestServer;
+use common::Todo;
+use futures::{stream::BoxStream, StreamExt};
+use miniorm::{prelude::*, Operation};
+use serial_test::serial;
+use std::{error::Error, time::Duration};
+use tower::Service;
+
+async fn authorized(request: Request, next: Next) -> Result<Response, StatusCode> {
+    match request.headers().get(AUTHORIZATION) {
+        Some(token) if token == "Bearer secret" => Ok(next.run(request).await),
+        _ => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+/// Subscribes to `GET /events` and returns the body of the response.
+async fn subscribe(router: &mut Router) -> BoxStream<'static, Result<String, axum::Error>> {
+    let request = http::Request::get("/events").body(Body::empty()).unwrap();
+    let response = router.call(request).await.unwrap();
+    assert_eq!(response.headers()[CONTENT_TYPE], "text/event-stream");
+    let events = response.into_body().into_data_stream();
+    events
+        .map(|chunk| chunk.map(|chunk| String::from_utf8_lossy(&chunk).into_owned()))
+        .boxed()
+}
+
+/// Reads the server-sent events until `count` of them were received.
+async fn receive(
+    events: &mut BoxStream<'static, Result<String, axum::Error>>,
+    count: usize,
+) -> String {
+    let mut received = String::new();
+    while received.matches("\n\n").count() < count {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), events.next());
+        received += &chunk.await.unwrap().unwrap().unwrap();
+    }
+    received
+}
+
+#[macro_export]
+macro_rules! test_router {
+    ($backend: ty, $db: block) => {
+        async fn get_store_with_sample_data() -> Result<Store<$backend, Todo>, Box<dyn Error>> {
+            let pool = $db;
+            let store = Store::new(pool);
+            store.recreate_table().await?;
+            store.create(Todo::new("do the laundry")).await?;
+            store.create(Todo::new("wash the dishes")).await?;
+            Ok(store)
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn read_only() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let router = store.clone().router_builder().read_only().build();
+            let server = TestServer::new(router).unwrap();
+            server.get("/").await.assert_status_ok();
+            server.get("/1").await.assert_status_ok();
+            server
+                .post("/")
+                .json(&Todo::new("nope"))
+                .await
+                .assert_status(StatusCode::METHOD_NOT_ALLOWED);
+            server
+                .delete("/")
+                .await
+                .assert_status(StatusCode::METHOD_NOT_ALLOWED);
+            server
+                .delete("/1")
+                .await
+                .assert_status(StatusCode::METHOD_NOT_ALLOWED);
+            assert_eq!(store.count().await.unwrap(), 2);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn disable_delete_all() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let router = store
+                .clone()
+                .router_builder()
+                .disable(Operation::DeleteAll)
+                .build();
+            let server = TestServer::new(router).unwrap();
+            server
+                .delete("/")
+                .await
+                .assert_status(StatusCode::METHOD_NOT_ALLOWED);
+            server
+                .delete("/1")
+                .await
+                .assert_status(StatusCode::NO_CONTENT);
+            assert_eq!(store.count().await.unwrap(), 1);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn only_item_operations() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let router = store
+                .clone()
+                .router_builder()
+                .only([Operation::Read])
+                .build();
+            let server = TestServer::new(router).unwrap();
+            server.get("/").await.assert_status_not_found();
+            server.get("/2").await.assert_status_ok();
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn id_param() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let builder = store.clone().router_builder().id_param("todo_id");
+            let openapi = builder.openapi("/todos");
+            assert!(openapi.as_json()["paths"]["/todos/{todo_id}"].is_object());
+            let server = TestServer::new(builder.build()).unwrap();
+            let actual = server.get("/2").await.json::<WithId<Todo>>();
+            assert_eq!(actual, store.read(2).await.unwrap());
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn layer() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let router = store
+                .clone()
+                .router_builder()
+                .layer(Operation::Delete, middleware::from_fn(authorized))
+                .build();
+            let server = TestServer::new(router).unwrap();
+            server.get("/1").await.assert_status_ok();
+            server
+                .delete("/1")
+                .await
+                .assert_status(StatusCode::UNAUTHORIZED);
+            server
+                .delete("/1")
+                .add_header(AUTHORIZATION, HeaderValue::from_static("Bearer secret"))
+                .await
+                .assert_status(StatusCode::NO_CONTENT);
+            assert_eq!(store.count().await.unwrap(), 1);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn flat_ids() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let builder = store.clone().router_builder().flat_ids();
+            let openapi = builder.openapi("/todos");
+            let schema = &openapi.as_json()["components"]["schemas"]["TodoWithId"];
+            assert!(schema["allOf"].is_array());
+            let server = TestServer::new(builder.build()).unwrap();
+
+            let json = server.get("/2").await.json::<serde_json::Value>();
+            let expected = serde_json::json!({"id": 2, "description": "wash the dishes", "done": false});
+            assert_eq!(json, expected);
+            let Flat(created) = server
+                .post("/")
+                .json(&Todo::new("buy milk"))
+                .await
+                .json::<Flat<Todo>>();
+            assert_eq!(created, store.read(created.id()).await.unwrap());
+
+            let mut updated = created.clone();
+            updated.mark_as_done();
+            let actual = server
+                .put("/")
+                .json(&Flat(updated.clone()))
+                .await
+                .json::<Flat<Todo>>();
+            assert_eq!(actual.0, updated);
+            let actual = server
+                .put("/_bulk")
+                .json(&[Flat(updated.clone())])
+                .await
+                .json::<Vec<Flat<Todo>>>();
+            assert_eq!(actual, [Flat(updated)]);
+            let all = server.get("/").await.json::<Vec<Flat<Todo>>>();
+            assert_eq!(all.len(), 3);
+            server
+                .put("/")
+                .json(&store.read(1).await.unwrap())
+                .await
+                .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn events() {
+            let store = get_store_with_sample_data().await.unwrap();
+            let server = TestServer::new(store.clone().into_axum_router()).unwrap();
+            // served as `GET /:id` unless enabled
+            server
+                .get("/events")
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+
+            let mut router = store
+                .clone()
+                .router_builder()
+                .enable(Operation::Events)
+                .build();
+            let mut events = subscribe(&mut router).await;
+            let server = TestServer::new(router).unwrap();
+            let created = server
+                .post("/")
+                .json(&Todo::new("buy milk"))
+                .await
+                .json::<WithId<Todo>>();
+            server
+                .delete(&format!("/{}", created.id()))
+                .await
+                .assert_status(StatusCode::NO_CONTENT);
+            server
+                .delete("/")
+                .await
+                .assert_status(StatusCode::NO_CONTENT);
+
+            let received = receive(&mut events, 3).await;
+            let created_json = serde_json::to_string(&created).unwrap();
+            let expected = format!(
+                "event: create\ndata: {created_json}\n\n\
+                event: delete\ndata: {{\"id\":{}}}\n\n\
+                event: delete_all\ndata: {{}}\n\n",
+                created.id()
+            );
+            assert_eq!(received, expected);
+        }
+    };
+}
+
+mod test_router {
+    use super::*;
+
+    #[cfg(feature = "mysql")]
+    mod mysql {
+        use super::*;
+        use sqlx::{MySql, MySqlPool};
+
+        test_router!(MySql, {
+            dotenv::dotenv()?;
+            let url = std::env::var("MYSQL_URL").expect("missing MYSQL_URL env");
+            MySqlPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "postgres")]
+    mod postgres {
+        use super::*;
+        use sqlx::{PgPool, Postgres};
+
+        test_router!(Postgres, {
+            dotenv::dotenv()?;
+            let url = std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env");
+            PgPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod sqlite {
+        use super::*;
+        use sqlx::{Sqlite, SqlitePool};
+
+        test_router!(Sqlite, { SqlitePool::connect(":memory:").await? });
+    }
+}
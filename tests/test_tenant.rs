@@ -0,0 +1,142 @@
+mod common;
+
+use common::Todo;
+use miniorm::{prelude::*, FilterOp, ListQuery, TenantStore};
+use serial_test::serial;
+use std::error::Error;
+
+#[macro_export]
+macro_rules! test_tenant {
+    ($backend: ty, $db: block) => {
+        async fn get_clean_store(
+            tenant_id: i64,
+        ) -> Result<TenantStore<$backend, Todo>, Box<dyn Error>> {
+            let pool = $db;
+            let store = Store::new(pool);
+            store.drop_table().await?;
+            let store = store.for_tenant(tenant_id);
+            store.create_table().await?;
+            Ok(store)
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn conformance() {
+            miniorm::testing::run_all(
+                || async { get_clean_store(1).await.unwrap() },
+                |n| Todo::new(format!("todo{n}")),
+            )
+            .await;
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn isolation() {
+            let acme = get_clean_store(1).await.unwrap();
+            let initech = acme.for_tenant(2);
+            let todo1 = acme.create(Todo::new("todo1")).await.unwrap();
+            let todo2 = initech.create(Todo::new("todo2")).await.unwrap();
+
+            assert_eq!(acme.list().await.unwrap(), [todo1]);
+            assert_eq!(initech.read(todo2.id()).await.unwrap(), todo2);
+            assert_eq!(acme.count().await.unwrap(), 1);
+            assert!(matches!(
+                acme.read(todo2.id()).await,
+                Err(sqlx::Error::RowNotFound)
+            ));
+            assert!(matches!(
+                acme.delete(todo2.id()).await,
+                Err(sqlx::Error::RowNotFound)
+            ));
+
+            let mut hijacked = todo2.clone();
+            hijacked.mark_as_done();
+            acme.update(hijacked).await.unwrap();
+            assert_eq!(initech.read(todo2.id()).await.unwrap(), todo2);
+
+            assert_eq!(acme.delete_all().await.unwrap(), 1);
+            assert_eq!(initech.list().await.unwrap(), [todo2]);
+        }
+
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn query() {
+            let acme = get_clean_store(1).await.unwrap();
+            let initech = acme.for_tenant(2);
+            let todo1 = acme.create(Todo::new("buy milk")).await.unwrap();
+            initech.create(Todo::new("buy bread")).await.unwrap();
+            acme.create(Todo::new("walk the dog")).await.unwrap();
+
+            let query = ListQuery::new().filter("description", FilterOp::Like, "buy%");
+            let page = acme.query(&query).await.unwrap();
+            assert_eq!(page.items, [todo1]);
+            assert_eq!(page.total, 1);
+        }
+    };
+}
+
+mod test_tenant {
+    use super::*;
+
+    #[cfg(feature = "mysql")]
+    mod mysql {
+        use super::*;
+        use sqlx::{MySql, MySqlPool};
+
+        test_tenant!(MySql, {
+            dotenv::dotenv()?;
+            let url = std::env::var("MYSQL_URL").expect("missing MYSQL_URL env");
+            MySqlPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "postgres")]
+    mod postgres {
+        use super::*;
+        use sqlx::{PgPool, Postgres};
+
+        test_tenant!(Postgres, {
+            dotenv::dotenv()?;
+            let url = std::env::var("POSTGRES_URL").expect("missing POSTGRES_URL env");
+            PgPool::connect(&url).await?
+        });
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod sqlite {
+        use super::*;
+        use sqlx::{sqlite::SqliteConnectOptions, Sqlite, SqlitePool};
+        use std::{str::FromStr, time::Duration};
+
+        test_tenant!(Sqlite, { SqlitePool::connect(":memory:").await? });
+
+        /// the created rows are committed as soon as `create` returns, and
+        /// are thus visible from other connections
+        #[cfg_attr(not(feature = "integration_tests"), ignore)]
+        #[serial]
+        #[tokio::test]
+        async fn create_commits() {
+            let path = std::env::temp_dir().join("miniorm_test_tenant.db");
+            let url = format!("sqlite://{}?mode=rwc", path.display());
+            let store = Store::new(SqlitePool::connect(&url).await.unwrap());
+            store.drop_table().await.unwrap();
+            let store = store.for_tenant(1);
+            store.create_table().await.unwrap();
+            // fails right away if the database is still locked by `create`
+            let options = SqliteConnectOptions::from_str(&url)
+                .unwrap()
+                .busy_timeout(Duration::ZERO);
+            let other = SqlitePool::connect_with(options).await.unwrap();
+            for n in 1..=10 {
+                store.create(Todo::new(format!("todo{n}"))).await.unwrap();
+                sqlx::query("UPDATE todo SET done = true")
+                    .execute(&other)
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+#![cfg(all(feature = "tracing", feature = "sqlite"))]
+mod common;
+
+use common::Todo;
+use miniorm::prelude::*;
+use sqlx::SqlitePool;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+
+type Fields = HashMap<&'static str, String>;
+
+/// Subscriber keeping the fields of the `miniorm` spans.
+#[derive(Clone, Default)]
+struct Spans(Arc<Mutex<Vec<Fields>>>);
+
+impl Spans {
+    fn take(&self) -> Vec<Fields> {
+        std::mem::take(&mut self.0.lock().unwrap())
+    }
+}
+
+struct Visitor<'a>(&'a mut Fields);
+
+impl Visit for Visitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name(), format!("{value:?}"));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.into());
+    }
+}
+
+impl Subscriber for Spans {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.name() == "miniorm"
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut spans = self.0.lock().unwrap();
+        let mut fields = Fields::new();
+        span.record(&mut Visitor(&mut fields));
+        spans.push(fields);
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = self.0.lock().unwrap();
+        values.record(&mut Visitor(&mut spans[span.into_u64() as usize - 1]));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, _: &Event<'_>) {}
+    fn enter(&self, _: &Id) {}
+    fn exit(&self, _: &Id) {}
+}
+
+#[tokio::test]
+async fn spans() {
+    let spans = Spans::default();
+    let _guard = tracing::subscriber::set_default(spans.clone());
+
+    let store = Store::<_, Todo>::new(SqlitePool::connect(":memory:").await.unwrap());
+    store.recreate_table().await.unwrap();
+    let todo = store.create(Todo::new("checkout miniorm")).await.unwrap();
+    store.read(todo.id()).await.unwrap();
+    store.read(42).await.unwrap_err();
+    store.list().await.unwrap();
+    store.update(todo.clone()).await.unwrap();
+    let missing = WithId::new(Todo::new("buy milk"), 42);
+    store.update(missing).await.unwrap();
+
+    let spans = spans.take();
+    let names = spans.iter().map(|span| span["otel.name"].as_str());
+    assert_eq!(
+        names.collect::<Vec<_>>(),
+        [
+            "DROP TABLE todo",
+            "CREATE TABLE todo",
+            "INSERT todo",
+            "SELECT todo",
+            "SELECT todo",
+            "SELECT todo",
+            "UPDATE todo",
+            "UPDATE todo",
+        ]
+    );
+    assert!(spans.iter().all(|span| span["db.system"] == "sqlite"));
+    assert!(spans
+        .iter()
+        .all(|span| span["db.collection.name"] == "todo"));
+    assert!(spans.iter().all(|span| span["otel.kind"] == "client"));
+    assert!(spans
+        .iter()
+        .all(|span| span.contains_key("miniorm.duration_ms")));
+
+    let read = &spans[3];
+    assert_eq!(read["miniorm.operation"], "read");
+    assert_eq!(read["miniorm.id"], "1");
+    assert_eq!(read["miniorm.rows_affected"], "1");
+    assert!(!read.contains_key("error.type"));
+
+    let not_found = &spans[4];
+    assert_eq!(not_found["miniorm.id"], "42");
+    assert_eq!(not_found["otel.status_code"], "ERROR");
+    assert_eq!(not_found["error.type"], "row_not_found");
+    assert!(!not_found.contains_key("miniorm.rows_affected"));
+
+    let list = &spans[5];
+    assert_eq!(list["miniorm.operation"], "list");
+    assert!(!list.contains_key("miniorm.id"));
+
+    let (update, missing) = (&spans[6], &spans[7]);
+    assert_eq!(update["miniorm.rows_affected"], "1");
+    assert_eq!(missing["miniorm.id"], "42");
+    assert_eq!(missing["miniorm.rows_affected"], "0");
+}
+
+#[tokio::test]
+async fn stream_span() {
+    use futures::StreamExt;
+
+    let spans = Spans::default();
+    let _guard = tracing::subscriber::set_default(spans.clone());
+
+    let store = Store::<_, Todo>::new(SqlitePool::connect(":memory:").await.unwrap());
+    store.recreate_table().await.unwrap();
+    store.create(Todo::new("checkout miniorm")).await.unwrap();
+    store.create(Todo::new("buy milk")).await.unwrap();
+
+    let mut stream = store.stream();
+    stream.next().await.unwrap().unwrap();
+    let last = |spans: &Spans| spans.0.lock().unwrap().last().unwrap().clone();
+    assert!(!last(&spans).contains_key("miniorm.rows_affected"));
+    assert_eq!(stream.count().await, 1);
+
+    let stream = last(&spans);
+    assert_eq!(stream["otel.name"], "SELECT todo");
+    assert_eq!(stream["miniorm.operation"], "stream");
+    assert_eq!(stream["miniorm.rows_affected"], "2");
+    assert!(stream.contains_key("miniorm.duration_ms"));
+}
+
+#[tokio::test]
+async fn tenant_spans() {
+    let spans = Spans::default();
+    let _guard = tracing::subscriber::set_default(spans.clone());
+
+    let store = Store::<_, Todo>::new(SqlitePool::connect(":memory:").await.unwrap());
+    let acme = store.for_tenant(1);
+    acme.create_table().await.unwrap();
+    let todo = acme.create(Todo::new("checkout miniorm")).await.unwrap();
+    acme.for_tenant(2).read(todo.id()).await.unwrap_err();
+
+    let spans = spans.take();
+    let operations = spans.iter().map(|span| span["miniorm.operation"].as_str());
+    assert_eq!(
+        operations.collect::<Vec<_>>(),
+        ["create_table", "create", "read"]
+    );
+    assert_eq!(spans[2]["error.type"], "row_not_found");
+}